@@ -0,0 +1,16 @@
+-- Exclusão lógica de registros financeiros
+ALTER TABLE sales ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE expenses ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+
+-- Excluir um produto não pode mais apagar o histórico de vendas
+ALTER TABLE sales DROP CONSTRAINT IF EXISTS sales_product_id_fkey;
+ALTER TABLE sales
+    ADD CONSTRAINT sales_product_id_fkey
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE RESTRICT;
+
+-- Restaurações também entram na auditoria
+ALTER TABLE audit_log DROP CONSTRAINT IF EXISTS audit_log_action_check;
+ALTER TABLE audit_log
+    ADD CONSTRAINT audit_log_action_check
+    CHECK (action IN ('create', 'update', 'delete', 'restore'));
@@ -0,0 +1,15 @@
+-- Respostas guardadas por Idempotency-Key (retentativas de POST)
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY,
+    request_hash TEXT NOT NULL,
+    -- NULL enquanto a requisição original ainda está em processamento
+    status_code SMALLINT,
+    content_type TEXT,
+    etag TEXT,
+    response_body BYTEA,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at
+    ON idempotency_keys (expires_at);
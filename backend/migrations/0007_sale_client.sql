@@ -0,0 +1,4 @@
+-- Cliente (opcional) da venda, impresso no recibo
+ALTER TABLE sales ADD COLUMN IF NOT EXISTS client_id UUID REFERENCES clients(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_sales_client_id ON sales (client_id);
@@ -0,0 +1,37 @@
+-- Contas bancárias e linhas de extrato (OFX/CSV) para conciliação
+CREATE TABLE IF NOT EXISTS accounts (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    bank_code TEXT,
+    branch TEXT,
+    number TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS statement_lines (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    -- Identificador da transação no banco; evita importar a mesma linha duas vezes
+    fitid TEXT NOT NULL,
+    posted_on DATE NOT NULL,
+    -- Negativo para débitos, positivo para créditos
+    amount DOUBLE PRECISION NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'unmatched'
+        CHECK (status IN ('unmatched', 'auto', 'manual', 'ignored')),
+    payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    sale_id UUID REFERENCES sales(id) ON DELETE SET NULL,
+    imported_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    version INTEGER NOT NULL DEFAULT 1,
+    UNIQUE (account_id, fitid),
+    CHECK (payment_id IS NULL OR sale_id IS NULL)
+);
+
+-- Cada pagamento/venda só pode ser conciliado com uma linha de extrato
+CREATE UNIQUE INDEX IF NOT EXISTS idx_statement_lines_payment_id
+    ON statement_lines (payment_id) WHERE payment_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_statement_lines_sale_id
+    ON statement_lines (sale_id) WHERE sale_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_statement_lines_account_status
+    ON statement_lines (account_id, status, posted_on);
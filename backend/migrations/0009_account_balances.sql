@@ -0,0 +1,37 @@
+-- Contas financeiras (banco, poupança, caixa, adquirente de cartão) com saldo
+-- inicial; pagamentos e recebimentos (vendas) passam a informar a conta.
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'checking'
+    CHECK (kind IN ('checking', 'savings', 'cash', 'card_acquirer'));
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS opening_balance DOUBLE PRECISION NOT NULL DEFAULT 0;
+
+-- Conta padrão para os lançamentos anteriores a esta migração
+INSERT INTO accounts (id, name, kind)
+VALUES ('00000000-0000-0000-0000-000000000001', 'Caixa', 'cash')
+ON CONFLICT (id) DO NOTHING;
+
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS account_id UUID REFERENCES accounts(id);
+UPDATE payments SET account_id = '00000000-0000-0000-0000-000000000001' WHERE account_id IS NULL;
+ALTER TABLE payments ALTER COLUMN account_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_payments_account_id ON payments (account_id);
+
+ALTER TABLE sales ADD COLUMN IF NOT EXISTS account_id UUID REFERENCES accounts(id);
+UPDATE sales SET account_id = '00000000-0000-0000-0000-000000000001' WHERE account_id IS NULL;
+ALTER TABLE sales ALTER COLUMN account_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_sales_account_id ON sales (account_id);
+
+-- Transferências entre contas (ex.: depósito do caixa no banco)
+CREATE TABLE IF NOT EXISTS transfers (
+    id UUID PRIMARY KEY,
+    from_account_id UUID NOT NULL REFERENCES accounts(id),
+    to_account_id UUID NOT NULL REFERENCES accounts(id),
+    amount DOUBLE PRECISION NOT NULL CHECK (amount > 0),
+    transferred_on DATE NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMP,
+    version INTEGER NOT NULL DEFAULT 1,
+    CHECK (from_account_id <> to_account_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfers_from_account ON transfers (from_account_id, transferred_on);
+CREATE INDEX IF NOT EXISTS idx_transfers_to_account ON transfers (to_account_id, transferred_on);
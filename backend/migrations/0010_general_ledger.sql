@@ -0,0 +1,74 @@
+-- Contabilidade em partidas dobradas: plano de contas e lançamentos
+CREATE TABLE IF NOT EXISTS ledger_accounts (
+    id UUID PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('asset', 'liability', 'equity', 'revenue', 'expense')),
+    -- Conta financeira (caixa/banco) representada por esta conta contábil
+    financial_account_id UUID UNIQUE REFERENCES accounts(id),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Plano de contas básico; as contas de caixa e bancos (1.1.01.xx) são
+-- criadas junto com as contas financeiras.
+INSERT INTO ledger_accounts (id, code, name, kind) VALUES
+    ('10000000-0000-0000-0000-000000000102', '1.1.02', 'Estoque de mercadorias', 'asset'),
+    ('10000000-0000-0000-0000-000000000201', '2.1.01', 'Fornecedores a pagar', 'liability'),
+    ('10000000-0000-0000-0000-000000000301', '3.1.01', 'Capital e saldos iniciais', 'equity'),
+    ('10000000-0000-0000-0000-000000000302', '3.2.01', 'Ajustes de estoque', 'equity'),
+    ('10000000-0000-0000-0000-000000000401', '4.1.01', 'Receita de vendas', 'revenue'),
+    ('10000000-0000-0000-0000-000000000501', '5.1.01', 'Despesas operacionais', 'expense')
+ON CONFLICT (code) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS journal_entries (
+    id UUID PRIMARY KEY,
+    entry_date DATE NOT NULL,
+    description TEXT NOT NULL,
+    -- Operação que gerou o lançamento (sale, expense, payment, transfer,
+    -- account, stock) ou manual
+    source TEXT NOT NULL,
+    source_id UUID,
+    -- Estorno: aponta o lançamento estornado; reversed_by marca o original
+    reverses UUID REFERENCES journal_entries(id),
+    reversed_by UUID REFERENCES journal_entries(id),
+    user_id TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_journal_entries_source ON journal_entries (source, source_id);
+CREATE INDEX IF NOT EXISTS idx_journal_entries_date ON journal_entries (entry_date);
+
+CREATE TABLE IF NOT EXISTS journal_lines (
+    id BIGSERIAL PRIMARY KEY,
+    entry_id UUID NOT NULL REFERENCES journal_entries(id) ON DELETE CASCADE,
+    ledger_account_id UUID NOT NULL REFERENCES ledger_accounts(id),
+    debit DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (debit >= 0),
+    credit DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (credit >= 0),
+    CHECK ((debit > 0) <> (credit > 0))
+);
+
+CREATE INDEX IF NOT EXISTS idx_journal_lines_entry ON journal_lines (entry_id);
+CREATE INDEX IF NOT EXISTS idx_journal_lines_account ON journal_lines (ledger_account_id);
+
+-- Débitos = créditos em cada lançamento, conferido no commit da transação
+CREATE OR REPLACE FUNCTION check_journal_entry_balanced() RETURNS TRIGGER AS $$
+DECLARE
+    target UUID := COALESCE(NEW.entry_id, OLD.entry_id);
+    difference DOUBLE PRECISION;
+BEGIN
+    SELECT COALESCE(SUM(debit), 0) - COALESCE(SUM(credit), 0) INTO difference
+    FROM journal_lines WHERE entry_id = target;
+
+    IF abs(difference) > 0.005 THEN
+        RAISE EXCEPTION 'Lançamento % desbalanceado (diferença %)', target, difference
+            USING ERRCODE = 'check_violation';
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS journal_lines_balanced ON journal_lines;
+CREATE CONSTRAINT TRIGGER journal_lines_balanced
+    AFTER INSERT OR UPDATE OR DELETE ON journal_lines
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION check_journal_entry_balanced();
@@ -0,0 +1,22 @@
+-- Fechamento mensal: meses fechados não aceitam lançamentos nem alterações
+CREATE TABLE IF NOT EXISTS fiscal_periods (
+    id UUID PRIMARY KEY,
+    -- Primeiro dia do mês
+    month DATE NOT NULL UNIQUE CHECK (EXTRACT(DAY FROM month) = 1),
+    status TEXT NOT NULL CHECK (status IN ('closed', 'open')),
+    closed_at TIMESTAMP,
+    closed_by TEXT,
+    reopened_at TIMESTAMP,
+    reopened_by TEXT,
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+-- Saldos acumulados de cada conta contábil no último dia do mês fechado;
+-- o balancete parte do fechamento mais recente em vez de somar tudo.
+CREATE TABLE IF NOT EXISTS period_balances (
+    period_id UUID NOT NULL REFERENCES fiscal_periods(id) ON DELETE CASCADE,
+    ledger_account_id UUID NOT NULL REFERENCES ledger_accounts(id),
+    debit DOUBLE PRECISION NOT NULL,
+    credit DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (period_id, ledger_account_id)
+);
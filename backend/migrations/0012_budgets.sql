@@ -0,0 +1,20 @@
+-- Orçamento mensal: teto de gastos por categoria de despesa e meta de vendas
+ALTER TABLE expenses ADD COLUMN IF NOT EXISTS category TEXT NOT NULL DEFAULT 'Sem categoria';
+
+CREATE INDEX IF NOT EXISTS idx_expenses_category ON expenses (category);
+
+CREATE TABLE IF NOT EXISTS budgets (
+    id UUID PRIMARY KEY,
+    year INTEGER NOT NULL CHECK (year BETWEEN 2000 AND 2100),
+    month INTEGER NOT NULL CHECK (month BETWEEN 1 AND 12),
+    -- expense: teto da categoria; revenue: meta de vendas do mês (sem categoria)
+    kind TEXT NOT NULL CHECK (kind IN ('expense', 'revenue')),
+    category TEXT,
+    amount DOUBLE PRECISION NOT NULL CHECK (amount >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    version INTEGER NOT NULL DEFAULT 1,
+    CHECK ((kind = 'revenue') = (category IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_budgets_unique
+    ON budgets (year, month, kind, COALESCE(category, ''));
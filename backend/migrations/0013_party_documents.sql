@@ -0,0 +1,9 @@
+-- CPF/CNPJ (só dígitos) de clientes e fornecedores, usado na detecção de duplicados
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS document TEXT;
+ALTER TABLE suppliers ADD COLUMN IF NOT EXISTS document TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_clients_document ON clients (document);
+CREATE INDEX IF NOT EXISTS idx_suppliers_document ON suppliers (document);
+
+-- O modelo de fornecedor sempre leu created_at, que faltava na tabela
+ALTER TABLE suppliers ADD COLUMN IF NOT EXISTS created_at TIMESTAMP DEFAULT NOW();
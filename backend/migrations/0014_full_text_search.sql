@@ -0,0 +1,34 @@
+-- Busca textual: português com radicais e sem acentos
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'pt_unaccent') THEN
+        CREATE TEXT SEARCH CONFIGURATION pt_unaccent (COPY = portuguese);
+        ALTER TEXT SEARCH CONFIGURATION pt_unaccent
+            ALTER MAPPING FOR hword, hword_part, word WITH unaccent, portuguese_stem;
+    END IF;
+END
+$$;
+
+-- Peso A no nome/descrição principal, B nos campos secundários
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('pt_unaccent', name), 'A') || setweight(to_tsvector('pt_unaccent', email), 'B')
+) STORED;
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('pt_unaccent', name), 'A') || setweight(to_tsvector('pt_unaccent', COALESCE(description, '')), 'B')
+) STORED;
+
+ALTER TABLE suppliers ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('pt_unaccent', name), 'A') || setweight(to_tsvector('pt_unaccent', COALESCE(email, '')), 'B')
+) STORED;
+
+ALTER TABLE expenses ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('pt_unaccent', description), 'A') || setweight(to_tsvector('pt_unaccent', category), 'B')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_clients_search ON clients USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_products_search ON products USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_suppliers_search ON suppliers USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_expenses_search ON expenses USING GIN (search_vector);
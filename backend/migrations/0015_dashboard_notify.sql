@@ -0,0 +1,27 @@
+-- Avisa o painel ao vivo (canal dashboard_events) de mudanças em vendas,
+-- despesas e pagamentos. Por comando, não por linha: dentro da mesma
+-- transação o Postgres junta avisos iguais, então uma importação gera um só.
+CREATE OR REPLACE FUNCTION notify_dashboard() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify(
+        'dashboard_events',
+        json_build_object('entity', TG_TABLE_NAME, 'action', lower(TG_OP))::text
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS sales_notify_dashboard ON sales;
+CREATE TRIGGER sales_notify_dashboard
+    AFTER INSERT OR UPDATE OR DELETE ON sales
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_dashboard();
+
+DROP TRIGGER IF EXISTS expenses_notify_dashboard ON expenses;
+CREATE TRIGGER expenses_notify_dashboard
+    AFTER INSERT OR UPDATE OR DELETE ON expenses
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_dashboard();
+
+DROP TRIGGER IF EXISTS payments_notify_dashboard ON payments;
+CREATE TRIGGER payments_notify_dashboard
+    AFTER INSERT OR UPDATE OR DELETE ON payments
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_dashboard();
@@ -0,0 +1,11 @@
+-- O resumo do painel também conta clientes e mostra nomes de produtos:
+-- mudanças nessas tabelas avisam o mesmo canal para invalidar o cache.
+DROP TRIGGER IF EXISTS clients_notify_dashboard ON clients;
+CREATE TRIGGER clients_notify_dashboard
+    AFTER INSERT OR UPDATE OR DELETE ON clients
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_dashboard();
+
+DROP TRIGGER IF EXISTS products_notify_dashboard ON products;
+CREATE TRIGGER products_notify_dashboard
+    AFTER INSERT OR UPDATE OR DELETE ON products
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_dashboard();
@@ -0,0 +1,5 @@
+-- Custo unitário do produto, opcional, para a margem na curva ABC
+ALTER TABLE products ADD COLUMN IF NOT EXISTS cost DOUBLE PRECISION;
+
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_cost_check;
+ALTER TABLE products ADD CONSTRAINT products_cost_check CHECK (cost IS NULL OR cost >= 0);
@@ -0,0 +1,10 @@
+-- Idempotency-Key passa a valer por usuário e rota: a mesma chave enviada por
+-- outra pessoa ou para outro endpoint é uma requisição independente
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS user_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS route TEXT NOT NULL DEFAULT '';
+
+-- Chaves antigas não têm escopo; expiram em 24h de qualquer forma
+DELETE FROM idempotency_keys;
+
+ALTER TABLE idempotency_keys DROP CONSTRAINT IF EXISTS idempotency_keys_pkey;
+ALTER TABLE idempotency_keys ADD PRIMARY KEY (user_id, route, key);
@@ -0,0 +1,4 @@
+-- Custo das mercadorias vendidas, lançado junto com cada venda
+INSERT INTO ledger_accounts (id, code, name, kind) VALUES
+    ('10000000-0000-0000-0000-000000000502', '5.1.02', 'Custo das mercadorias vendidas', 'expense')
+ON CONFLICT (code) DO NOTHING;
@@ -0,0 +1,20 @@
+-- Busca de cadastros parecidos na criação de clientes e fornecedores: os
+-- candidatos são filtrados no banco pelos mesmos dados normalizados que
+-- src/duplicates.rs compara, e só eles são pontuados na aplicação
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- E-mail em minúsculas e sem o sufixo `+alguma-coisa`
+CREATE INDEX IF NOT EXISTS idx_clients_email_normalized
+    ON clients ((regexp_replace(lower(btrim(email)), '\+[^@]*@', '@')));
+CREATE INDEX IF NOT EXISTS idx_suppliers_email_normalized
+    ON suppliers ((regexp_replace(lower(btrim(email)), '\+[^@]*@', '@')));
+
+-- Últimos 8 dígitos do telefone
+CREATE INDEX IF NOT EXISTS idx_clients_phone_digits
+    ON clients ((right(regexp_replace(phone, '\D', '', 'g'), 8)));
+CREATE INDEX IF NOT EXISTS idx_suppliers_phone_digits
+    ON suppliers ((right(regexp_replace(phone, '\D', '', 'g'), 8)));
+
+-- Nomes parecidos por trigramas
+CREATE INDEX IF NOT EXISTS idx_clients_name_trgm ON clients USING gin (lower(name) gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_suppliers_name_trgm ON suppliers USING gin (lower(name) gin_trgm_ops);
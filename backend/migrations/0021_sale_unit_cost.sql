@@ -0,0 +1,6 @@
+-- Custo unitário do produto no momento da venda, base do custo das
+-- mercadorias vendidas. Vazio quando o produto não tinha custo cadastrado:
+-- a venda então não gera CMV nem baixa de estoque no razão.
+ALTER TABLE sales ADD COLUMN unit_cost DOUBLE PRECISION CHECK (unit_cost IS NULL OR unit_cost >= 0);
+
+UPDATE sales s SET unit_cost = p.cost FROM products p WHERE p.id = s.product_id;
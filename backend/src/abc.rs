@@ -0,0 +1,139 @@
+// Curva ABC (Pareto)
+// src/abc.rs
+//
+// Os itens são ordenados pelo valor, do maior para o menor, e recebem a
+// classe pela participação acumulada antes deles: enquanto ela está abaixo
+// de `a` o item é A, abaixo de `b` é B, e o restante é C. Assim o item que
+// cruza o limite ainda fica na classe mais alta e o primeiro é sempre A.
+// Itens sem valor positivo (sem vendas ou com margem negativa) não entram no
+// total e ficam em C.
+use std::collections::BTreeMap;
+
+use crate::models::abc::{AbcClass, AbcItem, AbcRow, ClassSummary};
+
+pub fn classify(
+    mut rows: Vec<AbcRow>,
+    value: impl Fn(&AbcRow) -> f64,
+    a: f64,
+    b: f64,
+) -> (Vec<AbcItem>, f64, BTreeMap<AbcClass, ClassSummary>) {
+    rows.sort_by(|x, y| value(y).total_cmp(&value(x)).then_with(|| x.name.cmp(&y.name)));
+    let total = rows.iter().map(&value).filter(|v| *v > 0.0).fold(0.0, |sum, v| sum + v);
+    let percent = |v: f64| if total > 0.0 { round(v / total * 100.0) } else { 0.0 };
+
+    let mut classes: BTreeMap<AbcClass, ClassSummary> =
+        [AbcClass::A, AbcClass::B, AbcClass::C].into_iter().map(|c| (c, ClassSummary::default())).collect();
+    let mut accumulated = 0.0;
+    let mut items = Vec::with_capacity(rows.len());
+
+    for (index, row) in rows.into_iter().enumerate() {
+        let item_value = value(&row);
+        let before = if total > 0.0 { accumulated / total * 100.0 } else { 100.0 };
+        let class = if item_value <= 0.0 || before >= b {
+            AbcClass::C
+        } else if before < a {
+            AbcClass::A
+        } else {
+            AbcClass::B
+        };
+        if item_value > 0.0 {
+            accumulated += item_value;
+        }
+
+        // Valores negativos não descontam do total da classe C
+        let summary = classes.entry(class).or_default();
+        summary.items += 1;
+        summary.value += item_value.max(0.0);
+
+        items.push(AbcItem {
+            rank: index + 1,
+            value: round(item_value),
+            share: percent(item_value.max(0.0)),
+            cumulative_share: percent(accumulated),
+            class,
+            row,
+        });
+    }
+
+    for summary in classes.values_mut() {
+        summary.value = round(summary.value);
+        summary.share = percent(summary.value);
+    }
+    (items, round(total), classes)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn row(name: &str, revenue: f64) -> AbcRow {
+        AbcRow {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            revenue,
+            quantity: 1,
+            sales_count: 1,
+            margin: None,
+        }
+    }
+
+    fn classes_of(items: &[AbcItem]) -> Vec<(&str, AbcClass)> {
+        items.iter().map(|item| (item.row.name.as_str(), item.class)).collect()
+    }
+
+    #[test]
+    fn cut_offs_use_the_share_before_the_item() {
+        let rows = vec![row("c", 10.0), row("a", 50.0), row("e", 4.0), row("b", 30.0), row("d", 6.0)];
+        let (items, total, classes) = classify(rows, |r| r.revenue, 80.0, 95.0);
+
+        assert_eq!(total, 100.0);
+        // "b" começa exatamente em 50% e "c" exatamente em 80%: o limite é exclusivo
+        assert_eq!(
+            classes_of(&items),
+            vec![("a", AbcClass::A), ("b", AbcClass::A), ("c", AbcClass::B), ("d", AbcClass::B), ("e", AbcClass::C)]
+        );
+        assert_eq!(items.iter().map(|i| i.cumulative_share).collect::<Vec<_>>(), vec![50.0, 80.0, 90.0, 96.0, 100.0]);
+        assert_eq!(items.iter().map(|i| i.rank).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!((classes[&AbcClass::A].items, classes[&AbcClass::A].share), (2, 80.0));
+        assert_eq!((classes[&AbcClass::B].items, classes[&AbcClass::B].share), (2, 16.0));
+        assert_eq!((classes[&AbcClass::C].items, classes[&AbcClass::C].share), (1, 4.0));
+    }
+
+    #[test]
+    fn ties_are_ordered_by_name() {
+        let rows = vec![row("Beta", 40.0), row("Alfa", 40.0), row("Gama", 20.0)];
+        let (items, _, _) = classify(rows, |r| r.revenue, 80.0, 95.0);
+        // Alfa e Beta empatam; a ordem (e portanto a classe) não depende da entrada
+        assert_eq!(classes_of(&items), vec![("Alfa", AbcClass::A), ("Beta", AbcClass::A), ("Gama", AbcClass::B)]);
+    }
+
+    #[test]
+    fn non_positive_values_go_to_c_without_changing_totals() {
+        let rows = vec![row("lucro", 90.0), row("pouco", 10.0), row("zero", 0.0), row("prejuizo", -20.0)];
+        let (items, total, classes) = classify(rows, |r| r.revenue, 80.0, 95.0);
+
+        assert_eq!(total, 100.0);
+        assert_eq!(
+            classes_of(&items),
+            vec![("lucro", AbcClass::A), ("pouco", AbcClass::B), ("zero", AbcClass::C), ("prejuizo", AbcClass::C)]
+        );
+        assert_eq!(items[3].value, -20.0);
+        assert_eq!(items[3].share, 0.0);
+        assert_eq!(items[3].cumulative_share, 100.0);
+        assert_eq!((classes[&AbcClass::C].items, classes[&AbcClass::C].value, classes[&AbcClass::C].share), (2, 0.0, 0.0));
+        let shares: f64 = classes.values().map(|c| c.share).sum();
+        assert_eq!(shares, 100.0);
+    }
+
+    #[test]
+    fn without_positive_total_everything_is_c() {
+        let (items, total, _) = classify(vec![row("a", 0.0), row("b", -5.0)], |r| r.revenue, 80.0, 95.0);
+        assert_eq!(total, 0.0);
+        assert!(items.iter().all(|item| item.class == AbcClass::C && item.share == 0.0));
+    }
+}
@@ -0,0 +1,356 @@
+// Detecção de anomalias nas despesas
+// src/anomalies.rs
+//
+// Três verificações sobre o histórico: valor muito acima do habitual do
+// fornecedor (ou da categoria, sem fornecedor), despesas duplicadas com o
+// mesmo fornecedor e valor em vencimentos próximos, e crescimento atípico da
+// categoria de um mês para o seguinte. O habitual é a mediana das demais
+// despesas do grupo, com a dispersão medida pelo desvio absoluto mediano,
+// para que a própria anomalia não distorça a referência.
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::{
+    format::{brl, date_br},
+    models::insight::{Anomaly, AnomalyKind, ExpenseRecord, Severity},
+};
+
+// Outras despesas do grupo necessárias para estabelecer o habitual
+const MIN_HISTORY: usize = 3;
+// Quantas vezes acima da mediana e quantos desvios robustos para sinalizar
+const MIN_RATIO: f64 = 2.0;
+const MIN_SCORE: f64 = 3.5;
+// Centavos de diferença ainda tratados como o mesmo valor
+const SAME_AMOUNT: f64 = 0.005;
+
+pub fn outliers(expenses: &[ExpenseRecord]) -> Vec<Anomaly> {
+    let mut groups: HashMap<(Option<Uuid>, &str), Vec<&ExpenseRecord>> = HashMap::new();
+    for expense in expenses {
+        let key = match expense.supplier_id {
+            Some(supplier) => (Some(supplier), ""),
+            None => (None, expense.category.as_str()),
+        };
+        groups.entry(key).or_default().push(expense);
+    }
+
+    let mut found = Vec::new();
+    for group in groups.values().filter(|group| group.len() > MIN_HISTORY) {
+        for expense in group {
+            let others: Vec<f64> = group.iter().filter(|e| e.id != expense.id).map(|e| e.amount).collect();
+            let usual = median(&others);
+            if usual <= 0.0 {
+                continue;
+            }
+            let deviations: Vec<f64> = others.iter().map(|amount| (amount - usual).abs()).collect();
+            let spread = 1.4826 * median(&deviations);
+
+            let ratio = expense.amount / usual;
+            // Valores sempre iguais: qualquer salto acima do dobro conta
+            let score = if spread > 0.0 { (expense.amount - usual) / spread } else { f64::INFINITY };
+            if ratio < MIN_RATIO || score < MIN_SCORE {
+                continue;
+            }
+
+            let severity = if ratio >= 5.0 {
+                Severity::High
+            } else if ratio >= 3.0 {
+                Severity::Medium
+            } else {
+                Severity::Low
+            };
+            let reference = match &expense.supplier_name {
+                Some(name) => format!("do fornecedor {}", name),
+                None => format!("da categoria {}", expense.category),
+            };
+
+            found.push(Anomaly {
+                kind: AnomalyKind::Outlier,
+                severity,
+                reason: format!(
+                    "{} de {} é {}x o valor habitual {} ({})",
+                    expense.description,
+                    brl(expense.amount),
+                    format!("{:.1}", ratio).replace('.', ","),
+                    reference,
+                    brl(usual)
+                ),
+                expense_ids: vec![expense.id],
+                supplier_id: expense.supplier_id,
+                category: Some(expense.category.clone()),
+                month: None,
+                amount: expense.amount,
+                expected: Some(usual),
+            });
+        }
+    }
+    found
+}
+
+pub fn duplicates(expenses: &[ExpenseRecord], max_days: i64) -> Vec<Anomaly> {
+    let mut by_supplier: HashMap<Uuid, Vec<&ExpenseRecord>> = HashMap::new();
+    for expense in expenses {
+        if let Some(supplier) = expense.supplier_id {
+            by_supplier.entry(supplier).or_default().push(expense);
+        }
+    }
+
+    let mut found = Vec::new();
+    for group in by_supplier.values_mut() {
+        group.sort_by_key(|e| e.due_date);
+        for (i, first) in group.iter().enumerate() {
+            for second in &group[i + 1..] {
+                let days = (second.due_date - first.due_date).num_days();
+                if days > max_days {
+                    break;
+                }
+                if (first.amount - second.amount).abs() >= SAME_AMOUNT {
+                    continue;
+                }
+
+                let same_description = first.description.trim().eq_ignore_ascii_case(second.description.trim());
+                let severity = match (days, same_description) {
+                    (0, true) => Severity::High,
+                    (0, false) | (_, true) => Severity::Medium,
+                    _ => Severity::Low,
+                };
+                let when = match days {
+                    0 => format!("no mesmo vencimento ({})", date_br(first.due_date)),
+                    1 => "com vencimentos a 1 dia de distância".to_string(),
+                    _ => format!("com vencimentos a {} dias de distância", days),
+                };
+
+                found.push(Anomaly {
+                    kind: AnomalyKind::Duplicate,
+                    severity,
+                    reason: format!(
+                        "Possível duplicidade: duas despesas de {} do fornecedor {} {}",
+                        brl(first.amount),
+                        first.supplier_name.as_deref().unwrap_or("-"),
+                        when
+                    ),
+                    expense_ids: vec![first.id, second.id],
+                    supplier_id: first.supplier_id,
+                    category: Some(first.category.clone()),
+                    month: None,
+                    amount: first.amount,
+                    expected: None,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Compara cada mês da categoria com o anterior; um mês sem despesa não serve
+/// de base, já que crescimento a partir de zero não tem percentual.
+pub fn growth(expenses: &[ExpenseRecord], threshold: f64) -> Vec<Anomaly> {
+    let mut monthly: BTreeMap<&str, BTreeMap<NaiveDate, (f64, Vec<Uuid>)>> = BTreeMap::new();
+    for expense in expenses {
+        let month = expense.due_date.with_day(1).unwrap_or(expense.due_date);
+        let total = monthly.entry(expense.category.as_str()).or_default().entry(month).or_default();
+        total.0 += expense.amount;
+        total.1.push(expense.id);
+    }
+
+    let mut found = Vec::new();
+    for (category, months) in monthly {
+        for (month, (amount, ids)) in &months {
+            let previous_month = match month.checked_sub_months(chrono::Months::new(1)) {
+                Some(previous) => previous,
+                None => continue,
+            };
+            let previous = match months.get(&previous_month) {
+                Some((previous, _)) if *previous > 0.0 => *previous,
+                _ => continue,
+            };
+
+            let percentage = (amount - previous) / previous * 100.0;
+            if percentage <= threshold {
+                continue;
+            }
+            let severity = if percentage >= 3.0 * threshold {
+                Severity::High
+            } else if percentage >= 2.0 * threshold {
+                Severity::Medium
+            } else {
+                Severity::Low
+            };
+
+            found.push(Anomaly {
+                kind: AnomalyKind::Growth,
+                severity,
+                reason: format!(
+                    "Despesas de {} cresceram {:.0}% em {} ({} contra {} no mês anterior)",
+                    category,
+                    percentage,
+                    month.format("%m/%Y"),
+                    brl(*amount),
+                    brl(previous)
+                ),
+                expense_ids: ids.clone(),
+                supplier_id: None,
+                category: Some(category.to_string()),
+                month: Some(*month),
+                amount: *amount,
+                expected: Some(previous),
+            });
+        }
+    }
+    found
+}
+
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn expense(supplier: Option<Uuid>, category: &str, amount: f64, due_date: NaiveDate) -> ExpenseRecord {
+        ExpenseRecord {
+            id: Uuid::new_v4(),
+            description: "Energia".to_string(),
+            category: category.to_string(),
+            supplier_id: supplier,
+            supplier_name: supplier.map(|_| "Luz S.A.".to_string()),
+            amount,
+            due_date,
+        }
+    }
+
+    #[test]
+    fn median_of_odd_even_and_empty() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(&[]), 0.0);
+    }
+
+    #[test]
+    fn outlier_against_supplier_median() {
+        let supplier = Some(Uuid::new_v4());
+        let mut expenses: Vec<_> = [100.0, 110.0, 90.0, 105.0]
+            .iter()
+            .map(|amount| expense(supplier, "Utilidades", *amount, date(1)))
+            .collect();
+        expenses.push(expense(supplier, "Utilidades", 600.0, date(2)));
+
+        let found = outliers(&expenses);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].expense_ids, vec![expenses[4].id]);
+        assert_eq!(found[0].expected, Some(102.5));
+        assert_eq!(found[0].severity, Severity::High);
+        assert!(found[0].reason.contains("R$ 600,00"));
+    }
+
+    #[test]
+    fn no_outlier_without_enough_history_or_within_spread() {
+        let supplier = Some(Uuid::new_v4());
+        let short: Vec<_> = [100.0, 100.0, 500.0]
+            .iter()
+            .map(|amount| expense(supplier, "Utilidades", *amount, date(1)))
+            .collect();
+        assert!(outliers(&short).is_empty());
+
+        // Dispersão alta: o dobro da mediana ainda é habitual
+        let spread: Vec<_> = [50.0, 150.0, 60.0, 140.0, 210.0]
+            .iter()
+            .map(|amount| expense(supplier, "Utilidades", *amount, date(1)))
+            .collect();
+        assert!(outliers(&spread).is_empty());
+    }
+
+    #[test]
+    fn outliers_group_by_category_without_supplier() {
+        let mut expenses: Vec<_> = [40.0, 40.0, 40.0, 40.0]
+            .iter()
+            .map(|amount| expense(None, "Correios", *amount, date(1)))
+            .collect();
+        expenses.push(expense(None, "Correios", 100.0, date(3)));
+        // Outra categoria não entra na referência
+        expenses.push(expense(None, "Aluguel", 5000.0, date(5)));
+
+        let found = outliers(&expenses);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].severity, Severity::Low);
+        assert_eq!(found[0].category.as_deref(), Some("Correios"));
+    }
+
+    #[test]
+    fn duplicates_same_supplier_and_amount_within_window() {
+        let supplier = Some(Uuid::new_v4());
+        let expenses = vec![
+            expense(supplier, "Utilidades", 250.0, date(10)),
+            expense(supplier, "Utilidades", 250.0, date(10)),
+            expense(supplier, "Utilidades", 250.0, date(25)),
+            expense(supplier, "Utilidades", 251.0, date(11)),
+            expense(Some(Uuid::new_v4()), "Utilidades", 250.0, date(10)),
+        ];
+
+        let found = duplicates(&expenses, 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].severity, Severity::High);
+        assert!(found[0].expense_ids.contains(&expenses[0].id));
+        assert!(found[0].expense_ids.contains(&expenses[1].id));
+    }
+
+    #[test]
+    fn duplicates_a_few_days_apart_are_less_severe() {
+        let supplier = Some(Uuid::new_v4());
+        let mut second = expense(supplier, "Utilidades", 80.0, date(13));
+        second.description = "Outra descrição".to_string();
+        let expenses = vec![expense(supplier, "Utilidades", 80.0, date(10)), second];
+
+        let found = duplicates(&expenses, 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].severity, Severity::Low);
+        assert!(found[0].reason.contains("3 dias"));
+        assert!(duplicates(&expenses, 2).is_empty());
+    }
+
+    #[test]
+    fn growth_compares_with_previous_month() {
+        let february = NaiveDate::from_ymd_opt(2024, 2, 10).unwrap();
+        let expenses = vec![
+            expense(None, "Marketing", 100.0, february),
+            expense(None, "Marketing", 150.0, date(5)),
+            expense(None, "Marketing", 50.0, date(20)),
+            expense(None, "Aluguel", 1000.0, february),
+            expense(None, "Aluguel", 1100.0, date(5)),
+        ];
+
+        let found = growth(&expenses, 50.0);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].category.as_deref(), Some("Marketing"));
+        assert_eq!(found[0].month, Some(date(1)));
+        assert_eq!(found[0].amount, 200.0);
+        assert_eq!(found[0].expected, Some(100.0));
+        assert_eq!(found[0].severity, Severity::Medium);
+        assert_eq!(found[0].expense_ids.len(), 2);
+    }
+
+    #[test]
+    fn growth_needs_a_previous_month_with_spending() {
+        let january = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let expenses = vec![
+            expense(None, "Marketing", 100.0, january),
+            expense(None, "Marketing", 900.0, date(5)),
+        ];
+        assert!(growth(&expenses, 50.0).is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+// Trilha de auditoria das mutações financeiras
+// src/audit.rs
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{auth::AuthUser, request_id};
+
+/// Estado do registro antes/depois da mutação.
+pub enum Change<'a, T> {
+    Created(&'a T),
+    Updated(&'a T, &'a T),
+    Deleted(&'a T),
+    /// Exclusão lógica: o registro permanece com `deleted_at` preenchido.
+    SoftDeleted(&'a T, &'a T),
+    Restored(&'a T, &'a T),
+}
+
+/// Grava uma entrada na `audit_log`. Deve ser chamada dentro da mesma
+/// transação da mutação, para que ambas sejam confirmadas juntas.
+pub async fn record<T: Serialize>(
+    conn: &mut PgConnection,
+    user: Option<&AuthUser>,
+    entity: &str,
+    entity_id: Uuid,
+    change: Change<'_, T>,
+) -> Result<(), sqlx::Error> {
+    let (action, before, after) = match change {
+        Change::Created(after) => ("create", None, Some(to_json(after)?)),
+        Change::Updated(before, after) => ("update", Some(to_json(before)?), Some(to_json(after)?)),
+        Change::Deleted(before) => ("delete", Some(to_json(before)?), None),
+        Change::SoftDeleted(before, after) => ("delete", Some(to_json(before)?), Some(to_json(after)?)),
+        Change::Restored(before, after) => ("restore", Some(to_json(before)?), Some(to_json(after)?)),
+    };
+    let changes = diff(before.as_ref(), after.as_ref());
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (entity, entity_id, action, before, after, changes, user_id, request_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        entity,
+        entity_id,
+        action,
+        before,
+        after,
+        changes,
+        user.map(|u| u.id.as_str()),
+        request_id::current(),
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Value, sqlx::Error> {
+    serde_json::to_value(value).map_err(|err| sqlx::Error::Protocol(err.to_string()))
+}
+
+/// Campos alterados no formato `{ "campo": { "before": .., "after": .. } }`.
+fn diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys()) {
+        let old = before.get(key).unwrap_or(&Value::Null);
+        let new = after.get(key).unwrap_or(&Value::Null);
+        if old != new && !changes.contains_key(key) {
+            changes.insert(
+                key.clone(),
+                serde_json::json!({ "before": old, "after": new }),
+            );
+        }
+    }
+
+    Value::Object(changes)
+}
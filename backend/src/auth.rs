@@ -1,13 +1,102 @@
-use actix_web::{HttpRequest, Error};
-use actix_web::dev::ServiceRequest;
+use actix_web::{dev::Payload, http::{header, StatusCode}, web, Error, FromRequest, HttpRequest};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use serde::Deserialize;
+use std::future::{ready, Ready};
 
-pub async fn validate_token(req: ServiceRequest) -> Result<ServiceRequest, Error> {
-    // Exemplo simples de autenticação via header
-    if let Some(auth) = req.headers().get("Authorization") {
-        if auth == "Bearer token123" {
-            return Ok(req);
-        }
+use crate::{config::Config, errors::as_error};
+
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    #[serde(default)]
+    pub role: String,
+}
+
+/// Usuário autenticado via token JWT (Bearer) assinado com `auth.jwt_secret`.
+///
+/// Nas rotas que aceitam chamadas anônimas use `OptionalUser`.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: String,
+    pub role: String,
+}
+
+impl AuthUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
+}
+
+impl FromRequest for AuthUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticate(req))
+    }
+}
+
+/// Usuário opcional: `None` apenas quando não há cabeçalho `Authorization`.
+/// Um token presente mas inválido ou expirado responde 401, em vez de a
+/// chamada seguir como anônima (o que `Option<AuthUser>` faria).
+#[derive(Debug, Clone)]
+pub struct OptionalUser(pub Option<AuthUser>);
+
+impl FromRequest for OptionalUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(optional_user(req).map(OptionalUser))
+    }
+}
+
+/// Regra do `OptionalUser`, para quem precisa do usuário fora de um handler.
+pub fn optional_user(req: &HttpRequest) -> Result<Option<AuthUser>, Error> {
+    if !req.headers().contains_key(header::AUTHORIZATION) {
+        return Ok(None);
     }
+    authenticate(req).map(Some)
+}
+
+/// Usuário autenticado com papel `admin`.
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub AuthUser);
+
+impl FromRequest for AdminUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticate(req).and_then(|user| {
+            if user.is_admin() {
+                Ok(AdminUser(user))
+            } else {
+                Err(as_error(StatusCode::FORBIDDEN, "Forbidden"))
+            }
+        }))
+    }
+}
+
+fn authenticate(req: &HttpRequest) -> Result<AuthUser, Error> {
+    let config = req
+        .app_data::<web::Data<Config>>()
+        .ok_or_else(|| actix_web::error::ErrorInternalServerError("Config ausente"))?;
+
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| as_error(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let key = DecodingKey::from_secret(config.auth.jwt_secret.as_bytes());
+    let claims = decode::<Claims>(token, &key, &Validation::default())
+        .map_err(|_| as_error(StatusCode::UNAUTHORIZED, "Unauthorized"))?
+        .claims;
 
-    Err(actix_web::error::ErrorUnauthorized("Unauthorized"))
+    Ok(AuthUser {
+        id: claims.sub,
+        role: claims.role,
+    })
 }
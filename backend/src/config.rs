@@ -0,0 +1,345 @@
+// Configuração do servidor
+// src/config.rs
+//
+// Os valores são resolvidos nesta ordem (o último vence):
+//   1. padrões definidos aqui;
+//   2. arquivo TOML opcional (`CONFIG_FILE`, ou `config.toml` se existir);
+//   3. variáveis de ambiente (`SERVER_PORT`, `DATABASE_URL`, ...).
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::{env, fs, path::Path, str::FromStr};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub auth: AuthConfig,
+    pub log: LogConfig,
+    pub company: CompanyConfig,
+    pub ml_api: MlApiConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Número de workers do actix; `None` usa um por núcleo.
+    pub workers: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// Aplica as migrações pendentes ao iniciar; desligado, elas são aplicadas
+    /// à parte (`sqlx migrate run`) e o /ready acusa as que faltam
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origens permitidas; `"*"` libera qualquer origem.
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+/// Dados da empresa impressos no cabeçalho de recibos e relatórios em PDF.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompanyConfig {
+    pub name: String,
+    /// CNPJ ou CPF
+    pub document: Option<String>,
+    pub address: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Serviço Python de previsão (`ml_api`). Sem `url`, as previsões usam só a
+/// heurística local.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MlApiConfig {
+    pub url: Option<String>,
+    pub timeout_ms: u64,
+    /// Novas tentativas após a primeira, em falhas de rede ou respostas 5xx
+    pub retries: u32,
+    /// Falhas seguidas que abrem o circuito
+    pub failure_threshold: u32,
+    /// Tempo com o circuito aberto antes de testar o serviço de novo
+    pub open_secs: u64,
+    pub cache_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("formato de log desconhecido '{}' (use 'text' ou 'json')", other)),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8080,
+            workers: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: String::new(),
+            max_connections: 5,
+            acquire_timeout_secs: 30,
+            auto_migrate: false,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: vec!["http://localhost:3000".to_string()],
+        }
+    }
+}
+
+impl Default for CompanyConfig {
+    fn default() -> Self {
+        CompanyConfig {
+            name: "FinanceFlow".to_string(),
+            document: None,
+            address: None,
+            phone: None,
+            email: None,
+        }
+    }
+}
+
+impl Default for MlApiConfig {
+    fn default() -> Self {
+        MlApiConfig {
+            url: None,
+            timeout_ms: 2000,
+            retries: 2,
+            failure_threshold: 5,
+            open_secs: 30,
+            cache_ttl_secs: 300,
+        }
+    }
+}
+
+impl Config {
+    /// Carrega a configuração do arquivo TOML (se houver) e do ambiente e a valida.
+    pub fn load() -> anyhow::Result<Self> {
+        let mut config = match env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            Err(_) => Config::default(),
+        };
+
+        let mut errors = Vec::new();
+        config.apply_env(&mut errors);
+        errors.extend(config.validate());
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(anyhow!(
+                "configuração inválida:\n  - {}",
+                errors.join("\n  - ")
+            ))
+        }
+    }
+
+    fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("não foi possível ler {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("erro ao interpretar {}", path.display()))
+    }
+
+    fn apply_env(&mut self, errors: &mut Vec<String>) {
+        if let Some(host) = env_string("SERVER_HOST") {
+            self.server.host = host;
+        }
+        if let Some(port) = env_parse("SERVER_PORT", errors) {
+            self.server.port = port;
+        }
+        if let Some(workers) = env_parse("SERVER_WORKERS", errors) {
+            self.server.workers = Some(workers);
+        }
+        if let Some(url) = env_string("DATABASE_URL") {
+            self.database.url = url;
+        }
+        if let Some(max) = env_parse("DATABASE_MAX_CONNECTIONS", errors) {
+            self.database.max_connections = max;
+        }
+        if let Some(secs) = env_parse("DATABASE_ACQUIRE_TIMEOUT_SECS", errors) {
+            self.database.acquire_timeout_secs = secs;
+        }
+        if let Some(auto_migrate) = env_parse("DATABASE_AUTO_MIGRATE", errors) {
+            self.database.auto_migrate = auto_migrate;
+        }
+        if let Some(origins) = env_string("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(|o| o.trim().to_string())
+                .filter(|o| !o.is_empty())
+                .collect();
+        }
+        if let Some(secret) = env_string("JWT_SECRET") {
+            self.auth.jwt_secret = secret;
+        }
+        if let Some(format) = env_parse("LOG_FORMAT", errors) {
+            self.log.format = format;
+        }
+        if let Some(name) = env_string("COMPANY_NAME") {
+            self.company.name = name;
+        }
+        if let Some(document) = env_string("COMPANY_DOCUMENT") {
+            self.company.document = Some(document);
+        }
+        if let Some(address) = env_string("COMPANY_ADDRESS") {
+            self.company.address = Some(address);
+        }
+        if let Some(phone) = env_string("COMPANY_PHONE") {
+            self.company.phone = Some(phone);
+        }
+        if let Some(email) = env_string("COMPANY_EMAIL") {
+            self.company.email = Some(email);
+        }
+        if let Some(url) = env_string("ML_API_URL") {
+            self.ml_api.url = Some(url);
+        }
+        if let Some(timeout) = env_parse("ML_API_TIMEOUT_MS", errors) {
+            self.ml_api.timeout_ms = timeout;
+        }
+        if let Some(retries) = env_parse("ML_API_RETRIES", errors) {
+            self.ml_api.retries = retries;
+        }
+        if let Some(threshold) = env_parse("ML_API_FAILURE_THRESHOLD", errors) {
+            self.ml_api.failure_threshold = threshold;
+        }
+        if let Some(secs) = env_parse("ML_API_OPEN_SECS", errors) {
+            self.ml_api.open_secs = secs;
+        }
+        if let Some(secs) = env_parse("ML_API_CACHE_TTL_SECS", errors) {
+            self.ml_api.cache_ttl_secs = secs;
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.server.host.trim().is_empty() {
+            errors.push("server.host não pode ser vazio".to_string());
+        }
+        if self.server.port == 0 {
+            errors.push("server.port deve ser maior que zero".to_string());
+        }
+        if self.server.workers == Some(0) {
+            errors.push("server.workers deve ser maior que zero".to_string());
+        }
+        if self.database.url.trim().is_empty() {
+            errors.push("database.url (DATABASE_URL) é obrigatório".to_string());
+        } else if !self.database.url.starts_with("postgres://")
+            && !self.database.url.starts_with("postgresql://")
+        {
+            errors.push("database.url deve começar com postgres:// ou postgresql://".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections deve ser maior que zero".to_string());
+        }
+        if self.database.acquire_timeout_secs == 0 {
+            errors.push("database.acquire_timeout_secs deve ser maior que zero".to_string());
+        }
+        if self.cors.allowed_origins.is_empty() {
+            errors.push("cors.allowed_origins precisa de ao menos uma origem (ou \"*\")".to_string());
+        }
+        for origin in &self.cors.allowed_origins {
+            if origin != "*" && !origin.starts_with("http://") && !origin.starts_with("https://") {
+                errors.push(format!("cors.allowed_origins: origem inválida '{}'", origin));
+            }
+        }
+        if self.auth.jwt_secret.trim().is_empty() {
+            errors.push("auth.jwt_secret (JWT_SECRET) é obrigatório".to_string());
+        }
+        if self.company.name.trim().is_empty() {
+            errors.push("company.name não pode ser vazio".to_string());
+        }
+        if let Some(url) = &self.ml_api.url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push("ml_api.url deve começar com http:// ou https://".to_string());
+            }
+        }
+        if self.ml_api.timeout_ms == 0 {
+            errors.push("ml_api.timeout_ms deve ser maior que zero".to_string());
+        }
+        if self.ml_api.failure_threshold == 0 {
+            errors.push("ml_api.failure_threshold deve ser maior que zero".to_string());
+        }
+
+        errors
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.cors.allowed_origins.iter().any(|o| o == "*")
+    }
+}
+
+fn env_string(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|v| !v.trim().is_empty())
+}
+
+fn env_parse<T>(key: &str, errors: &mut Vec<String>) -> Option<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let raw = env_string(key)?;
+    match raw.trim().parse() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            errors.push(format!("{}: valor inválido '{}' ({})", key, raw, err));
+            None
+        }
+    }
+}
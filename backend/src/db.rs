@@ -1,13 +1,42 @@
+use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
-use std::env;
+use std::time::Duration;
+
+use crate::config::DatabaseConfig;
 
 pub type DbPool = sqlx::Pool<sqlx::Postgres>;
 
-pub async fn init() -> DbPool {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+/// Migrações embutidas a partir de `migrations/`.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+pub async fn init(config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
     PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .max_connections(config.max_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .connect(&config.url)
         .await
-        .expect("Failed to create pool")
+}
+
+pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+/// Versões embutidas que ainda não constam como aplicadas no banco.
+pub async fn pending_migrations(pool: &DbPool) -> Result<Vec<i64>, sqlx::Error> {
+    let applied: Vec<i64> = sqlx::query_scalar(
+        "SELECT version FROM _sqlx_migrations WHERE success = TRUE",
+    )
+    .fetch_all(pool)
+    .await
+    .or_else(|err| match err {
+        // Tabela ainda não criada: nenhuma migração aplicada
+        sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("42P01") => Ok(Vec::new()),
+        err => Err(err),
+    })?;
+
+    Ok(MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
 }
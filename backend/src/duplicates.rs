@@ -0,0 +1,485 @@
+// Detecção de cadastros duplicados (clientes e fornecedores)
+// src/duplicates.rs
+//
+// Dois cadastros são candidatos a duplicados quando coincidem no documento,
+// no e-mail ou no telefone depois de normalizados, ou quando os nomes são
+// muito parecidos. A semelhança dos nomes é o coeficiente de Dice sobre os
+// pares de letras, depois de tirar acentos, pontuação, conectivos ("da",
+// "de"...) e sufixos societários ("Ltda", "ME"...).
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// Cabeçalho da criação com os ids de cadastros parecidos, separados por vírgula.
+pub const WARNING_HEADER: &str = "possible-duplicates";
+
+/// Filtro SQL dos candidatos a duplicado de um cadastro novo (migração 0020),
+/// com os parâmetros de `SearchKeys` a partir de `$2`. Compara os dados
+/// normalizados como `Normalized` faz e, para os nomes, usa a semelhança por
+/// trigramas do pg_trgm, mais frouxa que `NAME_THRESHOLD`: a pontuação final
+/// fica com `matches_for`.
+pub const CANDIDATE_FILTER: &str = r#"
+    id <> $1 AND (
+        document = $2
+        OR regexp_replace(lower(btrim(email)), '\+[^@]*@', '@') = $3
+        OR right(regexp_replace(phone, '\D', '', 'g'), 8) = $4
+        OR lower(name) % $5
+    )
+"#;
+
+/// Pares `(first, second)` de candidatos a duplicado entre os cadastros de
+/// `table`, pelos mesmos critérios de `CANDIDATE_FILTER`. Cada critério é uma
+/// autojunção própria, para que use o índice correspondente da migração 0020;
+/// e-mails e telefones vazios ou curtos demais não formam pares.
+pub fn candidate_pairs(table: &str) -> String {
+    let email = |alias: &str| format!(r"regexp_replace(lower(btrim({}.email)), '\+[^@]*@', '@')", alias);
+    let phone = |alias: &str| format!(r"right(regexp_replace({}.phone, '\D', '', 'g'), 8)", alias);
+    format!(
+        r#"
+        SELECT a.id, b.id FROM {table} a JOIN {table} b ON b.document = a.document AND a.id < b.id
+        UNION
+        SELECT a.id, b.id FROM {table} a JOIN {table} b ON {email_b} = {email_a} AND a.id < b.id
+        WHERE position('@' IN a.email) > 0
+        UNION
+        SELECT a.id, b.id FROM {table} a JOIN {table} b ON {phone_b} = {phone_a} AND a.id < b.id
+        WHERE length({phone_a}) = 8
+        UNION
+        SELECT a.id, b.id FROM {table} a JOIN {table} b ON lower(b.name) % lower(a.name) AND a.id < b.id
+        "#,
+        table = table,
+        email_a = email("a"),
+        email_b = email("b"),
+        phone_a = phone("a"),
+        phone_b = phone("b"),
+    )
+}
+
+// Semelhança mínima entre nomes para sinalizar sem outro dado em comum
+const NAME_THRESHOLD: f64 = 0.85;
+// Telefones comparados pelos últimos dígitos, ignorando DDI e DDD
+const PHONE_DIGITS: usize = 8;
+
+const IGNORED_WORDS: &[&str] = &["da", "de", "do", "das", "dos", "e", "ltda", "me", "epp", "eireli", "sa"];
+
+/// Campos comparados de um cadastro.
+pub struct Contact<'a> {
+    pub id: Uuid,
+    pub name: &'a str,
+    pub email: Option<&'a str>,
+    pub phone: Option<&'a str>,
+    pub document: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Signal {
+    Document,
+    Email,
+    Phone,
+    Name,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateMatch {
+    pub id: Uuid,
+    pub name: String,
+    /// De 0 a 1
+    pub score: f64,
+    pub signals: Vec<Signal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicatePair {
+    pub first: Uuid,
+    pub second: Uuid,
+    pub score: f64,
+    pub signals: Vec<Signal>,
+}
+
+struct Normalized {
+    id: Uuid,
+    name: String,
+    bigrams: Vec<[char; 2]>,
+    email: Option<String>,
+    phone: Option<String>,
+    document: Option<String>,
+}
+
+impl Normalized {
+    fn new(contact: &Contact) -> Self {
+        let name = normalize_name(contact.name);
+        Normalized {
+            id: contact.id,
+            bigrams: bigrams(&name),
+            name,
+            email: contact.email.and_then(normalize_email),
+            phone: contact.phone.and_then(normalize_phone),
+            document: contact.document.and_then(normalize_document),
+        }
+    }
+}
+
+/// Parâmetros de `CANDIDATE_FILTER`, na ordem.
+pub struct SearchKeys {
+    pub document: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub name: String,
+}
+
+impl SearchKeys {
+    pub fn new(contact: &Contact) -> Self {
+        SearchKeys {
+            document: contact.document.and_then(normalize_document),
+            email: contact.email.and_then(normalize_email),
+            phone: contact.phone.and_then(normalize_phone),
+            name: contact.name.to_lowercase(),
+        }
+    }
+}
+
+/// Cabeçalho `WARNING_HEADER` da resposta de criação, se houver parecidos.
+pub fn warning_header(found: &[DuplicateMatch]) -> Option<(&'static str, String)> {
+    if found.is_empty() {
+        return None;
+    }
+    let ids: Vec<String> = found.iter().map(|found| found.id.to_string()).collect();
+    Some((WARNING_HEADER, ids.join(",")))
+}
+
+/// Minúsculas, sem o sufixo `+alguma-coisa` do usuário.
+pub fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (user, domain) = email.split_once('@')?;
+    let user = user.split('+').next().unwrap_or(user);
+    (!user.is_empty() && !domain.is_empty()).then(|| format!("{}@{}", user, domain))
+}
+
+pub fn normalize_phone(phone: &str) -> Option<String> {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    (digits.len() >= PHONE_DIGITS).then(|| digits[digits.len() - PHONE_DIGITS..].to_string())
+}
+
+/// Só os dígitos; CPF tem 11 e CNPJ 14.
+pub fn normalize_document(document: &str) -> Option<String> {
+    let digits: String = document.chars().filter(char::is_ascii_digit).collect();
+    matches!(digits.len(), 11 | 14).then_some(digits)
+}
+
+/// Documento informado num cadastro: vazio vira `None`, com pontuação é
+/// reduzido aos dígitos e fora do formato de CPF/CNPJ é recusado.
+pub fn document_input(raw: Option<&str>) -> Result<Option<String>, String> {
+    match raw.map(str::trim).filter(|raw| !raw.is_empty()) {
+        None => Ok(None),
+        Some(raw) => normalize_document(raw)
+            .map(Some)
+            .ok_or_else(|| format!("documento inválido '{}': informe CPF (11 dígitos) ou CNPJ (14)", raw)),
+    }
+}
+
+pub fn normalize_name(name: &str) -> String {
+    let plain: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+
+    plain
+        .split_whitespace()
+        .filter(|word| !IGNORED_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bigrams(name: &str) -> Vec<[char; 2]> {
+    let chars: Vec<char> = name.chars().collect();
+    chars.windows(2).map(|pair| [pair[0], pair[1]]).collect()
+}
+
+/// Coeficiente de Dice entre os pares de letras dos dois nomes.
+fn name_similarity(a: &Normalized, b: &Normalized) -> f64 {
+    if a.name == b.name {
+        return if a.name.is_empty() { 0.0 } else { 1.0 };
+    }
+    let total = a.bigrams.len() + b.bigrams.len();
+    if total == 0 {
+        return 0.0;
+    }
+    let mut remaining = b.bigrams.clone();
+    let mut shared = 0;
+    for pair in &a.bigrams {
+        if let Some(pos) = remaining.iter().position(|other| other == pair) {
+            remaining.swap_remove(pos);
+            shared += 1;
+        }
+    }
+    2.0 * shared as f64 / total as f64
+}
+
+fn compare(a: &Normalized, b: &Normalized) -> Option<(f64, Vec<Signal>)> {
+    let same = |x: &Option<String>, y: &Option<String>| x.is_some() && x == y;
+    let similarity = name_similarity(a, b);
+
+    let mut signals = Vec::new();
+    let mut score: f64 = 0.0;
+    if same(&a.document, &b.document) {
+        signals.push(Signal::Document);
+        score = score.max(1.0);
+    }
+    if same(&a.email, &b.email) {
+        signals.push(Signal::Email);
+        score = score.max(0.95);
+    }
+    if same(&a.phone, &b.phone) {
+        signals.push(Signal::Phone);
+        score = score.max(0.85);
+    }
+    if similarity >= NAME_THRESHOLD {
+        signals.push(Signal::Name);
+        score = score.max(0.9 * similarity);
+    }
+    if signals.is_empty() {
+        return None;
+    }
+
+    // Cada indício a mais reforça a suspeita
+    let score = (score + 0.05 * (signals.len() - 1) as f64).min(1.0);
+    Some(((score * 100.0).round() / 100.0, signals))
+}
+
+/// Cadastros existentes parecidos com `candidate`, do mais para o menos provável.
+pub fn matches_for(candidate: &Contact, existing: &[Contact]) -> Vec<DuplicateMatch> {
+    let candidate = Normalized::new(candidate);
+    let mut found: Vec<DuplicateMatch> = existing
+        .iter()
+        .filter(|contact| contact.id != candidate.id)
+        .filter_map(|contact| {
+            let (score, signals) = compare(&candidate, &Normalized::new(contact))?;
+            Some(DuplicateMatch {
+                id: contact.id,
+                name: contact.name.to_string(),
+                score,
+                signals,
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| b.score.total_cmp(&a.score));
+    found
+}
+
+/// Pontua os pares `candidates` (ver `candidate_pairs`) e agrupa os
+/// suspeitos: cadastros ligados por qualquer par caem no mesmo grupo, na
+/// ordem em que aparecem em `contacts`.
+pub fn find_groups(contacts: &[Contact], candidates: &[(Uuid, Uuid)]) -> Vec<(Vec<Uuid>, Vec<DuplicatePair>)> {
+    let normalized: Vec<Normalized> = contacts.iter().map(Normalized::new).collect();
+    let index: HashMap<Uuid, usize> = normalized.iter().enumerate().map(|(i, n)| (n.id, i)).collect();
+
+    let mut candidates: Vec<(usize, usize)> = candidates
+        .iter()
+        .filter_map(|(first, second)| Some((*index.get(first)?, *index.get(second)?)))
+        .filter(|(i, j)| i != j)
+        .map(|(i, j)| (i.min(j), i.max(j)))
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut pairs = Vec::new();
+    for (i, j) in candidates {
+        let (a, b) = (&normalized[i], &normalized[j]);
+        if let Some((score, signals)) = compare(a, b) {
+            pairs.push((i, j, DuplicatePair { first: a.id, second: b.id, score, signals }));
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..normalized.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for (i, j, _) in &pairs {
+        let (a, b) = (root(&mut parent, *i), root(&mut parent, *j));
+        parent[a.max(b)] = a.min(b);
+    }
+
+    let mut groups: BTreeMap<usize, (Vec<Uuid>, Vec<DuplicatePair>)> = BTreeMap::new();
+    let linked: HashSet<usize> = pairs.iter().flat_map(|(i, j, _)| [*i, *j]).collect();
+    for (index, contact) in normalized.iter().enumerate() {
+        if linked.contains(&index) {
+            let group = root(&mut parent, index);
+            groups.entry(group).or_default().0.push(contact.id);
+        }
+    }
+    for (i, _, pair) in pairs {
+        let group = root(&mut parent, i);
+        groups.entry(group).or_default().1.push(pair);
+    }
+
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact<'a>(name: &'a str, email: Option<&'a str>, phone: Option<&'a str>, document: Option<&'a str>) -> Contact<'a> {
+        Contact { id: Uuid::new_v4(), name, email, phone, document }
+    }
+
+    fn all_pairs(contacts: &[Contact]) -> Vec<(Uuid, Uuid)> {
+        let ids: Vec<Uuid> = contacts.iter().map(|c| c.id).collect();
+        ids.iter().enumerate().flat_map(|(i, a)| ids[i + 1..].iter().map(move |b| (*a, *b))).collect()
+    }
+
+    fn similarity(a: &str, b: &str) -> f64 {
+        let (a, b) = (contact(a, None, None, None), contact(b, None, None, None));
+        name_similarity(&Normalized::new(&a), &Normalized::new(&b))
+    }
+
+    #[test]
+    fn email_is_lowercased_without_plus_suffix() {
+        assert_eq!(normalize_email(" Joao+Loja@Padaria.COM ").as_deref(), Some("joao@padaria.com"));
+        assert_eq!(normalize_email("sem-arroba"), None);
+        assert_eq!(normalize_email("+tag@dominio.com"), None);
+        assert_eq!(normalize_email("user@"), None);
+    }
+
+    #[test]
+    fn phone_keeps_last_eight_digits() {
+        assert_eq!(normalize_phone("+55 (11) 98765-4321").as_deref(), Some("87654321"));
+        assert_eq!(normalize_phone("8765-4321").as_deref(), Some("87654321"));
+        assert_eq!(normalize_phone("4321"), None);
+    }
+
+    #[test]
+    fn document_accepts_cpf_and_cnpj_digits() {
+        assert_eq!(normalize_document("123.456.789-09").as_deref(), Some("12345678909"));
+        assert_eq!(normalize_document("12.345.678/0001-90").as_deref(), Some("12345678000190"));
+        assert_eq!(normalize_document("1234"), None);
+
+        assert_eq!(document_input(None), Ok(None));
+        assert_eq!(document_input(Some("  ")), Ok(None));
+        assert_eq!(document_input(Some("123.456.789-09")), Ok(Some("12345678909".to_string())));
+        assert!(document_input(Some("123")).is_err());
+    }
+
+    #[test]
+    fn name_drops_accents_punctuation_and_ignored_words() {
+        assert_eq!(normalize_name("Padaria do João Ltda."), "padaria joao");
+        assert_eq!(normalize_name("Comércio & Cia - ME"), "comercio cia");
+        assert_eq!(normalize_name("  AÇÚCAR   S.A. "), "acucar s a");
+    }
+
+    #[test]
+    fn name_similarity_is_dice_over_bigrams() {
+        assert_eq!(similarity("Padaria do João", "PADARIA JOAO LTDA"), 1.0);
+        assert_eq!(similarity("Ltda", "ME"), 0.0);
+        // "ana" e "ane": um par ("an") de dois em cada nome
+        assert_eq!(similarity("Ana", "Ane"), 0.5);
+        assert!(similarity("Mercado Central", "Mercado Centrall") >= NAME_THRESHOLD);
+        assert!(similarity("Mercado Central", "Farmácia Central") < NAME_THRESHOLD);
+    }
+
+    #[test]
+    fn matches_combine_signals_and_sort_by_score() {
+        let candidate = contact("Maria Souza", Some("maria@x.com"), Some("(11) 91234-5678"), None);
+        let by_email = contact("M. Souza", Some("MARIA+loja@x.com"), None, None);
+        let by_phone = contact("Outra Pessoa", None, Some("1234-5678"), None);
+        let by_name_and_phone = contact("Maria Souzza", None, Some("91234-5678"), None);
+        let unrelated = contact("José Lima", Some("jose@y.com"), Some("5555-0000"), None);
+
+        let found = matches_for(&candidate, &[by_email, by_phone, by_name_and_phone, unrelated]);
+        let summary: Vec<_> = found.iter().map(|m| (m.name.as_str(), m.score, m.signals.clone())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("M. Souza", 0.95, vec![Signal::Email]),
+                ("Maria Souzza", 0.91, vec![Signal::Phone, Signal::Name]),
+                ("Outra Pessoa", 0.85, vec![Signal::Phone]),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_document_is_certain_and_self_is_ignored() {
+        let candidate = contact("Loja A", None, None, Some("12345678000190"));
+        let same_document = contact("Outro Nome", None, None, Some("12345678000190"));
+        let mut itself = contact("Loja A", None, None, Some("12345678000190"));
+        itself.id = candidate.id;
+
+        let found = matches_for(&candidate, &[itself, same_document]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].score, 1.0);
+        assert_eq!(found[0].signals, vec![Signal::Document]);
+    }
+
+    #[test]
+    fn groups_join_transitive_pairs() {
+        let contacts = vec![
+            contact("Ana Lima", Some("ana@x.com"), None, None),
+            contact("Bruno", None, None, None),
+            contact("A. Lima", Some("ana@x.com"), Some("1111-2222"), None),
+            contact("Carlos", None, Some("11112222"), None),
+        ];
+
+        let groups = find_groups(&contacts, &all_pairs(&contacts));
+        assert_eq!(groups.len(), 1);
+        let (ids, pairs) = &groups[0];
+        assert_eq!(ids, &vec![contacts[0].id, contacts[2].id, contacts[3].id]);
+        assert_eq!(pairs.len(), 2);
+    }
+
+    #[test]
+    fn groups_score_only_the_candidate_pairs() {
+        let contacts = vec![
+            contact("Ana Lima", Some("ana@x.com"), None, None),
+            contact("A. Lima", Some("ana@x.com"), Some("1111-2222"), None),
+            contact("Carlos", None, Some("11112222"), None),
+        ];
+
+        // Sem o par Ana/A. Lima, o grupo fica só com o telefone em comum;
+        // pares repetidos, invertidos ou de cadastros fora da lista são ignorados
+        let candidates = [
+            (contacts[2].id, contacts[1].id),
+            (contacts[1].id, contacts[2].id),
+            (contacts[0].id, Uuid::new_v4()),
+        ];
+        let groups = find_groups(&contacts, &candidates);
+        assert_eq!(groups.len(), 1);
+        let (ids, pairs) = &groups[0];
+        assert_eq!(ids, &vec![contacts[1].id, contacts[2].id]);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].first, pairs[0].second), (contacts[1].id, contacts[2].id));
+
+        assert!(find_groups(&contacts, &[]).is_empty());
+    }
+
+    #[test]
+    fn warning_header_lists_ids() {
+        assert_eq!(warning_header(&[]), None);
+        let id = Uuid::new_v4();
+        let found = [DuplicateMatch { id, name: "x".to_string(), score: 1.0, signals: vec![Signal::Email] }];
+        assert_eq!(warning_header(&found), Some((WARNING_HEADER, id.to_string())));
+    }
+
+    #[test]
+    fn search_keys_match_the_sql_filter_inputs() {
+        let keys = SearchKeys::new(&contact("Padaria JOÃO", Some("A+b@C.com"), Some("(11) 2345-6789"), Some("123.456.789-09")));
+        assert_eq!(keys.document.as_deref(), Some("12345678909"));
+        assert_eq!(keys.email.as_deref(), Some("a@c.com"));
+        assert_eq!(keys.phone.as_deref(), Some("23456789"));
+        assert_eq!(keys.name, "padaria joão");
+    }
+}
@@ -0,0 +1,70 @@
+// Respostas de erro
+// src/errors.rs
+use actix_web::{error::InternalError, http::StatusCode, Error, HttpRequest, HttpResponse, ResponseError};
+use serde_json::{json, Value};
+use std::fmt::Debug;
+
+use crate::request_id;
+
+/// Registra o erro no log e responde 500 com o id da requisição, para que o
+/// suporte encontre a query que falhou.
+pub fn internal_error(message: &str, err: impl Debug) -> HttpResponse {
+    log::error!("{}: {:?}", message, err);
+
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
+/// Corpo padrão de erro: `{"status": "error", "message": ..., "request_id": ...}`.
+pub fn error_response(status: StatusCode, message: impl Into<String>) -> HttpResponse {
+    error_with(status, message, json!({}))
+}
+
+/// Como `error_response`, acrescentando os campos de `extra` (um objeto JSON)
+/// ao corpo.
+pub fn error_with(status: StatusCode, message: impl Into<String>, extra: Value) -> HttpResponse {
+    let mut body = json!({
+        "status": "error",
+        "message": message.into(),
+        "request_id": request_id::current(),
+    });
+
+    if let (Some(body), Value::Object(extra)) = (body.as_object_mut(), extra) {
+        body.extend(extra);
+    }
+
+    HttpResponse::build(status).json(body)
+}
+
+pub fn bad_request(message: impl Into<String>) -> HttpResponse {
+    error_response(StatusCode::BAD_REQUEST, message)
+}
+
+pub fn not_found(message: impl Into<String>) -> HttpResponse {
+    error_response(StatusCode::NOT_FOUND, message)
+}
+
+pub fn conflict(message: impl Into<String>) -> HttpResponse {
+    error_response(StatusCode::CONFLICT, message)
+}
+
+pub fn unprocessable(message: impl Into<String>) -> HttpResponse {
+    error_response(StatusCode::UNPROCESSABLE_ENTITY, message)
+}
+
+/// Violação de chave única (23505), para responder 409 em vez de 500.
+pub fn is_unique_violation(err: &sqlx::Error) -> bool {
+    matches!(err, sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some("23505"))
+}
+
+/// `error_response` como `actix_web::Error`, para extractors e middlewares.
+pub fn as_error(status: StatusCode, message: impl Into<String>) -> Error {
+    let message = message.into();
+    let response = error_response(status, message.clone());
+    InternalError::from_response(message, response).into()
+}
+
+/// Handler de erro para `JsonConfig`, `QueryConfig` e `PathConfig`: mantém o
+/// status do extractor, mas responde no formato padrão.
+pub fn extractor_error<E: ResponseError>(err: E, _req: &HttpRequest) -> Error {
+    as_error(err.status_code(), err.to_string())
+}
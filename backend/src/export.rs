@@ -0,0 +1,449 @@
+// Exportação de listagens e relatórios em CSV/XLSX
+// src/export.rs
+//
+// Os endpoints de listagem aceitam `?format=json|csv|xlsx` (ou o cabeçalho
+// `Accept`) e `?locale=pt-BR|iso`. As linhas são lidas do banco como stream
+// e escritas aos poucos: o CSV vai direto para a resposta e o XLSX é montado
+// em modo de memória constante num arquivo temporário antes do envio.
+use actix_web::{
+    http::header::{self, HeaderValue},
+    web::Bytes,
+    HttpRequest, HttpResponse,
+};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use rust_xlsxwriter::{Format as CellFormat, Workbook};
+use serde::Deserialize;
+use std::io::{Seek, SeekFrom};
+use tokio::{io::AsyncReadExt, sync::mpsc};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    errors::{bad_request, internal_error},
+    format::{date_br, decimal_br},
+};
+
+const CSV_MIME: &str = "text/csv";
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+/// Linhas em trânsito entre a consulta e a escrita do arquivo
+const ROW_BUFFER: usize = 256;
+const CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Csv,
+    Xlsx,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum Locale {
+    /// `;` como separador, vírgula decimal, datas dd/mm/aaaa
+    #[default]
+    #[serde(rename = "pt-BR")]
+    PtBr,
+    /// `,` como separador, ponto decimal, datas ISO 8601
+    #[serde(rename = "iso")]
+    Iso,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    pub format: Option<String>,
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+impl ExportParams {
+    /// Formato de arquivo pedido, ou `None` para a resposta JSON de sempre.
+    /// `format` na query tem prioridade sobre o `Accept`.
+    pub fn file_format(&self, req: &HttpRequest) -> Result<Option<FileFormat>, HttpResponse> {
+        if let Some(format) = self.format.as_deref() {
+            return match format.to_ascii_lowercase().as_str() {
+                "json" => Ok(None),
+                "csv" => Ok(Some(FileFormat::Csv)),
+                "xlsx" => Ok(Some(FileFormat::Xlsx)),
+                other => Err(bad_request(format!("Formato '{}' não suportado (use json, csv ou xlsx)", other))),
+            };
+        }
+
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        if accept.contains(XLSX_MIME) {
+            Ok(Some(FileFormat::Xlsx))
+        } else if accept.contains(CSV_MIME) {
+            Ok(Some(FileFormat::Csv))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Valor de uma célula da planilha.
+pub enum Cell {
+    Empty,
+    Text(String),
+    Integer(i64),
+    Decimal(f64),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    Bool(bool),
+}
+
+/// Linha exportável: cabeçalhos das colunas e os valores, na mesma ordem.
+pub trait Exportable {
+    fn headers() -> &'static [&'static str];
+    fn cells(&self) -> Vec<Cell>;
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<Uuid> for Cell {
+    fn from(value: Uuid) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<i32> for Cell {
+    fn from(value: i32) -> Self {
+        Cell::Integer(value.into())
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Cell::Integer(value)
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Self {
+        Cell::Decimal(value)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Cell::Bool(value)
+    }
+}
+
+impl From<NaiveDate> for Cell {
+    fn from(value: NaiveDate) -> Self {
+        Cell::Date(value)
+    }
+}
+
+impl From<NaiveDateTime> for Cell {
+    fn from(value: NaiveDateTime) -> Self {
+        Cell::DateTime(value)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Cell::Empty)
+    }
+}
+
+/// Responde com o arquivo no formato pedido. `fetch` monta a consulta; ela é
+/// executada numa task própria e as linhas chegam ao arquivo conforme são lidas.
+/// `filename` é o nome do arquivo sem extensão.
+pub async fn respond<T, F>(
+    pool: &DbPool,
+    format: FileFormat,
+    locale: Locale,
+    filename: &str,
+    fetch: F,
+) -> HttpResponse
+where
+    T: Exportable + Send + 'static,
+    F: for<'a> FnOnce(&'a DbPool) -> BoxStream<'a, Result<T, sqlx::Error>> + Send + 'static,
+{
+    let rows = spawn_query(pool.clone(), fetch);
+
+    match format {
+        FileFormat::Csv => csv_response::<T>(rows, locale, filename),
+        FileFormat::Xlsx => xlsx_response::<T>(rows, locale, filename).await,
+    }
+}
+
+/// Como `respond`, para relatórios cujas linhas já foram calculadas em memória.
+pub async fn respond_rows<T>(
+    pool: &DbPool,
+    format: FileFormat,
+    locale: Locale,
+    filename: &str,
+    rows: Vec<T>,
+) -> HttpResponse
+where
+    T: Exportable + Send + 'static,
+{
+    respond(pool, format, locale, filename, move |_| stream::iter(rows.into_iter().map(Ok)).boxed()).await
+}
+
+/// Nome de arquivo com a data de hoje, ex.: `clientes_2025-03-31`.
+pub fn filename(name: &str) -> String {
+    format!("{}_{}", name, Local::now().format("%Y-%m-%d"))
+}
+
+fn spawn_query<T, F>(pool: DbPool, fetch: F) -> mpsc::Receiver<Result<T, sqlx::Error>>
+where
+    T: Send + 'static,
+    F: for<'a> FnOnce(&'a DbPool) -> BoxStream<'a, Result<T, sqlx::Error>> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(ROW_BUFFER);
+
+    tokio::spawn(async move {
+        let mut rows = fetch(&pool);
+        while let Some(row) = rows.next().await {
+            let failed = row.is_err();
+            // O cliente desistiu do download ou a consulta falhou
+            if tx.send(row).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+fn attachment(filename: &str, extension: &str) -> (header::HeaderName, HeaderValue) {
+    let value = format!("attachment; filename=\"{}.{}\"", filename, extension);
+    (
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static("attachment")),
+    )
+}
+
+fn csv_response<T: Exportable + Send + 'static>(
+    rows: mpsc::Receiver<Result<T, sqlx::Error>>,
+    locale: Locale,
+    filename: &str,
+) -> HttpResponse {
+    let delimiter = match locale {
+        Locale::PtBr => b';',
+        Locale::Iso => b',',
+    };
+
+    // O primeiro pedaço leva o BOM (para o Excel reconhecer UTF-8) e o cabeçalho
+    let mut first = Vec::new();
+    if let Locale::PtBr = locale {
+        first.extend_from_slice("\u{feff}".as_bytes());
+    }
+    first.extend(csv_lines(delimiter, std::iter::once(T::headers().iter().map(|h| h.to_string()).collect())));
+
+    let body = futures_util::stream::unfold(
+        (rows, Some(first)),
+        move |(mut rows, pending)| async move {
+            if let Some(first) = pending {
+                return Some((Ok(Bytes::from(first)), (rows, None)));
+            }
+
+            let mut lines = Vec::new();
+            let mut size = 0;
+            loop {
+                let row = if lines.is_empty() {
+                    rows.recv().await
+                } else {
+                    rows.try_recv().ok()
+                };
+
+                match row {
+                    Some(Ok(row)) => {
+                        let line: Vec<String> = row.cells().iter().map(|c| csv_value(c, locale)).collect();
+                        size += line.iter().map(String::len).sum::<usize>();
+                        lines.push(line);
+                        if size >= CHUNK_BYTES {
+                            break;
+                        }
+                    }
+                    Some(Err(err)) => {
+                        log::error!("Erro ao exportar CSV: {:?}", err);
+                        return Some((Err(actix_web::error::ErrorInternalServerError(err)), (rows, None)));
+                    }
+                    None => break,
+                }
+            }
+
+            if lines.is_empty() {
+                None
+            } else {
+                Some((Ok(Bytes::from(csv_lines(delimiter, lines.into_iter()))), (rows, None)))
+            }
+        },
+    );
+
+    HttpResponse::Ok()
+        .content_type(format!("{}; charset=utf-8", CSV_MIME))
+        .insert_header(attachment(filename, "csv"))
+        .streaming(body)
+}
+
+fn csv_lines(delimiter: u8, lines: impl Iterator<Item = Vec<String>>) -> Vec<u8> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    for line in lines {
+        // Escrever num Vec em memória não falha
+        let _ = writer.write_record(&line);
+    }
+    writer.into_inner().unwrap_or_default()
+}
+
+fn csv_value(cell: &Cell, locale: Locale) -> String {
+    match (cell, locale) {
+        (Cell::Empty, _) => String::new(),
+        (Cell::Text(text), _) => escape_formula(text),
+        (Cell::Integer(n), _) => n.to_string(),
+        (Cell::Decimal(n), Locale::PtBr) => decimal_br(*n),
+        (Cell::Decimal(n), Locale::Iso) => format!("{:.2}", n),
+        (Cell::Date(d), Locale::PtBr) => date_br(*d),
+        (Cell::Date(d), Locale::Iso) => d.format("%Y-%m-%d").to_string(),
+        (Cell::DateTime(dt), Locale::PtBr) => dt.format("%d/%m/%Y %H:%M:%S").to_string(),
+        (Cell::DateTime(dt), Locale::Iso) => dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        (Cell::Bool(b), Locale::PtBr) => if *b { "Sim" } else { "Não" }.to_string(),
+        (Cell::Bool(b), Locale::Iso) => b.to_string(),
+    }
+}
+
+/// Planilhas interpretam como fórmula o texto que começa com `=`, `+`, `-`
+/// ou `@` (CSV injection), às vezes precedido de tabulação ou CR; o apóstrofo
+/// faz o valor ser exibido como texto.
+fn escape_formula(text: &str) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+async fn xlsx_response<T: Exportable + Send + 'static>(
+    rows: mpsc::Receiver<Result<T, sqlx::Error>>,
+    locale: Locale,
+    filename: &str,
+) -> HttpResponse {
+    let file = match tokio::task::spawn_blocking(move || write_xlsx::<T>(rows, locale)).await {
+        Ok(Ok(file)) => file,
+        Ok(Err(err)) => return internal_error("Erro ao gerar planilha", err),
+        Err(err) => return internal_error("Erro ao gerar planilha", err),
+    };
+
+    let body = futures_util::stream::unfold(tokio::fs::File::from_std(file), |mut file| async move {
+        let mut buffer = vec![0; CHUNK_BYTES];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok::<_, actix_web::Error>(Bytes::from(buffer)), file))
+            }
+            Err(err) => {
+                log::error!("Erro ao enviar planilha: {:?}", err);
+                Some((Err(actix_web::error::ErrorInternalServerError(err)), file))
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(XLSX_MIME)
+        .insert_header(attachment(filename, "xlsx"))
+        .streaming(body)
+}
+
+/// Escreve a planilha num arquivo temporário (apagado ao ser fechado). As
+/// linhas vão para o disco à medida que chegam, sem acumular em memória.
+fn write_xlsx<T: Exportable>(
+    mut rows: mpsc::Receiver<Result<T, sqlx::Error>>,
+    locale: Locale,
+) -> anyhow::Result<std::fs::File> {
+    let (date_format, datetime_format) = match locale {
+        Locale::PtBr => ("dd/mm/yyyy", "dd/mm/yyyy hh:mm:ss"),
+        Locale::Iso => ("yyyy-mm-dd", "yyyy-mm-dd hh:mm:ss"),
+    };
+    let bold = CellFormat::new().set_bold();
+    let decimal = CellFormat::new().set_num_format("#,##0.00");
+    let date = CellFormat::new().set_num_format(date_format);
+    let datetime = CellFormat::new().set_num_format(datetime_format);
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet_with_constant_memory();
+
+    for (col, title) in T::headers().iter().enumerate() {
+        let col = col as u16;
+        worksheet.write_string_with_format(0, col, *title, &bold)?;
+        worksheet.set_column_width(col, title.chars().count().max(14) as f64)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+
+    let mut row_index: u32 = 1;
+    while let Some(row) = rows.blocking_recv() {
+        let row = row?;
+        for (col, cell) in row.cells().into_iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Empty => {}
+                Cell::Text(text) => {
+                    worksheet.write_string(row_index, col, text)?;
+                }
+                Cell::Integer(n) => {
+                    worksheet.write_number(row_index, col, n as f64)?;
+                }
+                Cell::Decimal(n) => {
+                    worksheet.write_number_with_format(row_index, col, n, &decimal)?;
+                }
+                Cell::Date(d) => {
+                    worksheet.write_datetime_with_format(row_index, col, d, &date)?;
+                }
+                Cell::DateTime(dt) => {
+                    worksheet.write_datetime_with_format(row_index, col, dt, &datetime)?;
+                }
+                Cell::Bool(b) => {
+                    worksheet.write_boolean(row_index, col, b)?;
+                }
+            }
+        }
+        row_index += 1;
+    }
+
+    let mut file = tempfile::tempfile()?;
+    workbook.save_to_writer(&mut file)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_text_starting_with_formula_chars_is_escaped() {
+        for text in ["=SUM(A1:A2)", "+1", "-1+2", "@cmd", "\t=1"] {
+            let value = csv_value(&Cell::Text(text.to_string()), Locale::PtBr);
+            assert_eq!(value, format!("'{}", text));
+        }
+    }
+
+    #[test]
+    fn csv_plain_text_and_numbers_are_kept() {
+        assert_eq!(csv_value(&Cell::Text("Café".into()), Locale::PtBr), "Café");
+        assert_eq!(csv_value(&Cell::Text("a-b".into()), Locale::Iso), "a-b");
+        assert_eq!(csv_value(&Cell::Decimal(-1.5), Locale::PtBr), "-1,50");
+    }
+}
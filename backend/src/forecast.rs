@@ -0,0 +1,264 @@
+// Previsão de séries de vendas
+// src/forecast.rs
+//
+// Modelos de base para séries com sazonalidade: média móvel e Holt-Winters
+// aditivo (Holt com tendência quando não há duas temporadas de histórico).
+// Os parâmetros de suavização são escolhidos por busca em grade, minimizando
+// o erro de um passo à frente; o mesmo erro dá a largura dos intervalos.
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Model {
+    MovingAverage,
+    HoltWinters,
+}
+
+pub const MODELS: &[Model] = &[Model::MovingAverage, Model::HoltWinters];
+
+/// Valores previstos e o desvio padrão do erro em cada passo.
+pub struct Forecast {
+    pub values: Vec<f64>,
+    pub std_errors: Vec<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Accuracy {
+    pub model: Model,
+    /// Erro absoluto médio
+    pub mae: f64,
+    pub rmse: f64,
+    /// Erro percentual médio, só sobre períodos com venda
+    pub mape: Option<f64>,
+}
+
+// Grade de busca dos parâmetros de suavização
+const GRID: &[f64] = &[0.05, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+pub fn fit(model: Model, series: &[f64], season: usize, horizon: usize) -> Option<Forecast> {
+    match model {
+        Model::MovingAverage => moving_average(series, season, horizon),
+        Model::HoltWinters => holt_winters(series, season, horizon),
+    }
+}
+
+/// Ajusta o modelo sem os últimos `holdout` pontos e mede o erro neles.
+pub fn backtest(model: Model, series: &[f64], season: usize, holdout: usize) -> Option<Accuracy> {
+    if holdout == 0 || holdout >= series.len() {
+        return None;
+    }
+    let (train, test) = series.split_at(series.len() - holdout);
+    let forecast = fit(model, train, season, holdout)?;
+
+    let errors: Vec<f64> = test.iter().zip(&forecast.values).map(|(actual, predicted)| actual - predicted).collect();
+    let mae = errors.iter().map(|e| e.abs()).sum::<f64>() / holdout as f64;
+    let rmse = (errors.iter().map(|e| e * e).sum::<f64>() / holdout as f64).sqrt();
+    let percentages: Vec<f64> = test
+        .iter()
+        .zip(&errors)
+        .filter(|(actual, _)| **actual != 0.0)
+        .map(|(actual, error)| (error / actual).abs() * 100.0)
+        .collect();
+    let mape = (!percentages.is_empty()).then(|| percentages.iter().sum::<f64>() / percentages.len() as f64);
+
+    Some(Accuracy { model, mae, rmse, mape })
+}
+
+/// Previsão constante igual à média da última temporada.
+fn moving_average(series: &[f64], window: usize, horizon: usize) -> Option<Forecast> {
+    let window = window.max(1);
+    if series.len() <= window {
+        return None;
+    }
+
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let residuals: Vec<f64> = (window..series.len())
+        .map(|t| series[t] - mean(&series[t - window..t]))
+        .collect();
+    let sigma = rms(&residuals);
+    let level = mean(&series[series.len() - window..]);
+
+    // A média de `window` pontos também tem erro: variância σ²/window
+    Some(Forecast {
+        values: vec![level; horizon],
+        std_errors: vec![sigma * (1.0 + 1.0 / window as f64).sqrt(); horizon],
+    })
+}
+
+struct Smoothed {
+    level: f64,
+    trend: f64,
+    seasonals: Vec<f64>,
+    sse: f64,
+    steps: usize,
+}
+
+fn holt_winters(series: &[f64], season: usize, horizon: usize) -> Option<Forecast> {
+    let seasonal = season >= 2 && series.len() >= 2 * season;
+    if !seasonal && series.len() < 3 {
+        return None;
+    }
+    let gammas: &[f64] = if seasonal { GRID } else { &[0.0] };
+
+    let mut best: Option<(f64, f64, Smoothed)> = None;
+    for &alpha in GRID {
+        for &beta in GRID {
+            for &gamma in gammas {
+                let run = smooth(series, if seasonal { season } else { 1 }, alpha, beta, gamma);
+                if best.as_ref().is_none_or(|(_, _, b)| run.sse < b.sse) {
+                    best = Some((alpha, beta, run));
+                }
+            }
+        }
+    }
+    let (alpha, beta, fitted) = best?;
+    let sigma = (fitted.sse / fitted.steps.max(1) as f64).sqrt();
+    let period = fitted.seasonals.len();
+
+    let values = (1..=horizon)
+        .map(|h| fitted.level + h as f64 * fitted.trend + fitted.seasonals[(series.len() + h - 1) % period])
+        .collect();
+    // Variância do erro h passos à frente no modelo de Holt aditivo
+    let std_errors = (1..=horizon)
+        .map(|h| {
+            let spread: f64 = (1..h).map(|j| (alpha * (1.0 + j as f64 * beta)).powi(2)).sum();
+            sigma * (1.0 + spread).sqrt()
+        })
+        .collect();
+
+    Some(Forecast { values, std_errors })
+}
+
+/// Uma passada de suavização; `period == 1` dispensa o componente sazonal.
+fn smooth(series: &[f64], period: usize, alpha: f64, beta: f64, gamma: f64) -> Smoothed {
+    let (mut level, mut trend, mut seasonals, start) = if period > 1 {
+        let first = series[..period].iter().sum::<f64>() / period as f64;
+        let second = series[period..2 * period].iter().sum::<f64>() / period as f64;
+        let seasonals = series[..period].iter().map(|y| y - first).collect();
+        (first, (second - first) / period as f64, seasonals, period)
+    } else {
+        (series[0], series[1] - series[0], vec![0.0], 1)
+    };
+
+    let mut sse = 0.0;
+    for (t, &y) in series.iter().enumerate().skip(start) {
+        let s = seasonals[t % period];
+        let error = y - (level + trend + s);
+        sse += error * error;
+
+        let previous = level;
+        level = alpha * (y - s) + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - previous) + (1.0 - beta) * trend;
+        seasonals[t % period] = gamma * (y - level) + (1.0 - gamma) * s;
+    }
+
+    Smoothed {
+        level,
+        trend,
+        seasonals,
+        sse,
+        steps: series.len() - start,
+    }
+}
+
+fn rms(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    (values.iter().map(|v| v * v).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn constant_series_gives_flat_forecast_without_error() {
+        let series = [5.0; 12];
+        for &model in MODELS {
+            let forecast = fit(model, &series, 4, 3).unwrap();
+            assert_close(&forecast.values, &[5.0, 5.0, 5.0]);
+            assert_close(&forecast.std_errors, &[0.0, 0.0, 0.0]);
+
+            let accuracy = backtest(model, &series, 4, 4).unwrap();
+            assert!(accuracy.mae.abs() < 1e-9 && accuracy.rmse.abs() < 1e-9);
+            assert!(accuracy.mape.unwrap().abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn pure_seasonal_series_is_reproduced() {
+        let season = [10.0, 20.0, 30.0, 40.0];
+        let series: Vec<f64> = season.iter().cycle().take(12).copied().collect();
+        let forecast = fit(Model::HoltWinters, &series, 4, 6).unwrap();
+        assert_close(&forecast.values, &[10.0, 20.0, 30.0, 40.0, 10.0, 20.0]);
+        assert_close(&forecast.std_errors, &[0.0; 6]);
+
+        // Série que termina no meio da temporada continua do ponto certo
+        let series: Vec<f64> = season.iter().cycle().take(13).copied().collect();
+        let forecast = fit(Model::HoltWinters, &series, 4, 3).unwrap();
+        assert_close(&forecast.values, &[20.0, 30.0, 40.0]);
+    }
+
+    #[test]
+    fn holt_without_two_seasons_follows_the_trend() {
+        let series = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let forecast = fit(Model::HoltWinters, &series, 4, 2).unwrap();
+        assert_close(&forecast.values, &[8.0, 9.0]);
+    }
+
+    #[test]
+    fn short_series_have_no_forecast() {
+        assert!(fit(Model::MovingAverage, &[1.0, 2.0, 3.0], 3, 1).is_none());
+        assert!(fit(Model::MovingAverage, &[1.0, 2.0, 3.0, 4.0], 3, 1).is_some());
+        assert!(fit(Model::HoltWinters, &[1.0, 2.0], 4, 1).is_none());
+        assert!(fit(Model::HoltWinters, &[1.0, 2.0, 3.0], 4, 1).is_some());
+
+        let series = [1.0; 6];
+        assert!(backtest(Model::MovingAverage, &series, 2, 0).is_none());
+        assert!(backtest(Model::MovingAverage, &series, 2, 6).is_none());
+        // Treino de 3 pontos não basta para a janela de 3
+        assert!(backtest(Model::MovingAverage, &series, 3, 3).is_none());
+    }
+
+    #[test]
+    fn moving_average_error_includes_window_variance() {
+        // Resíduos ±1 contra a média dos dois pontos anteriores
+        let forecast = fit(Model::MovingAverage, &[0.0, 2.0, 0.0, 2.0, 0.0, 2.0], 2, 2).unwrap();
+        assert_close(&forecast.values, &[1.0, 1.0]);
+        assert_close(&forecast.std_errors, &[1.5_f64.sqrt(); 2]);
+    }
+
+    #[test]
+    fn holt_winters_error_grows_with_horizon() {
+        let series = [12.0, 15.0, 11.0, 18.0, 14.0, 13.0, 19.0, 16.0, 15.0, 21.0, 17.0, 16.0];
+        let forecast = fit(Model::HoltWinters, &series, 4, 4).unwrap();
+        assert!(forecast.std_errors[0] > 0.0);
+        assert!(forecast.std_errors.windows(2).all(|pair| pair[1] >= pair[0]));
+    }
+
+    #[test]
+    fn backtest_measures_the_holdout() {
+        // Treino [10, 10, 10, 10, 10]: média da janela 10 para os dois pontos
+        let accuracy = backtest(Model::MovingAverage, &[10.0, 10.0, 10.0, 10.0, 10.0, 20.0, 5.0], 4, 2).unwrap();
+        assert_eq!(accuracy.model, Model::MovingAverage);
+        assert!((accuracy.mae - 7.5).abs() < 1e-9);
+        assert!((accuracy.rmse - 62.5_f64.sqrt()).abs() < 1e-9);
+        assert!((accuracy.mape.unwrap() - 75.0).abs() < 1e-9);
+
+        // Períodos sem venda ficam fora do MAPE
+        let accuracy = backtest(Model::MovingAverage, &[10.0, 10.0, 10.0, 10.0, 10.0, 20.0, 0.0], 4, 2).unwrap();
+        assert!((accuracy.mae - 10.0).abs() < 1e-9);
+        assert!((accuracy.mape.unwrap() - 50.0).abs() < 1e-9);
+
+        let accuracy = backtest(Model::MovingAverage, &[10.0, 10.0, 10.0, 10.0, 10.0, 0.0, 0.0], 4, 2).unwrap();
+        assert!(accuracy.mape.is_none());
+    }
+}
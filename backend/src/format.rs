@@ -0,0 +1,64 @@
+// Formatação de valores para leitura (padrão brasileiro)
+// src/format.rs
+//
+// Compartilhada pelos PDFs, pela exportação CSV/XLSX e pelas mensagens das
+// anomalias, para que o mesmo valor apareça igual em todo lugar.
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Valor em reais no formato brasileiro: `R$ 1.234,56`.
+pub fn brl(value: f64) -> String {
+    let cents = (value.abs() * 100.0).round() as u64;
+    let digits = (cents / 100).to_string();
+
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if value < 0.0 && cents > 0 { "-" } else { "" };
+    format!("{}R$ {},{:02}", sign, grouped, cents % 100)
+}
+
+/// Número com duas casas e vírgula decimal, sem separador de milhar
+/// (`1234,56`), como as planilhas em português esperam no CSV.
+pub fn decimal_br(value: f64) -> String {
+    format!("{:.2}", value).replace('.', ",")
+}
+
+pub fn date_br(date: NaiveDate) -> String {
+    date.format("%d/%m/%Y").to_string()
+}
+
+pub fn datetime_br(datetime: NaiveDateTime) -> String {
+    datetime.format("%d/%m/%Y %H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brl_groups_thousands_and_rounds_cents() {
+        assert_eq!(brl(0.0), "R$ 0,00");
+        assert_eq!(brl(12.5), "R$ 12,50");
+        assert_eq!(brl(1234.567), "R$ 1.234,57");
+        assert_eq!(brl(1_000_000.0), "R$ 1.000.000,00");
+    }
+
+    #[test]
+    fn brl_keeps_sign_only_when_nonzero() {
+        assert_eq!(brl(-1234.5), "-R$ 1.234,50");
+        assert_eq!(brl(-0.001), "R$ 0,00");
+    }
+
+    #[test]
+    fn decimal_and_dates() {
+        assert_eq!(decimal_br(-1.5), "-1,50");
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(date_br(date), "05/03/2024");
+        assert_eq!(datetime_br(date.and_hms_opt(9, 7, 0).unwrap()), "05/03/2024 09:07");
+    }
+}
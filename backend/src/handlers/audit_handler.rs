@@ -0,0 +1,70 @@
+// Handler da trilha de auditoria
+// src/handlers/audit_handler.rs
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use sqlx::query_as;
+
+use crate::{
+    auth::AdminUser,
+    db::DbPool,
+    errors::internal_error,
+    export::{self, ExportParams},
+    models::audit::{AuditEntry, AuditQuery},
+};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+/// GET /api/audit?entity=expense&entity_id=...&limit=100 (somente admin)
+#[get("/audit")]
+pub async fn get_audit_log(
+    pool: web::Data<DbPool>,
+    query: web::Query<AuditQuery>,
+    AdminUser(admin): AdminUser,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    log::info!("Auditoria consultada por {}", admin.id);
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let sql = r#"
+        SELECT *
+        FROM audit_log
+        WHERE ($1::TEXT IS NULL OR entity = $1)
+          AND ($2::UUID IS NULL OR entity_id = $2)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3
+    "#;
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let (entity, entity_id) = (query.entity.clone(), query.entity_id);
+            let filename = export::filename("auditoria");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, AuditEntry>(sql)
+                    .bind(entity)
+                    .bind(entity_id)
+                    .bind(limit)
+                    .fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let entries = query_as::<_, AuditEntry>(sql)
+        .bind(query.entity.as_deref())
+        .bind(query.entity_id)
+        .bind(limit)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match entries {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar auditoria", err),
+    }
+}
+
+pub fn config_audit(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_audit_log);
+}
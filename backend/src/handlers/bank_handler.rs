@@ -0,0 +1,828 @@
+// Contas bancárias, importação de extratos e conciliação
+// src/handlers/bank_handler.rs
+use actix_multipart::Multipart;
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Local, NaiveDate};
+use serde_json::json;
+use sqlx::{query_as, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, Change},
+    auth::{AuthUser, OptionalUser},
+    db::DbPool,
+    errors::{bad_request, conflict, internal_error, is_unique_violation, not_found, unprocessable},
+    export::{self, ExportParams},
+    ledger,
+    models::bank::{
+        Account, AccountBalance, BalanceHistoryQuery, BalanceQuery, CreateAccount, DailyBalance,
+        MatchRequest, ReconcileParams, StatementLine, StatementLinesQuery, UpdateAccount, ACCOUNT_KINDS,
+    },
+    periods,
+    reconciliation::{self, Entry},
+    statement,
+    upload,
+    versioning::{self, etag},
+};
+
+const LINE_STATUSES: &[&str] = &["unmatched", "auto", "manual", "ignored"];
+const MAX_HISTORY_DAYS: i64 = 366;
+
+// Entradas (+) e saídas (-) de cada conta por dia: vendas recebidas,
+// pagamentos e transferências. Saldo = saldo inicial + soma dos movimentos.
+const ACCOUNT_MOVEMENTS: &str = r#"
+    movements AS (
+        SELECT account_id, total_price AS amount, created_at::date AS day
+        FROM sales WHERE deleted_at IS NULL
+        UNION ALL
+        SELECT account_id, -amount, COALESCE(payment_date, created_at::date)
+        FROM payments WHERE deleted_at IS NULL
+        UNION ALL
+        SELECT to_account_id, amount, transferred_on
+        FROM transfers WHERE deleted_at IS NULL
+        UNION ALL
+        SELECT from_account_id, -amount, transferred_on
+        FROM transfers WHERE deleted_at IS NULL
+    )
+"#;
+
+// Pagamentos da conta ($3) ainda não conciliados, na janela de datas ($1..$2)
+const PAYMENT_CANDIDATES: &str = r#"
+    SELECT p.id, p.payment_date AS date, p.amount,
+        concat_ws(' ', p.method, e.description, s.name) AS text
+    FROM payments p
+    LEFT JOIN expenses e ON e.id = p.expense_id
+    LEFT JOIN suppliers s ON s.id = e.supplier_id
+    WHERE p.deleted_at IS NULL
+      AND p.account_id = $3
+      AND p.payment_date BETWEEN $1 AND $2
+      AND NOT EXISTS (SELECT 1 FROM statement_lines l WHERE l.payment_id = p.id)
+"#;
+
+// Vendas (recebimentos) da conta ($3) ainda não conciliadas, na janela de datas ($1..$2)
+const SALE_CANDIDATES: &str = r#"
+    SELECT s.id, s.created_at::date AS date, s.total_price AS amount,
+        concat_ws(' ', p.name, c.name) AS text
+    FROM sales s
+    JOIN products p ON p.id = s.product_id
+    LEFT JOIN clients c ON c.id = s.client_id
+    WHERE s.deleted_at IS NULL
+      AND s.account_id = $3
+      AND s.created_at::date BETWEEN $1 AND $2
+      AND NOT EXISTS (SELECT 1 FROM statement_lines l WHERE l.sale_id = s.id)
+"#;
+
+// GET /api/accounts
+#[get("/accounts")]
+pub async fn get_accounts(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = "SELECT * FROM accounts ORDER BY name";
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("contas");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Account>(sql).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let accounts = query_as::<_, Account>(sql)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match accounts {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar contas", err),
+    }
+}
+
+// GET /api/accounts/{id}
+#[get("/accounts/{id}")]
+pub async fn get_account_by_id(pool: web::Data<DbPool>, path: web::Path<Uuid>) -> impl Responder {
+    let account = query_as::<_, Account>("SELECT * FROM accounts WHERE id = $1")
+        .bind(path.into_inner())
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    match account {
+        Ok(Some(account)) => HttpResponse::Ok().insert_header(etag(account.version)).json(account),
+        Ok(None) => not_found("Conta não encontrada"),
+        Err(err) => internal_error("Erro ao buscar conta", err),
+    }
+}
+
+// POST /api/accounts
+#[post("/accounts")]
+pub async fn create_account(
+    pool: web::Data<DbPool>,
+    account: web::Json<CreateAccount>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    if account.name.trim().is_empty() {
+        return bad_request("Informe o nome da conta");
+    }
+    if !ACCOUNT_KINDS.contains(&account.kind.as_str()) {
+        return invalid_kind();
+    }
+
+    let new_id = Uuid::new_v4();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let created = query_as::<_, Account>(
+        "INSERT INTO accounts (id, name, bank_code, branch, number, kind, opening_balance) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *"
+    )
+    .bind(new_id)
+    .bind(account.name.trim())
+    .bind(&account.bank_code)
+    .bind(&account.branch)
+    .bind(&account.number)
+    .bind(&account.kind)
+    .bind(account.opening_balance)
+    .fetch_one(&mut tx)
+    .await;
+
+    let created = match created {
+        Ok(account) => account,
+        Err(err) => return internal_error("Erro ao criar conta", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "account", new_id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = post_account(&mut tx, user.as_ref(), &created).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(created),
+        Err(err) => internal_error("Erro ao criar conta", err),
+    }
+}
+
+// PATCH /api/accounts/{id}
+#[patch("/accounts/{id}")]
+pub async fn update_account(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    account_update: web::Json<UpdateAccount>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    if let Some(kind) = account_update.kind.as_deref() {
+        if !ACCOUNT_KINDS.contains(&kind) {
+            return invalid_kind();
+        }
+    }
+    if account_update.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return bad_request("Informe o nome da conta");
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Account>("SELECT * FROM accounts WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(account)) => account,
+        Ok(None) => return not_found("Conta não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar conta", err),
+    };
+
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
+    }
+
+    let name = account_update.name.as_deref().map(str::trim).unwrap_or(&existing.name);
+    let bank_code = account_update.bank_code.clone().or_else(|| existing.bank_code.clone());
+    let branch = account_update.branch.clone().or_else(|| existing.branch.clone());
+    let number = account_update.number.clone().or_else(|| existing.number.clone());
+    let kind = account_update.kind.as_deref().unwrap_or(&existing.kind);
+    let opening_balance = account_update.opening_balance.unwrap_or(existing.opening_balance);
+
+    // O saldo inicial é lançado na data de abertura da conta
+    if opening_balance != existing.opening_balance {
+        if let Err(res) = periods::ensure_open(&mut tx, &[existing.created_at.date()]).await {
+            return res;
+        }
+    }
+
+    let updated = query_as::<_, Account>(
+        r#"
+        UPDATE accounts
+        SET name = $1, bank_code = $2, branch = $3, number = $4, kind = $5, opening_balance = $6, version = version + 1
+        WHERE id = $7
+        RETURNING *
+        "#
+    )
+    .bind(name)
+    .bind(bank_code)
+    .bind(branch)
+    .bind(number)
+    .bind(kind)
+    .bind(opening_balance)
+    .bind(id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let updated = match updated {
+        Ok(account) => account,
+        Err(err) => return internal_error("Erro ao atualizar conta", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "account", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = post_account(&mut tx, user.as_ref(), &updated).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(updated),
+        Err(err) => internal_error("Erro ao atualizar conta", err),
+    }
+}
+
+// GET /api/accounts/balances?as_of=2024-12-31 - saldo de todas as contas na data
+#[get("/accounts/balances")]
+pub async fn get_balances(pool: web::Data<DbPool>, params: web::Query<BalanceQuery>) -> impl Responder {
+    let as_of = params.as_of.unwrap_or_else(|| Local::now().date_naive());
+
+    let balances = fetch_balances(pool.get_ref(), None, as_of).await;
+
+    match balances {
+        Ok(data) => {
+            let total: f64 = data.iter().map(|b| b.balance).sum();
+            HttpResponse::Ok().json(json!({
+                "as_of": as_of,
+                "total": total,
+                "accounts": data,
+            }))
+        }
+        Err(err) => internal_error("Erro ao calcular saldos", err),
+    }
+}
+
+// GET /api/accounts/{id}/balance?as_of=2024-12-31
+#[get("/accounts/{id}/balance")]
+pub async fn get_account_balance(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    params: web::Query<BalanceQuery>,
+) -> impl Responder {
+    let as_of = params.as_of.unwrap_or_else(|| Local::now().date_naive());
+
+    let balance = fetch_balances(pool.get_ref(), Some(path.into_inner()), as_of).await;
+
+    match balance {
+        Ok(mut data) => match data.pop() {
+            Some(balance) => HttpResponse::Ok().json(balance),
+            None => not_found("Conta não encontrada"),
+        },
+        Err(err) => internal_error("Erro ao calcular saldo", err),
+    }
+}
+
+// GET /api/accounts/{id}/balance-history?start_date=..&end_date=.. - saldo diário (padrão: últimos 30 dias)
+#[get("/accounts/{id}/balance-history")]
+pub async fn get_balance_history(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    params: web::Query<BalanceHistoryQuery>,
+) -> impl Responder {
+    let account_id = path.into_inner();
+    let end = params.end_date.unwrap_or_else(|| Local::now().date_naive());
+    let start = params.start_date.unwrap_or(end - Duration::days(29));
+
+    if start > end {
+        return bad_request("start_date deve ser anterior a end_date");
+    }
+    if (end - start).num_days() >= MAX_HISTORY_DAYS {
+        return bad_request(format!("O período pode ter no máximo {} dias", MAX_HISTORY_DAYS));
+    }
+
+    let history = query_as::<_, DailyBalance>(&format!(
+        r#"
+        WITH {},
+        daily AS (
+            SELECT day, SUM(amount) AS net FROM movements WHERE account_id = $1 GROUP BY day
+        )
+        SELECT g.day::date AS date,
+            COALESCE(daily.net, 0) AS net_change,
+            a.opening_balance
+                + COALESCE((SELECT SUM(net) FROM daily WHERE daily.day < $2), 0)
+                + SUM(COALESCE(daily.net, 0)) OVER (ORDER BY g.day) AS balance
+        FROM generate_series($2::date, $3::date, INTERVAL '1 day') AS g(day)
+        JOIN accounts a ON a.id = $1
+        LEFT JOIN daily ON daily.day = g.day::date
+        ORDER BY g.day
+        "#,
+        ACCOUNT_MOVEMENTS
+    ))
+    .bind(account_id)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    // O período tem ao menos um dia, então a lista só vem vazia sem a conta
+    match history {
+        Ok(data) if data.is_empty() => not_found("Conta não encontrada"),
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao calcular histórico de saldo", err),
+    }
+}
+
+/// Saldos no fim do dia `as_of`, de uma conta ou de todas.
+async fn fetch_balances(
+    pool: &DbPool,
+    account_id: Option<Uuid>,
+    as_of: NaiveDate,
+) -> Result<Vec<AccountBalance>, sqlx::Error> {
+    query_as::<_, AccountBalance>(&format!(
+        r#"
+        WITH {}
+        SELECT a.id AS account_id, a.name, a.kind, $1::date AS as_of,
+            a.opening_balance + COALESCE(SUM(m.amount) FILTER (WHERE m.day <= $1), 0) AS balance
+        FROM accounts a
+        LEFT JOIN movements m ON m.account_id = a.id
+        WHERE $2::uuid IS NULL OR a.id = $2
+        GROUP BY a.id
+        ORDER BY a.name
+        "#,
+        ACCOUNT_MOVEMENTS
+    ))
+    .bind(as_of)
+    .bind(account_id)
+    .fetch_all(pool)
+    .await
+}
+
+// POST /api/accounts/{id}/statements - importa um extrato OFX ou CSV (multipart, campo `file`)
+// Linhas com FITID já importado na conta são ignoradas.
+#[post("/accounts/{id}/statements")]
+pub async fn import_statement(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    payload: Multipart,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let account_id = path.into_inner();
+
+    let file = match read_file(payload).await {
+        Ok(file) => file,
+        Err(response) => return response,
+    };
+
+    let parsed = match statement::parse(&file) {
+        Ok(lines) => lines,
+        Err(message) => return unprocessable(message),
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    match account_exists(&mut tx, account_id).await {
+        Ok(true) => {}
+        Ok(false) => return not_found("Conta não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar conta", err),
+    }
+
+    let mut imported = Vec::new();
+    let mut duplicates = 0;
+    for line in &parsed {
+        let inserted = query_as::<_, StatementLine>(
+            r#"
+            INSERT INTO statement_lines (id, account_id, fitid, posted_on, amount, description)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (account_id, fitid) DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(account_id)
+        .bind(&line.fitid)
+        .bind(line.posted_on)
+        .bind(line.amount)
+        .bind(&line.description)
+        .fetch_optional(&mut tx)
+        .await;
+
+        match inserted {
+            Ok(Some(created)) => {
+                if let Err(err) = audit::record(&mut tx, user.as_ref(), "statement_line", created.id, Change::Created(&created)).await {
+                    return internal_error("Erro ao registrar auditoria", err);
+                }
+                imported.push(created);
+            }
+            Ok(None) => duplicates += 1,
+            Err(err) => return internal_error("Erro ao importar extrato", err),
+        }
+    }
+
+    match tx.commit().await {
+        Ok(_) => {
+            log::info!("Extrato importado na conta {}: {} novas, {} repetidas", account_id, imported.len(), duplicates);
+            HttpResponse::Created().json(json!({
+                "status": "success",
+                "imported": imported.len(),
+                "duplicates": duplicates,
+                "lines": imported,
+            }))
+        }
+        Err(err) => internal_error("Erro ao importar extrato", err),
+    }
+}
+
+// GET /api/accounts/{id}/statement-lines?status=unmatched
+#[get("/accounts/{id}/statement-lines")]
+pub async fn get_statement_lines(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    params: web::Query<StatementLinesQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    if let Some(status) = params.status.as_deref() {
+        if !LINE_STATUSES.contains(&status) {
+            return bad_request("status deve ser unmatched, auto, manual ou ignored");
+        }
+    }
+
+    let sql = r#"
+        SELECT * FROM statement_lines
+        WHERE account_id = $1 AND ($2::text IS NULL OR status = $2)
+        ORDER BY posted_on DESC, imported_at, fitid
+    "#;
+    let account_id = path.into_inner();
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("extrato");
+            let status = params.status.clone();
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, StatementLine>(sql).bind(account_id).bind(status).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let lines = query_as::<_, StatementLine>(sql)
+        .bind(account_id)
+        .bind(&params.status)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match lines {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar extrato", err),
+    }
+}
+
+// POST /api/accounts/{id}/reconcile?amount_tolerance=0.01&date_tolerance_days=3
+// Concilia automaticamente as linhas pendentes da conta.
+#[post("/accounts/{id}/reconcile")]
+pub async fn reconcile_account(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    params: web::Query<ReconcileParams>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let account_id = path.into_inner();
+
+    if !(params.amount_tolerance >= 0.0 && params.amount_tolerance <= 1000.0) {
+        return bad_request("amount_tolerance deve estar entre 0 e 1000");
+    }
+    if !(0..=30).contains(&params.date_tolerance_days) {
+        return bad_request("date_tolerance_days deve estar entre 0 e 30");
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    match account_exists(&mut tx, account_id).await {
+        Ok(true) => {}
+        Ok(false) => return not_found("Conta não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar conta", err),
+    }
+
+    let pending = query_as::<_, StatementLine>(
+        "SELECT * FROM statement_lines WHERE account_id = $1 AND status = 'unmatched' FOR UPDATE"
+    )
+    .bind(account_id)
+    .fetch_all(&mut tx)
+    .await;
+
+    let pending = match pending {
+        Ok(lines) => lines,
+        Err(err) => return internal_error("Erro ao buscar extrato", err),
+    };
+
+    let as_entry = |line: &StatementLine| Entry {
+        id: line.id,
+        date: line.posted_on,
+        amount: line.amount.abs(),
+        text: line.description.clone(),
+    };
+    let debits: Vec<Entry> = pending.iter().filter(|l| l.amount < 0.0).map(as_entry).collect();
+    let credits: Vec<Entry> = pending.iter().filter(|l| l.amount > 0.0).map(as_entry).collect();
+
+    let mut matches = Vec::new();
+    for (lines, sql, column) in [
+        (&debits, PAYMENT_CANDIDATES, "payment_id"),
+        (&credits, SALE_CANDIDATES, "sale_id"),
+    ] {
+        let (Some(first), Some(last)) = (
+            lines.iter().map(|l| l.date).min(),
+            lines.iter().map(|l| l.date).max(),
+        ) else {
+            continue;
+        };
+        let window = Duration::days(params.date_tolerance_days);
+
+        let candidates = query_as::<_, Entry>(sql)
+            .bind(first - window)
+            .bind(last + window)
+            .bind(account_id)
+            .fetch_all(&mut tx)
+            .await;
+
+        let candidates = match candidates {
+            Ok(candidates) => candidates,
+            Err(err) => return internal_error("Erro ao buscar lançamentos para conciliação", err),
+        };
+
+        for found in reconciliation::auto_match(lines, &candidates, &params) {
+            matches.push((found, column));
+        }
+    }
+
+    let mut reconciled = Vec::new();
+    for (found, column) in &matches {
+        let before = match pending.iter().find(|l| l.id == found.line_id) {
+            Some(line) => line,
+            None => continue,
+        };
+
+        let sql = format!(
+            "UPDATE statement_lines SET status = 'auto', {} = $1, version = version + 1 WHERE id = $2 RETURNING *",
+            column
+        );
+        let updated = query_as::<_, StatementLine>(&sql)
+            .bind(found.candidate_id)
+            .bind(found.line_id)
+            .fetch_one(&mut tx)
+            .await;
+
+        let updated = match updated {
+            Ok(line) => line,
+            Err(err) if is_unique_violation(&err) => return already_reconciled(),
+            Err(err) => return internal_error("Erro ao conciliar extrato", err),
+        };
+
+        if let Err(err) = audit::record(&mut tx, user.as_ref(), "statement_line", updated.id, Change::Updated(before, &updated)).await {
+            return internal_error("Erro ao registrar auditoria", err);
+        }
+        reconciled.push(json!({ "line": updated, "score": found.score }));
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "status": "success",
+            "matched": reconciled.len(),
+            "unmatched": pending.len() - reconciled.len(),
+            "matches": reconciled,
+        })),
+        Err(err) => internal_error("Erro ao conciliar extrato", err),
+    }
+}
+
+// POST /api/statement-lines/{id}/match - vínculo manual com um pagamento ou venda
+#[post("/statement-lines/{id}/match")]
+pub async fn match_statement_line(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    body: web::Json<MatchRequest>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = match lock_line(&mut tx, id).await {
+        Ok(Some(line)) => line,
+        Ok(None) => return not_found("Linha do extrato não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar linha do extrato", err),
+    };
+
+    if existing.payment_id.is_some() || existing.sale_id.is_some() {
+        return conflict("Linha já conciliada; desfaça o vínculo antes");
+    }
+
+    // Débitos só se conciliam com pagamentos e créditos com vendas
+    let (column, target, exists_sql) = match (body.payment_id, body.sale_id) {
+        (Some(payment_id), None) if existing.amount < 0.0 => (
+            "payment_id",
+            payment_id,
+            "SELECT EXISTS (SELECT 1 FROM payments WHERE id = $1 AND account_id = $2 AND deleted_at IS NULL)",
+        ),
+        (None, Some(sale_id)) if existing.amount > 0.0 => (
+            "sale_id",
+            sale_id,
+            "SELECT EXISTS (SELECT 1 FROM sales WHERE id = $1 AND account_id = $2 AND deleted_at IS NULL)",
+        ),
+        (Some(_), None) | (None, Some(_)) => {
+            return bad_request("Débitos devem ser vinculados a um pagamento (payment_id) e créditos a uma venda (sale_id)")
+        }
+        _ => return bad_request("Informe payment_id ou sale_id"),
+    };
+
+    let found = sqlx::query_scalar::<_, bool>(exists_sql)
+        .bind(target)
+        .bind(existing.account_id)
+        .fetch_one(&mut tx)
+        .await;
+
+    match found {
+        Ok(true) => {}
+        Ok(false) if column == "payment_id" => return bad_request("Pagamento não encontrado nesta conta"),
+        Ok(false) => return bad_request("Venda não encontrada nesta conta"),
+        Err(err) => return internal_error("Erro ao buscar lançamento", err),
+    }
+
+    let sql = format!(
+        "UPDATE statement_lines SET status = 'manual', {} = $1, version = version + 1 WHERE id = $2 RETURNING *",
+        column
+    );
+    let updated = query_as::<_, StatementLine>(&sql)
+        .bind(target)
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let updated = match updated {
+        Ok(line) => line,
+        Err(err) if is_unique_violation(&err) => return already_reconciled(),
+        Err(err) => return internal_error("Erro ao conciliar linha do extrato", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "statement_line", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(updated),
+        Err(err) => internal_error("Erro ao conciliar linha do extrato", err),
+    }
+}
+
+// DELETE /api/statement-lines/{id}/match - desfaz o vínculo (ou o "ignorar") e volta a linha para pendente
+#[delete("/statement-lines/{id}/match")]
+pub async fn unmatch_statement_line(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    set_line_status(pool.get_ref(), path.into_inner(), user.as_ref(), "unmatched").await
+}
+
+// POST /api/statement-lines/{id}/ignore - tarifas, estornos e afins que não têm lançamento
+#[post("/statement-lines/{id}/ignore")]
+pub async fn ignore_statement_line(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    set_line_status(pool.get_ref(), path.into_inner(), user.as_ref(), "ignored").await
+}
+
+/// Muda a linha para `unmatched` (removendo o vínculo) ou `ignored` (só
+/// linhas sem vínculo podem ser ignoradas).
+async fn set_line_status(pool: &DbPool, id: Uuid, user: Option<&AuthUser>, status: &str) -> HttpResponse {
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = match lock_line(&mut tx, id).await {
+        Ok(Some(line)) => line,
+        Ok(None) => return not_found("Linha do extrato não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar linha do extrato", err),
+    };
+
+    if existing.status == status {
+        return HttpResponse::Ok().insert_header(etag(existing.version)).json(existing);
+    }
+    if status == "ignored" && existing.status != "unmatched" {
+        return conflict("Linha já conciliada; desfaça o vínculo antes de ignorá-la");
+    }
+
+    let updated = query_as::<_, StatementLine>(
+        "UPDATE statement_lines SET status = $1, payment_id = NULL, sale_id = NULL, version = version + 1 WHERE id = $2 RETURNING *"
+    )
+    .bind(status)
+    .bind(id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let updated = match updated {
+        Ok(line) => line,
+        Err(err) => return internal_error("Erro ao atualizar linha do extrato", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user, "statement_line", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(updated),
+        Err(err) => internal_error("Erro ao atualizar linha do extrato", err),
+    }
+}
+
+/// Conta contábil da conta financeira e lançamento do saldo inicial.
+async fn post_account(conn: &mut PgConnection, user: Option<&AuthUser>, account: &Account) -> Result<(), sqlx::Error> {
+    ledger::sync_financial_account(conn, account).await?;
+    ledger::sync(conn, user, "account", account.id, ledger::opening_balance_entry(account)).await
+}
+
+async fn account_exists(conn: &mut PgConnection, id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM accounts WHERE id = $1)")
+        .bind(id)
+        .fetch_one(conn)
+        .await
+}
+
+async fn lock_line(conn: &mut PgConnection, id: Uuid) -> Result<Option<StatementLine>, sqlx::Error> {
+    query_as::<_, StatementLine>("SELECT * FROM statement_lines WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+}
+
+fn invalid_kind() -> HttpResponse {
+    bad_request("kind deve ser checking, savings, cash ou card_acquirer")
+}
+
+fn already_reconciled() -> HttpResponse {
+    conflict("O lançamento já está conciliado com outra linha do extrato")
+}
+
+async fn read_file(payload: Multipart) -> Result<Vec<u8>, HttpResponse> {
+    upload::read_fields(payload)
+        .await?
+        .into_iter()
+        .find_map(|(name, data)| (name == "file").then_some(data))
+        .ok_or_else(|| bad_request("Envie o extrato no campo 'file'"))
+}
+
+// Configura as rotas
+pub fn config_bank(cfg: &mut web::ServiceConfig) {
+    // /accounts/balances antes de /accounts/{id}
+    cfg.service(get_accounts)
+       .service(get_balances)
+       .service(get_account_by_id)
+       .service(create_account)
+       .service(update_account)
+       .service(get_account_balance)
+       .service(get_balance_history)
+       .service(import_statement)
+       .service(get_statement_lines)
+       .service(reconcile_account)
+       .service(match_statement_line)
+       .service(unmatch_statement_line)
+       .service(ignore_statement_line);
+}
@@ -0,0 +1,347 @@
+// Orçamento: tetos por categoria de despesa, metas de vendas e realizado
+// src/handlers/budget_handler.rs
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::query_as;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, Change},
+    auth::OptionalUser,
+    db::DbPool,
+    errors::{bad_request, conflict, internal_error, is_unique_violation, not_found},
+    export::{self, ExportParams},
+    models::budget::{Budget, BudgetQuery, CreateBudget, UpdateBudget, VarianceQuery, VarianceRow, BUDGET_KINDS},
+    versioning::{self, etag},
+};
+
+// Realizado por mês: despesas pelo vencimento, vendas pela data da venda
+const ACTUALS: &str = r#"
+    SELECT EXTRACT(MONTH FROM due_date)::int AS month, 'expense' AS kind, category, SUM(amount) AS amount
+    FROM expenses
+    WHERE deleted_at IS NULL AND EXTRACT(YEAR FROM due_date) = $1
+    GROUP BY 1, 3
+    UNION ALL
+    SELECT EXTRACT(MONTH FROM created_at)::int, 'revenue', NULL, SUM(total_price)
+    FROM sales
+    WHERE deleted_at IS NULL AND EXTRACT(YEAR FROM created_at) = $1
+    GROUP BY 1
+"#;
+
+#[derive(sqlx::FromRow)]
+struct Actual {
+    month: i32,
+    kind: String,
+    category: Option<String>,
+    amount: f64,
+}
+
+// GET /api/budgets?year=2025
+#[get("/budgets")]
+pub async fn get_budgets(
+    pool: web::Data<DbPool>,
+    query: web::Query<BudgetQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = r#"
+        SELECT * FROM budgets
+        WHERE ($1::int IS NULL OR year = $1)
+        ORDER BY year, month, kind DESC, category
+    "#;
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("orcamento");
+            let year = query.year;
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Budget>(sql).bind(year).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let budgets = query_as::<_, Budget>(sql)
+        .bind(query.year)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match budgets {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar orçamento", err),
+    }
+}
+
+// POST /api/budgets
+#[post("/budgets")]
+pub async fn create_budget(
+    pool: web::Data<DbPool>,
+    budget: web::Json<CreateBudget>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    if !BUDGET_KINDS.contains(&budget.kind.as_str()) {
+        return bad_request("kind deve ser expense ou revenue");
+    }
+    if !(2000..=2100).contains(&budget.year) {
+        return bad_request("year deve estar entre 2000 e 2100");
+    }
+    if !(1..=12).contains(&budget.month) {
+        return bad_request("month deve estar entre 1 e 12");
+    }
+    if !(budget.amount.is_finite() && budget.amount >= 0.0) {
+        return bad_request("O valor orçado não pode ser negativo");
+    }
+
+    // Metas de vendas não têm categoria; tetos de despesa exigem uma
+    let category = budget.category.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    let category = match (budget.kind.as_str(), category) {
+        ("revenue", _) => None,
+        (_, Some(category)) => Some(category),
+        (_, None) => return bad_request("Informe a categoria da despesa"),
+    };
+
+    let new_id = Uuid::new_v4();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let created = query_as::<_, Budget>(
+        "INSERT INTO budgets (id, year, month, kind, category, amount) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
+    )
+    .bind(new_id)
+    .bind(budget.year)
+    .bind(budget.month)
+    .bind(&budget.kind)
+    .bind(category)
+    .bind(budget.amount)
+    .fetch_one(&mut tx)
+    .await;
+
+    let created = match created {
+        Ok(budget) => budget,
+        Err(err) if is_unique_violation(&err) => {
+            return conflict("Já existe orçamento para esta categoria no mês");
+        }
+        Err(err) => return internal_error("Erro ao criar orçamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "budget", new_id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(created),
+        Err(err) => internal_error("Erro ao criar orçamento", err),
+    }
+}
+
+// PATCH /api/budgets/{id} - altera o valor orçado
+#[patch("/budgets/{id}")]
+pub async fn update_budget(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    budget_update: web::Json<UpdateBudget>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    if !(budget_update.amount.is_finite() && budget_update.amount >= 0.0) {
+        return bad_request("O valor orçado não pode ser negativo");
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Budget>("SELECT * FROM budgets WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(budget)) => budget,
+        Ok(None) => return not_found("Orçamento não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar orçamento", err),
+    };
+
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
+    }
+
+    let updated = query_as::<_, Budget>("UPDATE budgets SET amount = $1, version = version + 1 WHERE id = $2 RETURNING *")
+        .bind(budget_update.amount)
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let updated = match updated {
+        Ok(budget) => budget,
+        Err(err) => return internal_error("Erro ao atualizar orçamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "budget", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(updated),
+        Err(err) => internal_error("Erro ao atualizar orçamento", err),
+    }
+}
+
+// DELETE /api/budgets/{id}
+#[delete("/budgets/{id}")]
+pub async fn delete_budget(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let deleted = query_as::<_, Budget>("DELETE FROM budgets WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let deleted = match deleted {
+        Ok(Some(budget)) => budget,
+        Ok(None) => return not_found("Orçamento não encontrado"),
+        Err(err) => return internal_error("Erro ao deletar orçamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "budget", id, Change::Deleted(&deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => internal_error("Erro ao deletar orçamento", err),
+    }
+}
+
+// GET /api/budgets/{year}/variance?threshold=10&month=3 - planejado x realizado
+#[get("/budgets/{year}/variance")]
+pub async fn get_budget_variance(
+    pool: web::Data<DbPool>,
+    path: web::Path<i32>,
+    query: web::Query<VarianceQuery>,
+) -> impl Responder {
+    let year = path.into_inner();
+
+    if query.month.is_some_and(|m| !(1..=12).contains(&m)) {
+        return bad_request("month deve estar entre 1 e 12");
+    }
+    if !(query.threshold.is_finite() && query.threshold >= 0.0) {
+        return bad_request("threshold deve ser um percentual positivo");
+    }
+
+    let budgets = query_as::<_, Budget>("SELECT * FROM budgets WHERE year = $1")
+        .bind(year)
+        .fetch_all(pool.get_ref())
+        .await;
+    let actuals = query_as::<_, Actual>(ACTUALS)
+        .bind(year)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    let (budgets, actuals) = match (budgets, actuals) {
+        (Ok(budgets), Ok(actuals)) => (budgets, actuals),
+        (Err(err), _) | (_, Err(err)) => return internal_error("Erro ao calcular planejado x realizado", err),
+    };
+
+    // (mês, tipo, categoria) -> (planejado, realizado)
+    let mut monthly: BTreeMap<(i32, String, Option<String>), (f64, f64)> = BTreeMap::new();
+    for budget in budgets {
+        monthly.entry((budget.month, budget.kind, budget.category)).or_default().0 += budget.amount;
+    }
+    for actual in actuals {
+        monthly.entry((actual.month, actual.kind, actual.category)).or_default().1 += actual.amount;
+    }
+    if let Some(month) = query.month {
+        monthly.retain(|(m, _, _), _| *m == month);
+    }
+
+    let mut yearly: BTreeMap<(String, Option<String>), (f64, f64)> = BTreeMap::new();
+    for ((_, kind, category), (planned, actual)) in &monthly {
+        let total = yearly.entry((kind.clone(), category.clone())).or_default();
+        total.0 += planned;
+        total.1 += actual;
+    }
+
+    let months: Vec<VarianceRow> = monthly
+        .into_iter()
+        .map(|((month, kind, category), (planned, actual))| {
+            variance_row(Some(month), kind, category, planned, actual, query.threshold)
+        })
+        .collect();
+    let totals: Vec<VarianceRow> = yearly
+        .into_iter()
+        .map(|((kind, category), (planned, actual))| variance_row(None, kind, category, planned, actual, query.threshold))
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "year": year,
+        "month": query.month,
+        "threshold": query.threshold,
+        "flagged": months.iter().filter(|row| row.flagged).count(),
+        "months": months,
+        "totals": totals,
+    }))
+}
+
+/// Despesa sinalizada quando passa do teto além do limite (ou não tinha
+/// orçamento); vendas, quando ficam abaixo da meta além do limite.
+fn variance_row(
+    month: Option<i32>,
+    kind: String,
+    category: Option<String>,
+    planned: f64,
+    actual: f64,
+    threshold: f64,
+) -> VarianceRow {
+    let variance = actual - planned;
+    let percentage = (planned > 0.0).then(|| (variance / planned * 10000.0).round() / 100.0);
+    let flagged = match (kind.as_str(), percentage) {
+        ("expense", Some(pct)) => pct > threshold,
+        ("expense", None) => actual > 0.0,
+        (_, Some(pct)) => pct < -threshold,
+        (_, None) => false,
+    };
+
+    VarianceRow {
+        month,
+        kind,
+        category,
+        planned,
+        actual,
+        variance,
+        percentage,
+        flagged,
+    }
+}
+
+// Configura as rotas
+pub fn config_budgets(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_budgets)
+       .service(get_budget_variance)
+       .service(create_budget)
+       .service(update_budget)
+       .service(delete_budget);
+}
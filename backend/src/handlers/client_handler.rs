@@ -2,59 +2,140 @@
 // src/handlers/client_handler.rs
 use actix_web::{
     get, post, delete, patch,
-    web::{Data, Json, Path, ServiceConfig},
-    HttpResponse, Responder,
+    http::StatusCode,
+    web::{Data, Json, Path, Query, ServiceConfig},
+    HttpRequest, HttpResponse, Responder,
 };
 use serde_json::json;
 use uuid::Uuid;
-use sqlx::query_as;
+use sqlx::{query_as, PgConnection};
+use std::{cmp::Reverse, collections::HashMap};
 
 use crate::{
+    audit::{self, Change},
+    auth::{AdminUser, OptionalUser},
     db::DbPool,
-    models::client::Client,
-    schema::{CreateClient, UpdateClient},
+    duplicates::{self, Contact, DuplicateMatch, SearchKeys},
+    errors::{bad_request, error_with, internal_error, not_found},
+    export::{self, ExportParams},
+    handlers::sale_handler::{SALE_RETURNING, SALE_SELECT},
+    models::{client::Client, sale::Sale},
+    schema::{CreateClient, MergeRequest, UpdateClient},
+    versioning::{self, etag},
 };
 
+fn contact(client: &Client) -> Contact<'_> {
+    Contact {
+        id: client.id,
+        name: &client.name,
+        email: Some(&client.email),
+        phone: Some(&client.phone),
+        document: client.document.as_deref(),
+    }
+}
+
+/// Outros clientes parecidos com `client`.
+async fn similar_clients(conn: &mut PgConnection, client: &Client) -> Result<Vec<DuplicateMatch>, sqlx::Error> {
+    let keys = SearchKeys::new(&contact(client));
+    let others = query_as::<_, Client>(&format!("SELECT * FROM clients WHERE {}", duplicates::CANDIDATE_FILTER))
+        .bind(client.id)
+        .bind(&keys.document)
+        .bind(&keys.email)
+        .bind(&keys.phone)
+        .bind(&keys.name)
+        .fetch_all(conn)
+        .await?;
+    let others: Vec<Contact> = others.iter().map(contact).collect();
+    Ok(duplicates::matches_for(&contact(client), &others))
+}
+
 #[post("/clients")]
 async fn create_client(
     body: Json<CreateClient>,
     db: Data<DbPool>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let new_id = Uuid::new_v4();
 
+    let document = match duplicates::document_input(body.document.as_deref()) {
+        Ok(document) => document,
+        Err(message) => return bad_request(message),
+    };
+
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
     let query = r#"
-        INSERT INTO clients (id, name, email, phone)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id, name, email, phone
+        INSERT INTO clients (id, name, email, phone, document)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, name, email, phone, document, version
     "#;
 
-    match query_as::<_, Client>(query)
+    let client = match query_as::<_, Client>(query)
         .bind(new_id)
         .bind(&body.name)
         .bind(&body.email)
         .bind(&body.phone)
-        .fetch_one(&**db)
+        .bind(&document)
+        .fetch_one(&mut tx)
         .await
     {
-        Ok(client) => HttpResponse::Created().json(json!({
+        Ok(client) => client,
+        Err(err) => return internal_error("Erro ao criar cliente", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "client", client.id, Change::Created(&client)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    // O cadastro é criado mesmo assim; os parecidos vão no cabeçalho e quem
+    // chamou decide se mescla depois
+    let possible_duplicates = match similar_clients(&mut tx, &client).await {
+        Ok(found) => found,
+        Err(err) => return internal_error("Erro ao procurar clientes duplicados", err),
+    };
+
+    let mut response = HttpResponse::Created();
+    response.insert_header(etag(client.version));
+    if let Some(header) = duplicates::warning_header(&possible_duplicates) {
+        response.insert_header(header);
+    }
+
+    match tx.commit().await {
+        Ok(_) => response.json(json!({
             "status": "success",
-            "client": client
-        })),
-        Err(err) => HttpResponse::InternalServerError().json(json!({
-            "status": "error",
-            "message": format!("Erro ao criar cliente: {:?}", err)
+            "client": client,
         })),
+        Err(err) => internal_error("Erro ao criar cliente", err),
     }
 }
 
 #[get("/clients")]
-async fn get_all_clients(db: Data<DbPool>) -> impl Responder {
+async fn get_all_clients(
+    db: Data<DbPool>,
+    req: HttpRequest,
+    export: Query<ExportParams>,
+) -> impl Responder {
     let query = r#"
-        SELECT id, name, email, phone
+        SELECT id, name, email, phone, document, version
         FROM clients
         ORDER BY name
     "#;
 
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("clientes");
+            return export::respond(db.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Client>(query).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
     match query_as::<_, Client>(query)
         .fetch_all(&**db)
         .await
@@ -63,10 +144,7 @@ async fn get_all_clients(db: Data<DbPool>) -> impl Responder {
             "status": "success",
             "clients": clients
         })),
-        Err(err) => HttpResponse::InternalServerError().json(json!({
-            "status": "error",
-            "message": format!("Erro ao buscar clientes: {:?}", err)
-        })),
+        Err(err) => internal_error("Erro ao buscar clientes", err),
     }
 }
 
@@ -78,7 +156,7 @@ async fn get_client_by_id(
     let id = path.into_inner();
 
     let query = r#"
-        SELECT id, name, email, phone
+        SELECT id, name, email, phone, document, version
         FROM clients
         WHERE id = $1
     "#;
@@ -88,18 +166,12 @@ async fn get_client_by_id(
         .fetch_optional(&**db)
         .await
     {
-        Ok(Some(client)) => HttpResponse::Ok().json(json!({
+        Ok(Some(client)) => HttpResponse::Ok().insert_header(etag(client.version)).json(json!({
             "status": "success",
             "client": client
         })),
-        Ok(None) => HttpResponse::NotFound().json(json!({
-            "status": "error",
-            "message": "Cliente não encontrado"
-        })),
-        Err(err) => HttpResponse::InternalServerError().json(json!({
-            "status": "error",
-            "message": format!("Erro ao buscar cliente: {:?}", err)
-        })),
+        Ok(None) => not_found("Cliente não encontrado"),
+        Err(err) => internal_error("Erro ao buscar cliente", err),
     }
 }
 
@@ -108,54 +180,81 @@ async fn update_client_by_id(
     path: Path<Uuid>,
     body: Json<UpdateClient>,
     db: Data<DbPool>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
 ) -> impl Responder {
     let id = path.into_inner();
 
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    let document = match duplicates::document_input(body.document.as_deref()) {
+        Ok(document) => document,
+        Err(message) => return bad_request(message),
+    };
+
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
     let existing = sqlx::query_as::<_, Client>(
-        "SELECT * FROM clients WHERE id = $1"
+        "SELECT * FROM clients WHERE id = $1 FOR UPDATE"
     )
     .bind(id)
-    .fetch_optional(&**db)
+    .fetch_optional(&mut tx)
     .await;
 
-    match existing {
-        Ok(Some(_)) => {
-            let query = r#"
-                UPDATE clients
-                SET
-                    name = COALESCE($1, name),
-                    email = COALESCE($2, email),
-                    phone = COALESCE($3, phone)
-                WHERE id = $4
-                RETURNING id, name, email, phone
-            "#;
-
-            match query_as::<_, Client>(query)
-                .bind(body.name.as_ref())
-                .bind(body.email.as_ref())
-                .bind(body.phone.as_ref())
-                .bind(id)
-                .fetch_one(&**db)
-                .await
-            {
-                Ok(updated) => HttpResponse::Ok().json(json!({
-                    "status": "success",
-                    "client": updated
-                })),
-                Err(err) => HttpResponse::InternalServerError().json(json!({
-                    "status": "error",
-                    "message": format!("Erro ao atualizar cliente: {:?}", err)
-                })),
-            }
+    let existing = match existing {
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return not_found("Cliente não encontrado")
         }
-        Ok(None) => HttpResponse::NotFound().json(json!({
-            "status": "error",
-            "message": "Cliente não encontrado"
-        })),
-        Err(err) => HttpResponse::InternalServerError().json(json!({
-            "status": "error",
-            "message": format!("Erro ao verificar cliente: {:?}", err)
+        Err(err) => return internal_error("Erro ao verificar cliente", err),
+    };
+
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
+    }
+
+    let query = r#"
+        UPDATE clients
+        SET
+            name = COALESCE($1, name),
+            email = COALESCE($2, email),
+            phone = COALESCE($3, phone),
+            document = CASE WHEN $4 THEN $5 ELSE document END,
+            version = version + 1
+        WHERE id = $6
+        RETURNING id, name, email, phone, document, version
+    "#;
+
+    let updated = match query_as::<_, Client>(query)
+        .bind(body.name.as_ref())
+        .bind(body.email.as_ref())
+        .bind(body.phone.as_ref())
+        .bind(body.document.is_some())
+        .bind(&document)
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await
+    {
+        Ok(updated) => updated,
+        Err(err) => return internal_error("Erro ao atualizar cliente", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "client", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(json!({
+            "status": "success",
+            "client": updated
         })),
+        Err(err) => internal_error("Erro ao atualizar cliente", err),
     }
 }
 
@@ -163,28 +262,248 @@ async fn update_client_by_id(
 async fn delete_client_by_id(
     path: Path<Uuid>,
     db: Data<DbPool>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    match sqlx::query!("DELETE FROM clients WHERE id = $1", id)
-        .execute(&**db)
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let deleted = match query_as::<_, Client>("DELETE FROM clients WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_optional(&mut tx)
         .await
     {
-        Ok(res) if res.rows_affected() > 0 => HttpResponse::NoContent().finish(),
-        Ok(_) => HttpResponse::NotFound().json(json!({
-            "status": "error",
-            "message": "Cliente não encontrado"
-        })),
-        Err(err) => HttpResponse::InternalServerError().json(json!({
-            "status": "error",
-            "message": format!("Erro ao deletar cliente: {:?}", err)
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return not_found("Cliente não encontrado")
+        }
+        Err(err) => return internal_error("Erro ao deletar cliente", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "client", id, Change::Deleted(&deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => internal_error("Erro ao deletar cliente", err),
+    }
+}
+
+// GET /api/clients/duplicates - grupos de cadastros provavelmente repetidos
+#[get("/clients/duplicates")]
+async fn get_client_duplicates(db: Data<DbPool>) -> impl Responder {
+    // Só os cadastros de algum par candidato, filtrado pelos índices
+    let candidates: Vec<(Uuid, Uuid)> = match sqlx::query_as(&duplicates::candidate_pairs("clients"))
+        .fetch_all(&**db)
+        .await
+    {
+        Ok(candidates) => candidates,
+        Err(err) => return internal_error("Erro ao procurar clientes duplicados", err),
+    };
+    let ids: Vec<Uuid> = candidates.iter().flat_map(|(first, second)| [*first, *second]).collect();
+
+    let clients = query_as::<_, Client>("SELECT * FROM clients WHERE id = ANY($1) ORDER BY name")
+        .bind(&ids)
+        .fetch_all(&**db)
+        .await;
+    let sales: Result<Vec<(Uuid, i64)>, _> = sqlx::query_as(
+        "SELECT client_id, COUNT(*) FROM sales WHERE client_id IS NOT NULL GROUP BY client_id"
+    )
+    .fetch_all(&**db)
+    .await;
+
+    let (clients, sales) = match (clients, sales) {
+        (Ok(clients), Ok(sales)) => (clients, sales.into_iter().collect::<HashMap<Uuid, i64>>()),
+        (Err(err), _) | (_, Err(err)) => return internal_error("Erro ao procurar clientes duplicados", err),
+    };
+
+    let by_id: HashMap<Uuid, &Client> = clients.iter().map(|client| (client.id, client)).collect();
+    let contacts: Vec<Contact> = clients.iter().map(contact).collect();
+
+    // Sugere manter o cadastro com mais vendas (no empate, o primeiro por nome)
+    let groups: Vec<_> = duplicates::find_groups(&contacts, &candidates)
+        .into_iter()
+        .map(|(ids, pairs)| {
+            let sales_of = |id: &Uuid| sales.get(id).copied().unwrap_or(0);
+            let survivor = ids.iter().min_by_key(|id| Reverse(sales_of(id))).copied();
+            let records: Vec<_> = ids
+                .iter()
+                .map(|id| json!({ "client": by_id[id], "sales": sales_of(id) }))
+                .collect();
+            json!({
+                "suggested_survivor": survivor,
+                "clients": records,
+                "pairs": pairs,
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "status": "success",
+        "count": groups.len(),
+        "groups": groups
+    }))
+}
+
+// POST /api/clients/{id}/merge - absorve os duplicados no cliente do caminho:
+// as vendas passam para ele, dados em branco são completados e os duplicados
+// são removidos
+#[post("/clients/{id}/merge")]
+async fn merge_clients(
+    path: Path<Uuid>,
+    body: Json<MergeRequest>,
+    db: Data<DbPool>,
+    AdminUser(admin): AdminUser,
+) -> impl Responder {
+    let id = path.into_inner();
+    let user = Some(admin);
+
+    if body.duplicate_ids.is_empty() {
+        return bad_request("Informe os clientes duplicados");
+    }
+    if body.duplicate_ids.contains(&id) {
+        return bad_request("O cliente mantido não pode estar entre os duplicados");
+    }
+
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let survivor = query_as::<_, Client>("SELECT * FROM clients WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let survivor = match survivor {
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return not_found("Cliente não encontrado")
+        }
+        Err(err) => return internal_error("Erro ao buscar cliente", err),
+    };
+
+    let merged = query_as::<_, Client>("SELECT * FROM clients WHERE id = ANY($1) ORDER BY name FOR UPDATE")
+        .bind(&body.duplicate_ids)
+        .fetch_all(&mut tx)
+        .await;
+
+    let merged = match merged {
+        Ok(merged) => merged,
+        Err(err) => return internal_error("Erro ao buscar clientes duplicados", err),
+    };
+
+    let missing: Vec<Uuid> = body
+        .duplicate_ids
+        .iter()
+        .filter(|id| !merged.iter().any(|client| client.id == **id))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return error_with(
+            StatusCode::NOT_FOUND,
+            "Clientes duplicados não encontrados",
+            json!({ "missing": missing }),
+        );
+    }
+
+    // Venda só muda de cliente, inclusive as excluídas logicamente; valores e
+    // datas ficam como estão, então o período fechado não impede a troca
+    let sales = query_as::<_, Sale>(&format!("{} WHERE s.client_id = ANY($1) FOR UPDATE OF s", SALE_SELECT))
+        .bind(&body.duplicate_ids)
+        .fetch_all(&mut tx)
+        .await;
+
+    let sales = match sales {
+        Ok(sales) => sales,
+        Err(err) => return internal_error("Erro ao buscar vendas dos duplicados", err),
+    };
+
+    let moved = query_as::<_, Sale>(&format!(
+        "UPDATE sales SET client_id = $1, version = version + 1 WHERE client_id = ANY($2) {}",
+        SALE_RETURNING
+    ))
+    .bind(id)
+    .bind(&body.duplicate_ids)
+    .fetch_all(&mut tx)
+    .await;
+
+    let moved: HashMap<Uuid, Sale> = match moved {
+        Ok(moved) => moved.into_iter().map(|sale| (sale.id, sale)).collect(),
+        Err(err) => return internal_error("Erro ao transferir vendas", err),
+    };
+
+    for before in &sales {
+        let after = &moved[&before.id];
+        if let Err(err) = audit::record(&mut tx, user.as_ref(), "sale", before.id, Change::Updated(before, after)).await {
+            return internal_error("Erro ao registrar auditoria", err);
+        }
+    }
+
+    let blank = |value: &str| value.trim().is_empty();
+    let email = merged.iter().map(|c| c.email.as_str()).find(|e| !blank(e)).filter(|_| blank(&survivor.email));
+    let phone = merged.iter().map(|c| c.phone.as_str()).find(|p| !blank(p)).filter(|_| blank(&survivor.phone));
+    let document = merged.iter().find_map(|c| c.document.as_deref()).filter(|_| survivor.document.is_none());
+
+    let updated = query_as::<_, Client>(
+        r#"
+        UPDATE clients
+        SET email = COALESCE($1, email), phone = COALESCE($2, phone), document = COALESCE($3, document),
+            version = version + 1
+        WHERE id = $4
+        RETURNING *
+        "#
+    )
+    .bind(email)
+    .bind(phone)
+    .bind(document)
+    .bind(id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let updated = match updated {
+        Ok(client) => client,
+        Err(err) => return internal_error("Erro ao atualizar cliente", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "client", id, Change::Updated(&survivor, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = sqlx::query("DELETE FROM clients WHERE id = ANY($1)")
+        .bind(&body.duplicate_ids)
+        .execute(&mut tx)
+        .await
+    {
+        return internal_error("Erro ao remover clientes duplicados", err);
+    }
+
+    for client in &merged {
+        if let Err(err) = audit::record(&mut tx, user.as_ref(), "client", client.id, Change::Deleted(client)).await {
+            return internal_error("Erro ao registrar auditoria", err);
+        }
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(json!({
+            "status": "success",
+            "client": updated,
+            "merged": merged.len(),
+            "reassigned_sales": moved.len()
         })),
+        Err(err) => internal_error("Erro ao mesclar clientes", err),
     }
 }
 
 /// Configura as rotas para clientes
 pub fn config_clients(cfg: &mut ServiceConfig) {
     cfg.service(create_client)
+        .service(get_client_duplicates)
+        .service(merge_clients)
         .service(get_all_clients)
         .service(get_client_by_id)
         .service(update_client_by_id)
@@ -1,141 +1,348 @@
 // src/handlers/dashboard_handler.rs
-use actix_web::{get, web::{Data, ServiceConfig}, HttpResponse, Responder};
-use crate::db::DbPool;
+use actix_web::{get, web::{Data, Query, ServiceConfig}, HttpResponse, Responder};
+use crate::{db::DbPool, errors::{bad_request, internal_error}, live::{self, LiveDashboard}, models::dashboard::{DashboardSummary, Period, SummaryQuery, TimeSeriesPoint, TimeSeriesQuery, TopProduct, SERIES_METRICS}};
+use chrono::{Local, NaiveDate};
+use futures_util::stream;
 use serde::Serialize;
+use std::{collections::HashMap, time::Duration};
+use tokio::sync::broadcast::error::RecvError;
 
-#[derive(Serialize)]
-struct CountResponse {
-    count: i64,
-}
-
-#[derive(Serialize)]
-struct RevenueResponse {
-    revenue: f64,
-}
+// Comentário enviado ao painel parado, para proxies não fecharem a conexão
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+// Produtos mais vendidos no resumo
+const TOP_PRODUCTS: i64 = 5;
+// Pontos por série (pouco mais de 3 anos por dia)
+const MAX_POINTS: usize = 1200;
 
-#[derive(Serialize)]
-struct TotalResponse {
-    total: f64,
-}
+// Vendas por mês do ano corrente, pelo nome do mês; usada pelo gráfico do
+// painel (/sales/monthly) e pelos indicadores ao vivo
+const MONTHLY_SALES: &str = r#"
+    SELECT
+        to_char(created_at, 'Mon') AS month,
+        COALESCE(SUM(total_price), 0) AS sales
+    FROM sales
+    WHERE EXTRACT(YEAR FROM created_at) = EXTRACT(YEAR FROM CURRENT_DATE)
+      AND deleted_at IS NULL
+    GROUP BY month, EXTRACT(MONTH FROM created_at)
+    ORDER BY EXTRACT(MONTH FROM created_at)
+"#;
 
-#[derive(Serialize)]
-struct MonthlySale {
+#[derive(Clone, Serialize, PartialEq, sqlx::FromRow)]
+pub(crate) struct MonthlySale {
     month: String,
     sales: f64,
 }
 
-/// GET /api/clients/count
-#[get("/clients/count")]
-async fn clients_count(db: Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!("SELECT COUNT(*) as count FROM clients")
-        .fetch_one(db.get_ref())
-        .await;
+/// Indicadores do painel, recalculados a cada mudança em vendas, despesas ou pagamentos.
+#[derive(Clone, Serialize)]
+pub(crate) struct Kpis {
+    pub sales_count: i64,
+    pub revenue: f64,
+    pub expenses_total: f64,
+    pub payments_total: f64,
+    /// Despesas ainda não pagas
+    pub open_payables: f64,
+    pub monthly_sales: Vec<MonthlySale>,
+}
 
-    match result {
-        Ok(row) => HttpResponse::Ok().json(CountResponse {
-            count: row.count.unwrap_or(0),
-        }),
-        Err(err) => {
-            eprintln!("Erro ao buscar número de clientes: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
-    }
+pub(crate) async fn load_kpis(pool: &DbPool) -> Result<Kpis, sqlx::Error> {
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM sales WHERE deleted_at IS NULL) AS "sales_count!",
+            (SELECT COALESCE(SUM(total_price), 0) FROM sales WHERE deleted_at IS NULL) AS "revenue!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses WHERE deleted_at IS NULL) AS "expenses_total!",
+            (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE deleted_at IS NULL) AS "payments_total!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses WHERE deleted_at IS NULL AND NOT paid) AS "open_payables!"
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let monthly_sales = sqlx::query_as::<_, MonthlySale>(MONTHLY_SALES).fetch_all(pool).await?;
+
+    Ok(Kpis {
+        sales_count: totals.sales_count,
+        revenue: totals.revenue,
+        expenses_total: totals.expenses_total,
+        payments_total: totals.payments_total,
+        open_payables: totals.open_payables,
+        monthly_sales,
+    })
 }
 
-/// GET /api/sales/count
-#[get("/sales/count")]
-async fn sales_count(db: Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!("SELECT COUNT(*) as count FROM sales")
-        .fetch_one(db.get_ref())
-        .await;
+/// GET /api/dashboard/summary?period=today|week|month|quarter|year|all
+#[get("/dashboard/summary")]
+async fn dashboard_summary(
+    db: Data<DbPool>,
+    live: Data<LiveDashboard>,
+    query: Query<SummaryQuery>,
+) -> impl Responder {
+    let period = query.period;
+    let generation = match live.cached_summary(period) {
+        Ok(summary) => return HttpResponse::Ok().insert_header(("X-Cache", "hit")).json(summary),
+        Err(generation) => generation,
+    };
 
-    match result {
-        Ok(row) => HttpResponse::Ok().json(CountResponse {
-            count: row.count.unwrap_or(0),
-        }),
-        Err(err) => {
-            eprintln!("Erro ao buscar número de vendas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+    match load_summary(db.get_ref(), period).await {
+        Ok(summary) => {
+            live.store_summary(generation, summary.clone());
+            HttpResponse::Ok().insert_header(("X-Cache", "miss")).json(summary)
         }
+        Err(err) => internal_error("Erro ao buscar resumo do painel", err),
     }
 }
 
-/// GET /api/sales/revenue
-#[get("/sales/revenue")]
-async fn sales_revenue(db: Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!("SELECT COALESCE(SUM(total_price),0) as revenue FROM sales")
-        .fetch_one(db.get_ref())
-        .await;
+async fn load_summary(pool: &DbPool, period: Period) -> Result<DashboardSummary, sqlx::Error> {
+    let range = period.range(Local::now().date_naive());
+    let (start, end) = (range.map(|(start, _)| start), range.map(|(_, end)| end));
 
-    match result {
-        Ok(row) => HttpResponse::Ok().json(RevenueResponse {
-            revenue: row.revenue.unwrap_or(0.0),
-        }),
-        Err(err) => {
-            eprintln!("Erro ao buscar receita de vendas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
-    }
+    let row = sqlx::query!(
+        r#"
+        WITH period_sales AS (
+            SELECT * FROM sales
+            WHERE deleted_at IS NULL
+              AND ($1::date IS NULL OR created_at >= $1)
+              AND ($2::date IS NULL OR created_at < $2)
+        ),
+        top AS (
+            SELECT p.id AS product_id, p.name, SUM(s.quantity) AS quantity, SUM(s.total_price) AS revenue
+            FROM period_sales s
+            JOIN products p ON p.id = s.product_id
+            GROUP BY p.id, p.name
+            ORDER BY revenue DESC, p.name
+            LIMIT $3
+        )
+        SELECT
+            (SELECT COUNT(*) FROM period_sales) AS "sales_count!",
+            (SELECT COALESCE(SUM(total_price), 0) FROM period_sales) AS "revenue!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses
+             WHERE deleted_at IS NULL
+               AND ($1::date IS NULL OR due_date >= $1)
+               AND ($2::date IS NULL OR due_date < $2)) AS "expenses!",
+            (SELECT COUNT(*) FROM clients) AS "clients!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses
+             WHERE deleted_at IS NULL AND NOT paid) AS "open_payables!",
+            (SELECT COUNT(*) FROM expenses
+             WHERE deleted_at IS NULL AND NOT paid AND due_date < CURRENT_DATE) AS "overdue_count!",
+            (SELECT COALESCE(json_agg(top), '[]') FROM top) AS "top_products!",
+            NOW()::timestamp AS "generated_at!"
+        "#,
+        start,
+        end,
+        TOP_PRODUCTS
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let top_products: Vec<TopProduct> =
+        serde_json::from_value(row.top_products).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+
+    Ok(DashboardSummary {
+        period,
+        start_date: start,
+        end_date: end.map(|end| end - chrono::Duration::days(1)),
+        sales_count: row.sales_count,
+        revenue: row.revenue,
+        expenses: row.expenses,
+        profit: row.revenue - row.expenses,
+        clients: row.clients,
+        open_payables: row.open_payables,
+        overdue_count: row.overdue_count,
+        top_products,
+        generated_at: row.generated_at,
+    })
 }
 
-/// GET /api/expenses/total
-#[get("/expenses/total")]
-async fn expenses_total(db: Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!("SELECT COALESCE(SUM(amount),0) as total FROM expenses")
-        .fetch_one(db.get_ref())
-        .await;
+/// GET /api/dashboard/timeseries?granularity=day|week|month|quarter|year
+///     &start_date=&end_date=&metrics=revenue,quantity,expenses,profit&tz=America/Sao_Paulo
+///
+/// Um ponto por período do intervalo, com zero onde não houve movimento.
+/// Vendas são agrupadas pela data no fuso `tz`; despesas, pelo vencimento.
+#[get("/dashboard/timeseries")]
+async fn dashboard_timeseries(db: Data<DbPool>, query: Query<TimeSeriesQuery>) -> impl Responder {
+    let metrics: Vec<String> = match query.metrics.as_deref() {
+        Some(metrics) => metrics
+            .split(',')
+            .map(|m| m.trim().to_lowercase())
+            .filter(|m| !m.is_empty())
+            .collect(),
+        None => vec!["revenue".to_string()],
+    };
+    if let Some(unknown) = metrics.iter().find(|m| !SERIES_METRICS.contains(&m.as_str())) {
+        return bad_request(format!(
+            "Métrica desconhecida '{}': use {}",
+            unknown,
+            SERIES_METRICS.join(", ")
+        ));
+    }
+    let wants = |metric: &str| metrics.iter().any(|m| m == metric);
 
-    match result {
-        Ok(row) => HttpResponse::Ok().json(TotalResponse {
-            total: row.total.unwrap_or(0.0),
-        }),
-        Err(err) => {
-            eprintln!("Erro ao buscar total de despesas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+    // Valida o fuso e já traz o dia de hoje nele
+    let today = sqlx::query_scalar!(
+        r#"SELECT (NOW() AT TIME ZONE name)::date AS "today!" FROM pg_timezone_names WHERE name = $1"#,
+        query.tz
+    )
+    .fetch_optional(db.get_ref())
+    .await;
+    let today = match today {
+        Ok(Some(today)) => today,
+        Ok(None) => return bad_request(format!("Fuso horário desconhecido '{}'", query.tz)),
+        Err(err) => return internal_error("Erro ao validar fuso horário", err),
+    };
+
+    let granularity = query.granularity;
+    let end = query.end_date.unwrap_or(today);
+    let start = query.start_date.unwrap_or_else(|| granularity.default_start(end));
+    if start > end {
+        return bad_request("start_date deve ser anterior ou igual a end_date");
+    }
+
+    let mut periods = Vec::new();
+    let mut period = granularity.truncate(start);
+    while period <= end {
+        if periods.len() == MAX_POINTS {
+            return bad_request(format!(
+                "Intervalo grande demais: no máximo {} pontos; use uma granularidade maior",
+                MAX_POINTS
+            ));
         }
+        let next = match granularity.next(period) {
+            Some(next) => next,
+            None => return bad_request("Intervalo fora das datas suportadas"),
+        };
+        periods.push((period, next));
+        period = next;
     }
-}
 
-/// GET /api/sales/monthly
-#[get("/sales/monthly")]
-async fn sales_monthly(db: Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!(
+    let rows = sqlx::query!(
         r#"
+        WITH period_sales AS (
+            SELECT date_trunc($1, (created_at AT TIME ZONE 'UTC') AT TIME ZONE $4) AS period,
+                SUM(total_price) AS revenue,
+                SUM(quantity) AS quantity
+            FROM sales
+            WHERE deleted_at IS NULL
+              AND ((created_at AT TIME ZONE 'UTC') AT TIME ZONE $4)::date BETWEEN $2 AND $3
+            GROUP BY 1
+        ),
+        period_expenses AS (
+            SELECT date_trunc($1, due_date::timestamp) AS period, SUM(amount) AS expenses
+            FROM expenses
+            WHERE deleted_at IS NULL AND due_date BETWEEN $2 AND $3
+            GROUP BY 1
+        )
         SELECT
-            to_char(created_at, 'Mon') AS month_name,
-            COALESCE(SUM(total_price), 0) AS sales
-        FROM sales
-        WHERE EXTRACT(YEAR FROM created_at) = EXTRACT(YEAR FROM CURRENT_DATE)
-        GROUP BY month_name, EXTRACT(MONTH FROM created_at)
-        ORDER BY EXTRACT(MONTH FROM created_at)
-        "#
+            COALESCE(s.period, e.period)::date AS "period!",
+            COALESCE(s.revenue, 0) AS "revenue!",
+            COALESCE(s.quantity, 0) AS "quantity!",
+            COALESCE(e.expenses, 0) AS "expenses!"
+        FROM period_sales s
+        FULL JOIN period_expenses e ON e.period = s.period
+        "#,
+        granularity.unit(),
+        start,
+        end,
+        query.tz
     )
     .fetch_all(db.get_ref())
     .await;
 
+    let totals: HashMap<NaiveDate, (f64, i64, f64)> = match rows {
+        Ok(rows) => rows
+            .into_iter()
+            .map(|r| (r.period, (r.revenue, r.quantity, r.expenses)))
+            .collect(),
+        Err(err) => return internal_error("Erro ao buscar série temporal", err),
+    };
+
+    let points: Vec<TimeSeriesPoint> = periods
+        .into_iter()
+        .map(|(period, next)| {
+            let (revenue, quantity, expenses) = totals.get(&period).copied().unwrap_or_default();
+            TimeSeriesPoint {
+                period: granularity.key(period),
+                start_date: period,
+                end_date: next.pred_opt().unwrap_or(period),
+                revenue: wants("revenue").then_some(revenue),
+                quantity: wants("quantity").then_some(quantity),
+                expenses: wants("expenses").then_some(expenses),
+                profit: wants("profit").then_some(revenue - expenses),
+            }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "granularity": granularity,
+        "timezone": query.tz,
+        "start_date": start,
+        "end_date": end,
+        "metrics": metrics,
+        "points": points,
+    }))
+}
+
+/// GET /api/sales/monthly - meses do ano corrente pelo nome, usado pelo gráfico do
+/// painel; para outros intervalos e granularidades, /api/dashboard/timeseries
+#[get("/sales/monthly")]
+async fn sales_monthly(db: Data<DbPool>) -> impl Responder {
+    let result = sqlx::query_as::<_, MonthlySale>(MONTHLY_SALES)
+        .fetch_all(db.get_ref())
+        .await;
+
     match result {
-        Ok(rows) => {
-            let data: Vec<MonthlySale> = rows
-                .into_iter()
-                .map(|r| MonthlySale {
-                    month: r.month_name.unwrap_or_else(|| "N/A".to_string()),
-                    sales: r.sales.unwrap_or(0.0),
-                })
-                .collect();
-
-            HttpResponse::Ok().json(data)
-        }
-        Err(err) => {
-            eprintln!("Erro ao buscar vendas mensais: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar vendas mensais", err),
     }
 }
 
+/// GET /api/dashboard/stream - Server-Sent Events: `snapshot` com todos os
+/// indicadores ao conectar e `delta` só com os que mudaram a cada alteração
+#[get("/dashboard/stream")]
+async fn dashboard_stream(db: Data<DbPool>, live: Data<LiveDashboard>) -> impl Responder {
+    // Inscreve antes de ler, para não perder mudanças entre a leitura e o envio
+    let receiver = live.subscribe();
+
+    let snapshot = match load_kpis(db.get_ref()).await {
+        Ok(kpis) => live::frame("snapshot", &serde_json::json!(kpis)),
+        Err(err) => return internal_error("Erro ao buscar indicadores do painel", err),
+    };
+
+    let events = stream::unfold((Some(snapshot), receiver, db), |(pending, mut receiver, db)| async move {
+        if let Some(frame) = pending {
+            return Some((Ok::<_, actix_web::Error>(web_bytes(frame)), (None, receiver, db)));
+        }
+
+        let frame = match tokio::time::timeout(KEEP_ALIVE, receiver.recv()).await {
+            Ok(Ok(frame)) => frame,
+            // Painel ficou para trás e perdeu diferenças: manda tudo de novo
+            Ok(Err(RecvError::Lagged(_))) => match load_kpis(db.get_ref()).await {
+                Ok(kpis) => live::frame("snapshot", &serde_json::json!(kpis)),
+                Err(err) => {
+                    log::error!("Erro ao buscar indicadores do painel: {}", err);
+                    return None;
+                }
+            },
+            Ok(Err(RecvError::Closed)) => return None,
+            Err(_) => ": keep-alive\n\n".to_string(),
+        };
+        Some((Ok(web_bytes(frame)), (None, receiver, db)))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(events)
+}
+
+fn web_bytes(frame: String) -> actix_web::web::Bytes {
+    actix_web::web::Bytes::from(frame)
+}
+
 pub fn config_dashboard(cfg: &mut ServiceConfig) {
-    cfg.service(clients_count)
-        .service(sales_count)
-        .service(sales_revenue)
-        .service(expenses_total)
+    cfg.service(dashboard_summary)
+        .service(dashboard_timeseries)
+        .service(dashboard_stream)
         .service(sales_monthly);
 }
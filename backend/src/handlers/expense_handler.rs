@@ -1,38 +1,54 @@
 // src/handlers/expense_handler.rs
-use actix_web::{get, post, patch, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, patch, delete, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
-use sqlx::{query, query_as};
-use crate::{db::DbPool, models::expense::{Expense, CreateExpense, UpdateExpense}};
-
-// Rota estática para total de despesas
-#[get("/expenses/total")]
-pub async fn get_expenses_total(pool: web::Data<DbPool>) -> impl Responder {
-    // Exemplo simples somando todas despesas (ajuste conforme seu banco)
-    let total = query!("SELECT COALESCE(SUM(amount), 0) as total FROM expenses")
-        .fetch_one(pool.get_ref())
-        .await;
+use sqlx::query_as;
+use crate::{audit::{self, Change}, auth::OptionalUser, db::DbPool, errors::{internal_error, not_found}, export::{self, ExportParams}, ledger, periods, schema::ListParams, models::expense::{Expense, CreateExpense, UpdateExpense}, versioning::{self, etag}};
 
-    match total {
-        Ok(record) => HttpResponse::Ok().json(serde_json::json!({ "total": record.total })),
-        Err(err) => {
-            eprintln!("Erro ao calcular total de despesas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+// GET /api/expenses/categories - categorias em uso, para formulários e orçamento
+#[get("/expenses/categories")]
+pub async fn get_expense_categories(pool: web::Data<DbPool>) -> impl Responder {
+    let categories = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT category FROM expenses WHERE deleted_at IS NULL ORDER BY category"
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match categories {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar categorias de despesa", err),
     }
 }
 
 // GET /api/expenses - lista todas despesas
 #[get("/expenses")]
-pub async fn get_expenses(pool: web::Data<DbPool>) -> impl Responder {
-    match query_as::<_, Expense>("SELECT * FROM expenses ORDER BY due_date ASC")
+pub async fn get_expenses(
+    pool: web::Data<DbPool>,
+    params: web::Query<ListParams>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = "SELECT * FROM expenses WHERE $1 OR deleted_at IS NULL ORDER BY due_date ASC";
+    let include_deleted = params.include_deleted;
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("despesas");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Expense>(sql).bind(include_deleted).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    match query_as::<_, Expense>(sql)
+        .bind(include_deleted)
         .fetch_all(pool.get_ref())
         .await
     {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("Erro ao buscar despesas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => internal_error("Erro ao buscar despesas", err),
     }
 }
 
@@ -41,20 +57,19 @@ pub async fn get_expenses(pool: web::Data<DbPool>) -> impl Responder {
 pub async fn get_expense_by_id(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    params: web::Query<ListParams>,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    match query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1")
+    match query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1 AND ($2 OR deleted_at IS NULL)")
         .bind(id)
+        .bind(params.include_deleted)
         .fetch_optional(pool.get_ref())
         .await
     {
-        Ok(Some(exp)) => HttpResponse::Ok().json(exp),
-        Ok(None) => HttpResponse::NotFound().body("Despesa não encontrada"),
-        Err(err) => {
-            eprintln!("Erro ao buscar despesa por id: {:?}", err);
-            HttpResponse::InternalServerError().body("Erro ao buscar despesa")
-        }
+        Ok(Some(exp)) => HttpResponse::Ok().insert_header(etag(exp.version)).json(exp),
+        Ok(None) => not_found("Despesa não encontrada"),
+        Err(err) => internal_error("Erro ao buscar despesa por id", err),
     }
 }
 
@@ -63,26 +78,51 @@ pub async fn get_expense_by_id(
 pub async fn create_expense(
     pool: web::Data<DbPool>,
     expense: web::Json<CreateExpense>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let new_id = Uuid::new_v4();
 
-    let result = query!(
-        "INSERT INTO expenses (id, description, supplier_id, amount, due_date, paid, created_at) VALUES ($1, $2, $3, $4, $5, false, NOW())",
-        new_id,
-        expense.description,
-        expense.supplier_id,
-        expense.amount,
-        expense.due_date
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[expense.due_date]).await {
+        return res;
+    }
+
+    let created = query_as::<_, Expense>(
+        r#"
+        INSERT INTO expenses (id, description, category, supplier_id, amount, due_date, paid, created_at)
+        VALUES ($1, $2, COALESCE(NULLIF(TRIM($3), ''), 'Sem categoria'), $4, $5, $6, false, NOW())
+        RETURNING *
+        "#
     )
-    .execute(pool.get_ref())
+    .bind(new_id)
+    .bind(&expense.description)
+    .bind(&expense.category)
+    .bind(expense.supplier_id)
+    .bind(expense.amount)
+    .bind(expense.due_date)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Created().json(new_id),
-        Err(err) => {
-            eprintln!("Erro ao criar despesa: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let created = match created {
+        Ok(exp) => exp,
+        Err(err) => return internal_error("Erro ao criar despesa", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "expense", new_id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "expense", new_id, ledger::expense_entry(&created)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(new_id),
+        Err(err) => internal_error("Erro ao criar despesa", err),
     }
 }
 
@@ -92,78 +132,197 @@ pub async fn update_expense(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
     expense_update: web::Json<UpdateExpense>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let existing = query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1")
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
         .bind(id)
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(&mut tx)
         .await;
 
     let existing = match existing {
         Ok(Some(exp)) => exp,
-        Ok(None) => return HttpResponse::NotFound().body("Despesa não encontrada"),
-        Err(err) => {
-            eprintln!("Erro ao buscar despesa para atualizar: {:?}", err);
-            return HttpResponse::InternalServerError().finish();
-        }
+        Ok(None) => return not_found("Despesa não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar despesa para atualizar", err),
     };
 
-    let description = expense_update.description.clone().unwrap_or(existing.description);
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
+    }
+
+    let description = expense_update.description.clone().unwrap_or_else(|| existing.description.clone());
+    let category = expense_update
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .unwrap_or(&existing.category);
     let supplier_id = expense_update.supplier_id.or(existing.supplier_id);
     let amount = expense_update.amount.unwrap_or(existing.amount);
     let due_date = expense_update.due_date.unwrap_or(existing.due_date);
     let paid = expense_update.paid.unwrap_or(existing.paid);
 
-    let result = query!(
-        "UPDATE expenses SET description = $1, supplier_id = $2, amount = $3, due_date = $4, paid = $5 WHERE id = $6",
-        description,
-        supplier_id,
-        amount,
-        due_date,
-        paid,
-        id
+    if let Err(res) = periods::ensure_open(&mut tx, &[existing.due_date, due_date]).await {
+        return res;
+    }
+
+    let updated = query_as::<_, Expense>(
+        "UPDATE expenses SET description = $1, category = $2, supplier_id = $3, amount = $4, due_date = $5, paid = $6, version = version + 1 WHERE id = $7 RETURNING *"
     )
-    .execute(pool.get_ref())
+    .bind(description)
+    .bind(category)
+    .bind(supplier_id)
+    .bind(amount)
+    .bind(due_date)
+    .bind(paid)
+    .bind(id)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Ok().body("Despesa atualizada"),
-        Err(err) => {
-            eprintln!("Erro ao atualizar despesa: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let updated = match updated {
+        Ok(exp) => exp,
+        Err(err) => return internal_error("Erro ao atualizar despesa", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "expense", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "expense", id, ledger::expense_entry(&updated)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).body("Despesa atualizada"),
+        Err(err) => internal_error("Erro ao atualizar despesa", err),
     }
 }
 
-// DELETE /api/expenses/{id} - deleta despesa
+// DELETE /api/expenses/{id} - exclusão lógica
 #[delete("/expenses/{id}")]
 pub async fn delete_expense(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let result = query!("DELETE FROM expenses WHERE id = $1", id)
-        .execute(pool.get_ref())
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
         .await;
 
-    match result {
-        Ok(res) if res.rows_affected() > 0 => HttpResponse::NoContent().finish(),
-        Ok(_) => HttpResponse::NotFound().body("Despesa não encontrada"),
-        Err(err) => {
-            eprintln!("Erro ao deletar despesa: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let existing = match existing {
+        Ok(Some(exp)) => exp,
+        Ok(None) => return not_found("Despesa não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar despesa para deletar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[existing.due_date]).await {
+        return res;
+    }
+
+    let deleted = query_as::<_, Expense>("UPDATE expenses SET deleted_at = NOW(), version = version + 1 WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let deleted = match deleted {
+        Ok(exp) => exp,
+        Err(err) => return internal_error("Erro ao deletar despesa", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "expense", id, Change::SoftDeleted(&existing, &deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "expense", id, ledger::expense_entry(&deleted)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => internal_error("Erro ao deletar despesa", err),
+    }
+}
+
+// POST /api/expenses/{id}/restore - desfaz a exclusão lógica
+#[post("/expenses/{id}/restore")]
+pub async fn restore_expense(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(exp)) => exp,
+        Ok(None) => return not_found("Despesa excluída não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar despesa para restaurar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[existing.due_date]).await {
+        return res;
+    }
+
+    let restored = query_as::<_, Expense>("UPDATE expenses SET deleted_at = NULL, version = version + 1 WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let restored = match restored {
+        Ok(exp) => exp,
+        Err(err) => return internal_error("Erro ao restaurar despesa", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "expense", id, Change::Restored(&existing, &restored)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "expense", id, ledger::expense_entry(&restored)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(restored.version)).json(restored),
+        Err(err) => internal_error("Erro ao restaurar despesa", err),
     }
 }
 
 // Registra rotas de despesas
 pub fn config_expenses(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_expenses_total);  // Registrar primeiro a rota estática
+    cfg.service(get_expense_categories);  // Registrar antes de /expenses/{id}
     cfg.service(get_expenses);
     cfg.service(get_expense_by_id);
     cfg.service(create_expense);
     cfg.service(update_expense);
     cfg.service(delete_expense);
+    cfg.service(restore_expense);
 }
@@ -0,0 +1,156 @@
+// Previsão de vendas a partir do histórico
+// src/handlers/forecast_handler.rs
+use actix_web::{get, http::StatusCode, web, HttpResponse, Responder};
+use chrono::Duration;
+use serde_json::json;
+use sqlx::query_as;
+
+use crate::{
+    db::DbPool,
+    errors::{bad_request, error_with, internal_error},
+    forecast::{self, Accuracy, MODELS},
+    models::forecast::{ForecastPoint, ForecastQuery, Granularity, Metric, SeriesPoint},
+};
+
+// Série contínua por período, com zero onde não houve venda, do início do
+// histórico (limitado a $2 períodos) até o último período completo.
+const SALES_SERIES: &str = r#"
+    WITH bounds AS (
+        SELECT
+            GREATEST(
+                date_trunc($1, MIN(created_at)),
+                date_trunc($1, NOW() AT TIME ZONE 'UTC') - $2 * ('1 ' || $1)::interval
+            ) AS first,
+            date_trunc($1, NOW() AT TIME ZONE 'UTC') - ('1 ' || $1)::interval AS last
+        FROM sales
+        WHERE deleted_at IS NULL AND ($3::uuid IS NULL OR product_id = $3)
+        HAVING COUNT(*) > 0
+    )
+    SELECT p::date AS period,
+        COALESCE(SUM(CASE WHEN $4 THEN s.quantity::float8 ELSE s.total_price END), 0) AS value
+    FROM bounds
+    CROSS JOIN generate_series(bounds.first, bounds.last, ('1 ' || $1)::interval) AS p
+    LEFT JOIN sales s
+        ON date_trunc($1, s.created_at) = p
+       AND s.deleted_at IS NULL
+       AND ($3::uuid IS NULL OR s.product_id = $3)
+    GROUP BY p
+    ORDER BY p
+"#;
+
+// GET /api/forecast/sales?horizon=14&granularity=day|week&metric=revenue|quantity
+//     &model=moving_average|holt_winters&level=95&product_id=
+#[get("/forecast/sales")]
+pub async fn forecast_sales(pool: web::Data<DbPool>, query: web::Query<ForecastQuery>) -> impl Responder {
+    // (unidade, temporada, horizonte padrão, horizonte máximo, histórico máximo, mínimo de pontos)
+    let (unit, season, default_horizon, max_horizon, max_history, min_points) = match query.granularity {
+        Granularity::Day => ("day", 7, 14, 180, 730, 14),
+        Granularity::Week => ("week", 52, 8, 52, 156, 8),
+    };
+
+    let horizon = query.horizon.unwrap_or(default_horizon);
+    if !(1..=max_horizon).contains(&horizon) {
+        return bad_request(format!("horizon deve estar entre 1 e {}", max_horizon));
+    }
+
+    let z = match query.level {
+        80 => 1.2816,
+        90 => 1.6449,
+        95 => 1.96,
+        99 => 2.5758,
+        _ => return bad_request("level deve ser 80, 90, 95 ou 99"),
+    };
+
+    let series = query_as::<_, SeriesPoint>(SALES_SERIES)
+        .bind(unit)
+        .bind(max_history)
+        .bind(query.product_id)
+        .bind(matches!(query.metric, Metric::Quantity))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    let series = match series {
+        Ok(series) => series,
+        Err(err) => return internal_error("Erro ao buscar histórico de vendas", err),
+    };
+
+    if series.len() < min_points {
+        return error_with(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Histórico insuficiente: são necessários ao menos {} períodos completos", min_points),
+            json!({ "history_points": series.len() }),
+        );
+    }
+
+    let values: Vec<f64> = series.iter().map(|p| p.value).collect();
+
+    // Backtest com os últimos períodos (até um quarto do histórico)
+    let holdout = horizon.min(values.len() / 4).max(1);
+    let backtest: Vec<Accuracy> = MODELS
+        .iter()
+        .filter_map(|&model| forecast::backtest(model, &values, season, holdout))
+        .collect();
+
+    let model = match query.model {
+        Some(model) => model,
+        None => match backtest.iter().min_by(|a, b| a.mae.total_cmp(&b.mae)) {
+            Some(best) => best.model,
+            None => forecast::Model::MovingAverage,
+        },
+    };
+
+    let fitted = match forecast::fit(model, &values, season, horizon) {
+        Some(fitted) => fitted,
+        None => {
+            return error_with(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Histórico insuficiente para o modelo escolhido",
+                json!({ "history_points": series.len() }),
+            )
+        }
+    };
+
+    let step = match query.granularity {
+        Granularity::Day => Duration::days(1),
+        Granularity::Week => Duration::weeks(1),
+    };
+    let last = series[series.len() - 1].period;
+
+    // Vendas não ficam negativas: previsão e limite inferior param em zero
+    let points: Vec<ForecastPoint> = fitted
+        .values
+        .iter()
+        .zip(&fitted.std_errors)
+        .zip(1..)
+        .map(|((value, std_error), h)| ForecastPoint {
+            period: last + step * h,
+            value: round2(value.max(0.0)),
+            lower: round2((value - z * std_error).max(0.0)),
+            upper: round2((value + z * std_error).max(0.0)),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "granularity": query.granularity,
+        "metric": query.metric,
+        "product_id": query.product_id,
+        "model": model,
+        "level": query.level,
+        "horizon": horizon,
+        "history": series,
+        "forecast": points,
+        "backtest": {
+            "holdout": holdout,
+            "results": backtest,
+        },
+    }))
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// Configura as rotas
+pub fn config_forecast(cfg: &mut web::ServiceConfig) {
+    cfg.service(forecast_sales);
+}
@@ -0,0 +1,94 @@
+// Handler de saúde, prontidão e métricas
+// src/handlers/health_handler.rs
+use actix_web::{get, web, HttpResponse, Responder};
+use serde_json::json;
+
+use crate::{config::Config, db::{self, DbPool}, errors::internal_error, metrics::Metrics};
+
+/// GET /health - o processo está no ar
+#[get("/health")]
+pub async fn health() -> impl Responder {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+/// GET /ready - banco acessível e migrações aplicadas
+#[get("/ready")]
+pub async fn ready(pool: web::Data<DbPool>) -> impl Responder {
+    let database = sqlx::query("SELECT 1").execute(pool.get_ref()).await;
+
+    let (database_check, migrations_check, is_ready) = match database {
+        Ok(_) => match db::pending_migrations(pool.get_ref()).await {
+            Ok(pending) if pending.is_empty() => (json!("ok"), json!("ok"), true),
+            Ok(pending) => (json!("ok"), json!({ "pending": pending }), false),
+            Err(err) => {
+                log::error!("Erro ao verificar migrações: {:?}", err);
+                (json!("ok"), json!("error"), false)
+            }
+        },
+        Err(err) => {
+            log::error!("Banco de dados indisponível: {:?}", err);
+            (json!("unreachable"), json!("unknown"), false)
+        }
+    };
+
+    let body = json!({
+        "status": if is_ready { "ready" } else { "not_ready" },
+        "checks": {
+            "database": database_check,
+            "migrations": migrations_check,
+        }
+    });
+
+    if is_ready {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+/// GET /metrics - métricas no formato Prometheus
+#[get("/metrics")]
+pub async fn metrics(
+    pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
+) -> impl Responder {
+    metrics.observe_pool(pool.get_ref(), config.database.max_connections);
+
+    let payables = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(SUM(amount), 0) AS "total!",
+            COUNT(*) AS "count!",
+            COUNT(*) FILTER (WHERE due_date < CURRENT_DATE) AS "overdue!"
+        FROM expenses
+        WHERE paid = FALSE AND deleted_at IS NULL
+        "#
+    )
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match payables {
+        Ok(row) => {
+            metrics.open_payables_total.set(row.total);
+            metrics.open_payables_count.set(row.count);
+            metrics.overdue_payables_count.set(row.overdue);
+        }
+        // Sem banco ainda expomos as métricas HTTP e do pool
+        Err(err) => log::error!("Erro ao calcular contas a pagar para métricas: {:?}", err),
+    }
+
+    match metrics.render() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body),
+        Err(err) => internal_error("Erro ao serializar métricas", err),
+    }
+}
+
+/// Configura as rotas de observabilidade (fora de /api)
+pub fn config_health(cfg: &mut web::ServiceConfig) {
+    cfg.service(health)
+        .service(ready)
+        .service(metrics);
+}
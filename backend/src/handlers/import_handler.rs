@@ -0,0 +1,562 @@
+// Importação em lote via CSV
+// src/handlers/import_handler.rs
+//
+// POST /api/import/{products|clients|suppliers|expenses} (multipart):
+//   - `file`: o CSV, com cabeçalho (separador `,` ou `;`, detectado sozinho);
+//   - `mapping` (opcional): JSON `{ "campo": "Coluna do CSV" }`; sem ele, as
+//     colunas devem ter o nome dos campos da API.
+// Com `?dry_run=true` nada é gravado e a resposta traz os erros por linha.
+// Sem dry run, qualquer erro cancela a importação inteira.
+use actix_multipart::Multipart;
+use actix_web::{http::StatusCode, post, web, HttpResponse, Responder};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::{query_as, PgConnection};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, Change},
+    auth::{AuthUser, OptionalUser},
+    db::DbPool,
+    duplicates,
+    errors::{bad_request, error_with, internal_error},
+    ledger,
+    models::{
+        client::Client,
+        expense::{CreateExpense, Expense},
+        product::{CreateProduct, Product},
+        supplier::Supplier,
+    },
+    parsing::{detect_delimiter, parse_date, parse_decimal},
+    periods,
+    request_id,
+    schema::{CreateClient, CreateSupplier, ImportParams},
+    upload,
+};
+
+const MAX_ROWS: usize = 5000;
+
+#[derive(Clone, Copy)]
+enum Entity {
+    Products,
+    Clients,
+    Suppliers,
+    Expenses,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    OptionalText,
+    Decimal,
+    OptionalDecimal,
+    Integer,
+    Date,
+    OptionalUuid,
+}
+
+struct FieldSpec {
+    name: &'static str,
+    kind: Kind,
+}
+
+const fn field(name: &'static str, kind: Kind) -> FieldSpec {
+    FieldSpec { name, kind }
+}
+
+const PRODUCT_FIELDS: &[FieldSpec] = &[
+    field("name", Kind::Text),
+    field("description", Kind::OptionalText),
+    field("price", Kind::Decimal),
+    field("stock", Kind::Integer),
+    field("cost", Kind::OptionalDecimal),
+];
+const CLIENT_FIELDS: &[FieldSpec] = &[
+    field("name", Kind::Text),
+    field("email", Kind::Text),
+    field("phone", Kind::Text),
+    field("document", Kind::OptionalText),
+];
+const SUPPLIER_FIELDS: &[FieldSpec] = &[
+    field("name", Kind::Text),
+    field("email", Kind::OptionalText),
+    field("phone", Kind::OptionalText),
+    field("document", Kind::OptionalText),
+];
+const EXPENSE_FIELDS: &[FieldSpec] = &[
+    field("description", Kind::Text),
+    field("category", Kind::OptionalText),
+    field("supplier_id", Kind::OptionalUuid),
+    field("amount", Kind::Decimal),
+    field("due_date", Kind::Date),
+];
+
+impl Entity {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "products" => Some(Entity::Products),
+            "clients" => Some(Entity::Clients),
+            "suppliers" => Some(Entity::Suppliers),
+            "expenses" => Some(Entity::Expenses),
+            _ => None,
+        }
+    }
+
+    fn fields(self) -> &'static [FieldSpec] {
+        match self {
+            Entity::Products => PRODUCT_FIELDS,
+            Entity::Clients => CLIENT_FIELDS,
+            Entity::Suppliers => SUPPLIER_FIELDS,
+            Entity::Expenses => EXPENSE_FIELDS,
+        }
+    }
+}
+
+/// Linha já convertida para o mesmo formato aceito pelos endpoints de criação.
+enum NewRecord {
+    Product(CreateProduct),
+    Client(CreateClient),
+    Supplier(CreateSupplier),
+    Expense(CreateExpense),
+}
+
+struct ParsedRow {
+    line: u64,
+    record: NewRecord,
+}
+
+#[derive(Serialize)]
+struct RowError {
+    line: u64,
+    field: Option<&'static str>,
+    message: String,
+}
+
+struct Upload {
+    file: Vec<u8>,
+    mapping: HashMap<String, String>,
+}
+
+// POST /api/import/{entity}
+#[post("/import/{entity}")]
+pub async fn import_csv(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+    params: web::Query<ImportParams>,
+    payload: Multipart,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let entity = match Entity::parse(&path) {
+        Some(entity) => entity,
+        None => return bad_request("Entidade inválida (use products, clients, suppliers ou expenses)"),
+    };
+
+    let delimiter = match params.delimiter.as_deref() {
+        None => None,
+        Some(d) if d.len() == 1 && d.is_ascii() => Some(d.as_bytes()[0]),
+        Some(_) => return bad_request("delimiter deve ser um único caractere"),
+    };
+
+    let upload = match read_upload(payload).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+
+    let (rows, mut errors) = match parse_csv(entity, &upload, delimiter) {
+        Ok(parsed) => parsed,
+        Err(response) => return response,
+    };
+    let total_rows = rows.len() + errors.iter().map(|e| e.line).collect::<HashSet<_>>().len();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    match missing_suppliers(&mut tx, &rows).await {
+        Ok(missing) => errors.extend(missing),
+        Err(err) => return internal_error("Erro ao validar fornecedores", err),
+    }
+    match locked_expenses(&mut tx, &rows).await {
+        Ok(locked) => errors.extend(locked),
+        Err(err) => return internal_error("Erro ao verificar período contábil", err),
+    }
+    errors.sort_by_key(|e| e.line);
+
+    if params.dry_run || !errors.is_empty() {
+        let invalid_lines = errors.iter().map(|e| e.line).collect::<HashSet<_>>().len();
+        let mut report = json!({
+            "dry_run": params.dry_run,
+            "total_rows": total_rows,
+            "valid_rows": total_rows - invalid_lines,
+            "errors": errors,
+        });
+        if !params.dry_run {
+            let message = format!("Importação cancelada: {} linha(s) com erro", invalid_lines);
+            return error_with(StatusCode::UNPROCESSABLE_ENTITY, message, report);
+        }
+
+        report["status"] = json!(if errors.is_empty() { "success" } else { "error" });
+        report["request_id"] = json!(request_id::current());
+        return HttpResponse::Ok().json(report);
+    }
+
+    let mut ids = Vec::with_capacity(rows.len());
+    for row in &rows {
+        match insert_row(&mut tx, user.as_ref(), &row.record).await {
+            Ok(id) => ids.push(id),
+            Err(err) => {
+                return internal_error(&format!("Erro ao importar a linha {}", row.line), err)
+            }
+        }
+    }
+
+    match tx.commit().await {
+        Ok(_) => {
+            log::info!("Importação concluída: {} registros em /import/{}", ids.len(), path);
+            HttpResponse::Created().json(json!({
+                "status": "success",
+                "imported": ids.len(),
+                "ids": ids,
+            }))
+        }
+        Err(err) => internal_error("Erro ao concluir importação", err),
+    }
+}
+
+async fn read_upload(payload: Multipart) -> Result<Upload, HttpResponse> {
+    let mut file = None;
+    let mut mapping = HashMap::new();
+
+    for (name, data) in upload::read_fields(payload).await? {
+        match name.as_str() {
+            "file" => file = Some(data),
+            "mapping" => {
+                mapping = serde_json::from_slice(&data).map_err(|err| {
+                    bad_request(format!(
+                        "mapping deve ser um objeto JSON {{\"campo\": \"coluna\"}}: {}",
+                        err
+                    ))
+                })?;
+            }
+            _ => {}
+        }
+    }
+
+    match file {
+        Some(file) => Ok(Upload { file, mapping }),
+        None => Err(bad_request("Envie o CSV no campo 'file'")),
+    }
+}
+
+/// Converte o CSV em registros de criação. Erros de estrutura (cabeçalho,
+/// mapeamento, codificação) encerram com 400; erros de conteúdo são
+/// acumulados por linha.
+fn parse_csv(
+    entity: Entity,
+    upload: &Upload,
+    delimiter: Option<u8>,
+) -> Result<(Vec<ParsedRow>, Vec<RowError>), HttpResponse> {
+    let text = std::str::from_utf8(&upload.file)
+        .map_err(|_| bad_request("O arquivo deve estar em UTF-8"))?;
+    let text = text.trim_start_matches('\u{feff}');
+    let delimiter = delimiter.unwrap_or_else(|| detect_delimiter(text));
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|err| bad_request(format!("Cabeçalho inválido: {}", err)))?
+        .clone();
+
+    let fields = entity.fields();
+    for key in upload.mapping.keys() {
+        if !fields.iter().any(|f| f.name == key) {
+            return Err(bad_request(format!("mapping: campo desconhecido '{}'", key)));
+        }
+    }
+
+    // Índice da coluna de cada campo
+    let mut columns = Vec::with_capacity(fields.len());
+    for spec in fields {
+        let column = upload.mapping.get(spec.name).map(String::as_str).unwrap_or(spec.name);
+        let index = headers.iter().position(|h| h.eq_ignore_ascii_case(column));
+        match (index, spec.kind) {
+            (None, Kind::OptionalText | Kind::OptionalDecimal | Kind::OptionalUuid) => {}
+            (None, _) => {
+                return Err(bad_request(format!(
+                    "Coluna '{}' (campo {}) não encontrada no cabeçalho",
+                    column, spec.name
+                )))
+            }
+            _ => {}
+        }
+        columns.push(index);
+    }
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+
+    for (position, result) in reader.records().enumerate() {
+        if position >= MAX_ROWS {
+            return Err(bad_request(format!("O arquivo excede o limite de {} linhas", MAX_ROWS)));
+        }
+
+        let record = match result {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map(|p| p.line()).unwrap_or(position as u64 + 2);
+                errors.push(RowError { line, field: None, message: format!("Linha ilegível: {}", err) });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(position as u64 + 2);
+
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        let mut object = Map::new();
+        let mut row_errors = Vec::new();
+        for (spec, index) in fields.iter().zip(&columns) {
+            let raw = index.and_then(|i| record.get(i)).unwrap_or("");
+            match convert(spec.kind, raw) {
+                Ok(value) => {
+                    object.insert(spec.name.to_string(), value);
+                }
+                Err(message) => row_errors.push(RowError { line, field: Some(spec.name), message }),
+            }
+        }
+
+        if !row_errors.is_empty() {
+            errors.extend(row_errors);
+            continue;
+        }
+
+        match build_record(entity, Value::Object(object)) {
+            Ok(record) => rows.push(ParsedRow { line, record }),
+            Err((field, message)) => errors.push(RowError { line, field, message }),
+        }
+    }
+
+    Ok((rows, errors))
+}
+
+/// Converte o texto da célula no valor JSON esperado pelo campo, aceitando
+/// o formato brasileiro (`1.234,56`, `31/12/2024`).
+fn convert(kind: Kind, raw: &str) -> Result<Value, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return match kind {
+            Kind::OptionalText | Kind::OptionalDecimal | Kind::OptionalUuid => Ok(Value::Null),
+            _ => Err("campo obrigatório".to_string()),
+        };
+    }
+
+    match kind {
+        Kind::Text | Kind::OptionalText => Ok(Value::String(raw.to_string())),
+        Kind::Decimal | Kind::OptionalDecimal => parse_decimal(raw)
+            .and_then(|n| serde_json::Number::from_f64(n).map(Value::Number))
+            .ok_or_else(|| format!("valor numérico inválido '{}'", raw)),
+        Kind::Integer => raw
+            .parse::<i32>()
+            .map(|n| json!(n))
+            .map_err(|_| format!("número inteiro inválido '{}'", raw)),
+        Kind::Date => parse_date(raw)
+            .map(|d| Value::String(d.format("%Y-%m-%d").to_string()))
+            .ok_or_else(|| format!("data inválida '{}' (use dd/mm/aaaa ou aaaa-mm-dd)", raw)),
+        Kind::OptionalUuid => Uuid::parse_str(raw)
+            .map(|id| Value::String(id.to_string()))
+            .map_err(|_| format!("UUID inválido '{}'", raw)),
+    }
+}
+
+/// Desserializa a linha nos mesmos structs dos endpoints de criação e
+/// aplica as regras básicas de cada cadastro.
+fn build_record(entity: Entity, value: Value) -> Result<NewRecord, (Option<&'static str>, String)> {
+    let invalid = |err: serde_json::Error| (None, format!("linha inválida: {}", err));
+
+    match entity {
+        Entity::Products => {
+            let product: CreateProduct = serde_json::from_value(value).map_err(invalid)?;
+            if product.price < 0.0 {
+                return Err((Some("price"), "o preço não pode ser negativo".to_string()));
+            }
+            if product.stock < 0 {
+                return Err((Some("stock"), "o estoque não pode ser negativo".to_string()));
+            }
+            if product.cost.is_some_and(|cost| cost < 0.0) {
+                return Err((Some("cost"), "o custo não pode ser negativo".to_string()));
+            }
+            Ok(NewRecord::Product(product))
+        }
+        Entity::Clients => {
+            let mut client: CreateClient = serde_json::from_value(value).map_err(invalid)?;
+            if !client.email.contains('@') {
+                return Err((Some("email"), format!("e-mail inválido '{}'", client.email)));
+            }
+            client.document = duplicates::document_input(client.document.as_deref()).map_err(|err| (Some("document"), err))?;
+            Ok(NewRecord::Client(client))
+        }
+        Entity::Suppliers => {
+            let mut supplier: CreateSupplier = serde_json::from_value(value).map_err(invalid)?;
+            if let Some(email) = supplier.email.as_deref().filter(|e| !e.contains('@')) {
+                return Err((Some("email"), format!("e-mail inválido '{}'", email)));
+            }
+            supplier.document = duplicates::document_input(supplier.document.as_deref()).map_err(|err| (Some("document"), err))?;
+            Ok(NewRecord::Supplier(supplier))
+        }
+        Entity::Expenses => {
+            let expense: CreateExpense = serde_json::from_value(value).map_err(invalid)?;
+            if expense.amount <= 0.0 {
+                return Err((Some("amount"), "o valor deve ser maior que zero".to_string()));
+            }
+            Ok(NewRecord::Expense(expense))
+        }
+    }
+}
+
+/// Despesas que apontam para fornecedores inexistentes.
+async fn missing_suppliers(
+    conn: &mut PgConnection,
+    rows: &[ParsedRow],
+) -> Result<Vec<RowError>, sqlx::Error> {
+    let referenced: Vec<Uuid> = rows
+        .iter()
+        .filter_map(|row| match &row.record {
+            NewRecord::Expense(expense) => expense.supplier_id,
+            _ => None,
+        })
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if referenced.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let existing: HashSet<Uuid> = sqlx::query_scalar("SELECT id FROM suppliers WHERE id = ANY($1)")
+        .bind(&referenced)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| match &row.record {
+            NewRecord::Expense(CreateExpense { supplier_id: Some(id), .. }) if !existing.contains(id) => {
+                Some(RowError {
+                    line: row.line,
+                    field: Some("supplier_id"),
+                    message: format!("fornecedor {} não encontrado", id),
+                })
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Despesas com vencimento em período contábil fechado.
+async fn locked_expenses(
+    conn: &mut PgConnection,
+    rows: &[ParsedRow],
+) -> Result<Vec<RowError>, sqlx::Error> {
+    let until = match periods::closed_until(conn).await? {
+        Some(until) => until,
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| match &row.record {
+            NewRecord::Expense(expense) if expense.due_date <= until => Some(RowError {
+                line: row.line,
+                field: Some("due_date"),
+                message: format!("o período {} está fechado", expense.due_date.format("%m/%Y")),
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+async fn insert_row(
+    conn: &mut PgConnection,
+    user: Option<&AuthUser>,
+    record: &NewRecord,
+) -> Result<Uuid, sqlx::Error> {
+    let id = Uuid::new_v4();
+
+    match record {
+        NewRecord::Product(product) => {
+            let created = query_as::<_, Product>(
+                "INSERT INTO products (id, name, description, price, stock, cost) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
+            )
+            .bind(id)
+            .bind(&product.name)
+            .bind(&product.description)
+            .bind(product.price)
+            .bind(product.stock)
+            .bind(product.cost)
+            .fetch_one(&mut *conn)
+            .await?;
+            audit::record(conn, user, "product", id, Change::Created(&created)).await?;
+            ledger::post_stock(conn, user, &created, created.stock).await?;
+        }
+        NewRecord::Client(client) => {
+            let created = query_as::<_, Client>(
+                "INSERT INTO clients (id, name, email, phone, document) VALUES ($1, $2, $3, $4, $5) RETURNING id, name, email, phone, document, version"
+            )
+            .bind(id)
+            .bind(&client.name)
+            .bind(&client.email)
+            .bind(&client.phone)
+            .bind(&client.document)
+            .fetch_one(&mut *conn)
+            .await?;
+            audit::record(conn, user, "client", id, Change::Created(&created)).await?;
+        }
+        NewRecord::Supplier(supplier) => {
+            let created = query_as::<_, Supplier>(
+                "INSERT INTO suppliers (id, name, email, phone, document) VALUES ($1, $2, $3, $4, $5) RETURNING *"
+            )
+            .bind(id)
+            .bind(&supplier.name)
+            .bind(&supplier.email)
+            .bind(&supplier.phone)
+            .bind(&supplier.document)
+            .fetch_one(&mut *conn)
+            .await?;
+            audit::record(conn, user, "supplier", id, Change::Created(&created)).await?;
+        }
+        NewRecord::Expense(expense) => {
+            let created = query_as::<_, Expense>(
+                r#"
+                INSERT INTO expenses (id, description, category, supplier_id, amount, due_date, paid, created_at)
+                VALUES ($1, $2, COALESCE(NULLIF(TRIM($3), ''), 'Sem categoria'), $4, $5, $6, false, NOW())
+                RETURNING *
+                "#
+            )
+            .bind(id)
+            .bind(&expense.description)
+            .bind(&expense.category)
+            .bind(expense.supplier_id)
+            .bind(expense.amount)
+            .bind(expense.due_date)
+            .fetch_one(&mut *conn)
+            .await?;
+            audit::record(conn, user, "expense", id, Change::Created(&created)).await?;
+            ledger::sync(conn, user, "expense", id, ledger::expense_entry(&created)).await?;
+        }
+    }
+
+    Ok(id)
+}
+
+pub fn config_import(cfg: &mut web::ServiceConfig) {
+    cfg.service(import_csv);
+}
@@ -0,0 +1,73 @@
+// Anomalias nas despesas: valores fora do habitual, duplicidades e crescimento
+// src/handlers/insights_handler.rs
+use actix_web::{get, web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::query_as;
+
+use crate::{
+    anomalies,
+    db::DbPool,
+    errors::{bad_request, internal_error},
+    models::insight::{AnomalyQuery, ExpenseRecord, Severity},
+};
+
+// GET /api/insights/anomalies?months=12&duplicate_days=7&growth_threshold=50&min_severity=medium
+#[get("/insights/anomalies")]
+pub async fn get_anomalies(pool: web::Data<DbPool>, query: web::Query<AnomalyQuery>) -> impl Responder {
+    if !(1..=60).contains(&query.months) {
+        return bad_request("months deve estar entre 1 e 60");
+    }
+    if !(0..=90).contains(&query.duplicate_days) {
+        return bad_request("duplicate_days deve estar entre 0 e 90");
+    }
+    if !(query.growth_threshold.is_finite() && query.growth_threshold > 0.0) {
+        return bad_request("growth_threshold deve ser um percentual positivo");
+    }
+
+    // Do primeiro dia da janela em diante, incluindo vencimentos futuros
+    let expenses = query_as::<_, ExpenseRecord>(
+        r#"
+        SELECT e.id, e.description, e.category, e.supplier_id, s.name AS supplier_name, e.amount, e.due_date
+        FROM expenses e
+        LEFT JOIN suppliers s ON s.id = e.supplier_id
+        WHERE e.deleted_at IS NULL
+          AND e.due_date >= date_trunc('month', CURRENT_DATE) - make_interval(months => $1 - 1)
+        "#
+    )
+    .bind(query.months)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let expenses = match expenses {
+        Ok(expenses) => expenses,
+        Err(err) => return internal_error("Erro ao buscar despesas", err),
+    };
+
+    let mut found = anomalies::outliers(&expenses);
+    found.extend(anomalies::duplicates(&expenses, query.duplicate_days));
+    found.extend(anomalies::growth(&expenses, query.growth_threshold));
+
+    if let Some(min) = query.min_severity {
+        found.retain(|anomaly| anomaly.severity >= min);
+    }
+    found.sort_by(|a, b| b.severity.cmp(&a.severity).then(b.amount.total_cmp(&a.amount)));
+
+    let count = |severity: Severity| found.iter().filter(|a| a.severity == severity).count();
+
+    HttpResponse::Ok().json(json!({
+        "months": query.months,
+        "analyzed_expenses": expenses.len(),
+        "count": found.len(),
+        "by_severity": {
+            "high": count(Severity::High),
+            "medium": count(Severity::Medium),
+            "low": count(Severity::Low),
+        },
+        "anomalies": found,
+    }))
+}
+
+// Configura as rotas
+pub fn config_insights(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_anomalies);
+}
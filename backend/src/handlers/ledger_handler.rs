@@ -0,0 +1,519 @@
+// Contabilidade: plano de contas, lançamentos, balancete e razão
+// src/handlers/ledger_handler.rs
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::Local;
+use serde_json::json;
+use sqlx::query_as;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    auth::AdminUser,
+    db::DbPool,
+    errors::{bad_request, conflict, internal_error, is_unique_violation, not_found, unprocessable},
+    export::{self, ExportParams},
+    handlers::sale_handler::SALE_SELECT,
+    ledger::{self, NewEntry, Posting, Target},
+    models::{
+        bank::Account,
+        expense::Expense,
+        ledger::{
+            AccountLedgerQuery, CreateJournalEntry, CreateLedgerAccount, JournalEntry, JournalExportLine, JournalQuery,
+            LedgerAccount, LedgerLine, TrialBalanceQuery, TrialBalanceRow, LEDGER_ACCOUNT_KINDS,
+        },
+        payment::Payment,
+        product::Product,
+        sale::Sale,
+        transfer::Transfer,
+    },
+    periods,
+};
+
+const DEFAULT_LIMIT: i64 = 200;
+const MAX_LIMIT: i64 = 2000;
+
+// Saldo pela natureza da conta: devedora (ativo, despesa) ou credora
+pub(crate) const NATURAL_BALANCE: &str = "CASE WHEN la.kind IN ('asset', 'expense') THEN 1 ELSE -1 END";
+
+// GET /api/ledger/accounts - plano de contas
+#[get("/ledger/accounts")]
+pub async fn get_ledger_accounts(pool: web::Data<DbPool>) -> impl Responder {
+    let accounts = query_as::<_, LedgerAccount>("SELECT * FROM ledger_accounts ORDER BY code")
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match accounts {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar plano de contas", err),
+    }
+}
+
+// POST /api/ledger/accounts (somente admin)
+#[post("/ledger/accounts")]
+pub async fn create_ledger_account(
+    pool: web::Data<DbPool>,
+    account: web::Json<CreateLedgerAccount>,
+    AdminUser(_): AdminUser,
+) -> impl Responder {
+    if account.code.trim().is_empty() || account.name.trim().is_empty() {
+        return bad_request("Informe o código e o nome da conta");
+    }
+    if !LEDGER_ACCOUNT_KINDS.contains(&account.kind.as_str()) {
+        return bad_request("kind deve ser asset, liability, equity, revenue ou expense");
+    }
+
+    let created = query_as::<_, LedgerAccount>(
+        "INSERT INTO ledger_accounts (id, code, name, kind) VALUES ($1, $2, $3, $4) RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(account.code.trim())
+    .bind(account.name.trim())
+    .bind(&account.kind)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match created {
+        Ok(account) => HttpResponse::Created().json(account),
+        Err(err) if is_unique_violation(&err) => {
+            conflict("Já existe uma conta com este código")
+        }
+        Err(err) => internal_error("Erro ao criar conta contábil", err),
+    }
+}
+
+// Lançamentos do filtro de /ledger/entries, mais recentes primeiro
+const JOURNAL_FILTER: &str = r#"
+    ($1::date IS NULL OR e.entry_date >= $1)
+    AND ($2::date IS NULL OR e.entry_date <= $2)
+    AND ($3::text IS NULL OR e.source = $3)
+    AND ($4::uuid IS NULL OR e.source_id = $4)
+"#;
+
+// GET /api/ledger/entries?start_date=&end_date=&source=sale&source_id=&limit=
+//
+// Na exportação CSV/XLSX, cada partida é uma linha.
+#[get("/ledger/entries")]
+pub async fn get_journal_entries(
+    pool: web::Data<DbPool>,
+    query: web::Query<JournalQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("lancamentos");
+            let sql = format!(
+                r#"
+                SELECT e.id AS entry_id, e.entry_date, e.description, e.source, e.source_id,
+                    la.code, la.name, l.debit, l.credit
+                FROM (
+                    SELECT * FROM journal_entries e
+                    WHERE {}
+                    ORDER BY e.entry_date DESC, e.created_at DESC
+                    LIMIT $5
+                ) e
+                JOIN journal_lines l ON l.entry_id = e.id
+                JOIN ledger_accounts la ON la.id = l.ledger_account_id
+                ORDER BY e.entry_date DESC, e.created_at DESC, l.id
+                "#,
+                JOURNAL_FILTER
+            );
+            let lines = query_as::<_, JournalExportLine>(&sql)
+                .bind(query.start_date)
+                .bind(query.end_date)
+                .bind(query.source.as_deref())
+                .bind(query.source_id)
+                .bind(limit)
+                .fetch_all(pool.get_ref())
+                .await;
+            return match lines {
+                Ok(lines) => export::respond_rows(pool.get_ref(), format, export.locale, &filename, lines).await,
+                Err(err) => internal_error("Erro ao buscar lançamentos", err),
+            };
+        }
+        Ok(None) => {}
+    }
+
+    let entries = query_as::<_, JournalEntry>(&format!(
+        r#"
+        SELECT e.*,
+            (SELECT json_agg(json_build_object(
+                    'ledger_account_id', l.ledger_account_id,
+                    'code', la.code,
+                    'name', la.name,
+                    'debit', l.debit,
+                    'credit', l.credit) ORDER BY l.id)
+             FROM journal_lines l JOIN ledger_accounts la ON la.id = l.ledger_account_id
+             WHERE l.entry_id = e.id) AS lines
+        FROM journal_entries e
+        WHERE {}
+        ORDER BY e.entry_date DESC, e.created_at DESC
+        LIMIT $5
+        "#,
+        JOURNAL_FILTER
+    ))
+    .bind(query.start_date)
+    .bind(query.end_date)
+    .bind(query.source.as_deref())
+    .bind(query.source_id)
+    .bind(limit)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match entries {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar lançamentos", err),
+    }
+}
+
+// POST /api/ledger/entries - lançamento manual (somente admin)
+#[post("/ledger/entries")]
+pub async fn create_journal_entry(
+    pool: web::Data<DbPool>,
+    entry: web::Json<CreateJournalEntry>,
+    AdminUser(admin): AdminUser,
+) -> impl Responder {
+    let entry = entry.into_inner();
+    if entry.description.trim().is_empty() {
+        return bad_request("Informe o histórico do lançamento");
+    }
+
+    let lines: Vec<Posting> = entry
+        .lines
+        .iter()
+        .map(|line| Posting {
+            target: Target::Id(line.ledger_account_id),
+            debit: line.debit,
+            credit: line.credit,
+        })
+        .collect();
+
+    if let Err(message) = ledger::validate(&lines) {
+        return unprocessable(message);
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[entry.entry_date]).await {
+        return res;
+    }
+
+    let new_entry = NewEntry {
+        date: entry.entry_date,
+        description: entry.description.trim().to_string(),
+        lines,
+    };
+    let id = match ledger::post(&mut tx, Some(&admin), "manual", None, new_entry).await {
+        Ok(id) => id,
+        Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("23503") => {
+            return bad_request("Conta contábil não encontrada");
+        }
+        Err(err) => return internal_error("Erro ao gravar lançamento", err),
+    };
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().json(id),
+        Err(err) => internal_error("Erro ao gravar lançamento", err),
+    }
+}
+
+// GET /api/ledger/trial-balance?as_of=2024-12-31 - balancete de verificação
+#[get("/ledger/trial-balance")]
+pub async fn get_trial_balance(
+    pool: web::Data<DbPool>,
+    query: web::Query<TrialBalanceQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let format = match export.file_format(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+    let as_of = query.as_of.unwrap_or_else(|| Local::now().date_naive());
+
+    let rows = query_as::<_, TrialBalanceRow>(&format!(
+        r#"
+        WITH snapshot AS (
+            -- Fechamento mais recente encerrado até a data: seus saldos
+            -- substituem a soma de todos os lançamentos anteriores
+            SELECT id, (month + INTERVAL '1 month')::date AS next_day
+            FROM fiscal_periods
+            WHERE status = 'closed' AND (month + INTERVAL '1 month')::date <= $1 + 1
+            ORDER BY month DESC
+            LIMIT 1
+        ),
+        movements AS (
+            SELECT pb.ledger_account_id, pb.debit, pb.credit
+            FROM period_balances pb JOIN snapshot s ON s.id = pb.period_id
+            UNION ALL
+            SELECT l.ledger_account_id, l.debit, l.credit
+            FROM journal_lines l
+            JOIN journal_entries e ON e.id = l.entry_id
+            WHERE e.entry_date <= $1
+              AND e.entry_date >= COALESCE((SELECT next_day FROM snapshot), '-infinity'::date)
+        )
+        SELECT la.id AS ledger_account_id, la.code, la.name, la.kind,
+            SUM(m.debit) AS debit,
+            SUM(m.credit) AS credit,
+            {} * (SUM(m.debit) - SUM(m.credit)) AS balance
+        FROM movements m
+        JOIN ledger_accounts la ON la.id = m.ledger_account_id
+        GROUP BY la.id
+        ORDER BY la.code
+        "#,
+        NATURAL_BALANCE
+    ))
+    .bind(as_of)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            if let Some(format) = format {
+                let filename = export::filename("balancete");
+                return export::respond_rows(pool.get_ref(), format, export.locale, &filename, rows).await;
+            }
+            let total_debit: f64 = rows.iter().map(|r| r.debit).sum();
+            let total_credit: f64 = rows.iter().map(|r| r.credit).sum();
+            HttpResponse::Ok().json(json!({
+                "as_of": as_of,
+                "accounts": rows,
+                "total_debit": total_debit,
+                "total_credit": total_credit,
+                "balanced": (total_debit - total_credit).abs() < 0.005,
+            }))
+        }
+        Err(err) => internal_error("Erro ao calcular balancete", err),
+    }
+}
+
+// GET /api/ledger/accounts/{id}/entries?start_date=&end_date= - razão da conta
+#[get("/ledger/accounts/{id}/entries")]
+pub async fn get_account_ledger(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    query: web::Query<AccountLedgerQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    if let (Some(start), Some(end)) = (query.start_date, query.end_date) {
+        if start > end {
+            return bad_request("start_date deve ser anterior a end_date");
+        }
+    }
+
+    let account = query_as::<_, LedgerAccount>("SELECT * FROM ledger_accounts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    let account = match account {
+        Ok(Some(account)) => account,
+        Ok(None) => return not_found("Conta contábil não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar conta contábil", err),
+    };
+
+    // Saldo anterior ao período (zero sem start_date)
+    let opening = sqlx::query_scalar::<_, f64>(&format!(
+        r#"
+        SELECT COALESCE(SUM({} * (l.debit - l.credit)), 0)
+        FROM journal_lines l
+        JOIN journal_entries e ON e.id = l.entry_id
+        JOIN ledger_accounts la ON la.id = l.ledger_account_id
+        WHERE l.ledger_account_id = $1 AND e.entry_date < $2
+        "#,
+        NATURAL_BALANCE
+    ))
+    .bind(id)
+    .bind(query.start_date)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let lines = query_as::<_, LedgerLine>(&format!(
+        r#"
+        SELECT e.id AS entry_id, e.entry_date, e.description, e.source, e.source_id, l.debit, l.credit,
+            {} * SUM(l.debit - l.credit) OVER (ORDER BY e.entry_date, e.created_at, l.id) AS balance
+        FROM journal_lines l
+        JOIN journal_entries e ON e.id = l.entry_id
+        JOIN ledger_accounts la ON la.id = l.ledger_account_id
+        WHERE l.ledger_account_id = $1
+          AND ($2::date IS NULL OR e.entry_date >= $2)
+          AND ($3::date IS NULL OR e.entry_date <= $3)
+        ORDER BY e.entry_date, e.created_at, l.id
+        "#,
+        NATURAL_BALANCE
+    ))
+    .bind(id)
+    .bind(query.start_date)
+    .bind(query.end_date)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match (lines, opening) {
+        (Ok(mut lines), Ok(opening)) => {
+            for line in &mut lines {
+                line.balance += opening;
+            }
+            let closing = lines.last().map(|l| l.balance).unwrap_or(opening);
+            HttpResponse::Ok().json(json!({
+                "account": account,
+                "start_date": query.start_date,
+                "end_date": query.end_date,
+                "opening_balance": opening,
+                "closing_balance": closing,
+                "lines": lines,
+            }))
+        }
+        (Err(err), _) | (_, Err(err)) => internal_error("Erro ao buscar razão da conta", err),
+    }
+}
+
+// POST /api/ledger/rebuild - gera os lançamentos que faltam para registros
+// anteriores à contabilidade (somente admin; pode ser repetido)
+#[post("/ledger/rebuild")]
+pub async fn rebuild_ledger(pool: web::Data<DbPool>, AdminUser(admin): AdminUser) -> impl Responder {
+    let user = Some(&admin);
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let missing = |source: &str, alias: &str| {
+        format!(
+            "NOT EXISTS (SELECT 1 FROM journal_entries j WHERE j.source = '{}' AND j.source_id = {}.id)",
+            source, alias
+        )
+    };
+
+    let accounts = query_as::<_, Account>(&format!("SELECT * FROM accounts a WHERE {}", missing("account", "a")))
+        .fetch_all(&mut tx)
+        .await;
+    let sales = query_as::<_, Sale>(&format!("{} WHERE s.deleted_at IS NULL AND {}", SALE_SELECT, missing("sale", "s")))
+        .fetch_all(&mut tx)
+        .await;
+    let expenses = query_as::<_, Expense>(&format!("SELECT * FROM expenses e WHERE deleted_at IS NULL AND {}", missing("expense", "e")))
+        .fetch_all(&mut tx)
+        .await;
+    let payments = query_as::<_, Payment>(&format!("SELECT * FROM payments p WHERE deleted_at IS NULL AND {}", missing("payment", "p")))
+        .fetch_all(&mut tx)
+        .await;
+    let transfers = query_as::<_, Transfer>(&format!("SELECT * FROM transfers t WHERE deleted_at IS NULL AND {}", missing("transfer", "t")))
+        .fetch_all(&mut tx)
+        .await;
+    let products = query_as::<_, Product>(&format!("SELECT * FROM products p WHERE {}", missing("stock", "p")))
+        .fetch_all(&mut tx)
+        .await;
+
+    let (accounts, sales, expenses, payments, transfers, products) =
+        match (accounts, sales, expenses, payments, transfers, products) {
+            (Ok(a), Ok(s), Ok(e), Ok(p), Ok(t), Ok(pr)) => (a, s, e, p, t, pr),
+            (Err(err), ..) | (_, Err(err), ..) | (_, _, Err(err), ..) | (_, _, _, Err(err), ..)
+            | (_, _, _, _, Err(err), _) | (_, _, _, _, _, Err(err)) => {
+                return internal_error("Erro ao buscar registros sem lançamento", err)
+            }
+        };
+
+    // Registros datados em períodos fechados ficam sem lançamento
+    let mut skipped = 0;
+    let result: Result<(), sqlx::Error> = async {
+        periods::lock_exclusive(&mut tx).await?;
+        let closed_until = periods::last_closed(&mut tx).await?.map(periods::month_end);
+        let mut open = |entry: &Option<NewEntry>| match (entry, closed_until) {
+            (Some(entry), Some(until)) if entry.date <= until => {
+                skipped += 1;
+                false
+            }
+            _ => true,
+        };
+
+        for account in &accounts {
+            ledger::sync_financial_account(&mut tx, account).await?;
+            let entry = ledger::opening_balance_entry(account);
+            if open(&entry) {
+                ledger::sync(&mut tx, user, "account", account.id, entry).await?;
+            }
+        }
+        // Custo já baixado ou a baixar do estoque pelas vendas, por produto
+        let mut sold: HashMap<Uuid, f64> = sqlx::query_as::<_, (Uuid, f64)>(
+            "SELECT product_id, SUM(quantity * unit_cost) FROM sales WHERE deleted_at IS NULL AND unit_cost IS NOT NULL GROUP BY product_id",
+        )
+        .fetch_all(&mut tx)
+        .await?
+        .into_iter()
+        .collect();
+        for sale in &sales {
+            let entry = ledger::sale_entry(sale);
+            if open(&entry) {
+                ledger::sync(&mut tx, user, "sale", sale.id, entry).await?;
+            } else if let (Some(cost), Some(total)) = (sale.unit_cost, sold.get_mut(&sale.product_id)) {
+                *total -= sale.quantity as f64 * cost;
+            }
+        }
+        for expense in &expenses {
+            let entry = ledger::expense_entry(expense);
+            if open(&entry) {
+                ledger::sync(&mut tx, user, "expense", expense.id, entry).await?;
+            }
+        }
+        for payment in &payments {
+            let entry = ledger::payment_entry(payment);
+            if open(&entry) {
+                ledger::sync(&mut tx, user, "payment", payment.id, entry).await?;
+            }
+        }
+        for transfer in &transfers {
+            let entry = ledger::transfer_entry(transfer);
+            if open(&entry) {
+                ledger::sync(&mut tx, user, "transfer", transfer.id, entry).await?;
+            }
+        }
+        // O estoque inicial cobre o saldo atual e o que as vendas baixam
+        for product in &products {
+            let value = product.stock as f64 * product.cost.unwrap_or(0.0) + sold.get(&product.id).copied().unwrap_or(0.0);
+            if let Some(entry) = ledger::opening_stock_entry(&product.name, value) {
+                ledger::post(&mut tx, user, "stock", Some(product.id), entry).await?;
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = result {
+        return internal_error("Erro ao gerar lançamentos contábeis", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => {
+            log::info!("Lançamentos contábeis reconstruídos por {}", admin.id);
+            HttpResponse::Ok().json(json!({
+                "status": "success",
+                "accounts": accounts.len(),
+                "sales": sales.len(),
+                "expenses": expenses.len(),
+                "payments": payments.len(),
+                "transfers": transfers.len(),
+                "skipped_closed_periods": skipped,
+                "products": products.len(),
+            }))
+        }
+        Err(err) => internal_error("Erro ao gerar lançamentos contábeis", err),
+    }
+}
+
+// Configura as rotas
+pub fn config_ledger(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_ledger_accounts)
+       .service(create_ledger_account)
+       .service(get_journal_entries)
+       .service(create_journal_entry)
+       .service(get_trial_balance)
+       .service(get_account_ledger)
+       .service(rebuild_ledger);
+}
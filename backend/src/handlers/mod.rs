@@ -8,8 +8,21 @@ pub mod reports_handler;
 pub mod expense_handler;
 pub mod payment_handler;
 pub mod dashboard_handler;
+pub mod health_handler;
+pub mod audit_handler;
+pub mod import_handler;
+pub mod bank_handler;
+pub mod transfer_handler;
+pub mod ledger_handler;
+pub mod period_handler;
+pub mod budget_handler;
+pub mod forecast_handler;
+pub mod prediction_handler;
+pub mod insights_handler;
+pub mod search_handler;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.configure(health_handler::config_health);
     cfg.service(
         web::scope("/api")
             .configure(client_handler::config_clients)
@@ -19,6 +32,17 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .configure(expense_handler::config_expenses) // só um handler
             .configure(payment_handler::config_payments)
             .configure(dashboard_handler::config_dashboard)
-            .configure(reports_handler::config_reports),
+            .configure(reports_handler::config_reports)
+            .configure(audit_handler::config_audit)
+            .configure(import_handler::config_import)
+            .configure(bank_handler::config_bank)
+            .configure(transfer_handler::config_transfers)
+            .configure(ledger_handler::config_ledger)
+            .configure(period_handler::config_periods)
+            .configure(budget_handler::config_budgets)
+            .configure(forecast_handler::config_forecast)
+            .configure(prediction_handler::config_predictions)
+            .configure(insights_handler::config_insights)
+            .configure(search_handler::config_search),
     );
 }
@@ -1,20 +1,41 @@
 // src/handlers/payment_handler.rs
-use actix_web::{get, post, patch, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, patch, delete, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
-use sqlx::{query, query_as};
-use chrono::NaiveDate;
-use crate::{db::DbPool, models::payment::{Payment, CreatePayment, UpdatePayment}};
+use sqlx::query_as;
+use chrono::{Local, NaiveDate};
+use crate::{audit::{self, Change}, auth::OptionalUser, db::DbPool, errors::{bad_request, internal_error, not_found}, export::{self, ExportParams}, ledger, periods, schema::ListParams, models::payment::{Payment, CreatePayment, UpdatePayment}, versioning::{self, etag}};
 
 // GET /api/payments
 #[get("/payments")]
-pub async fn get_payments(pool: web::Data<DbPool>) -> impl Responder {
-    let payments = query_as::<_, Payment>("SELECT * FROM payments")
+pub async fn get_payments(
+    pool: web::Data<DbPool>,
+    params: web::Query<ListParams>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = "SELECT * FROM payments WHERE $1 OR deleted_at IS NULL ORDER BY payment_date DESC NULLS LAST";
+    let include_deleted = params.include_deleted;
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("pagamentos");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Payment>(sql).bind(include_deleted).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let payments = query_as::<_, Payment>(sql)
+        .bind(include_deleted)
         .fetch_all(pool.get_ref())
         .await;
 
     match payments {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        Err(err) => internal_error("Erro ao buscar pagamentos", err),
     }
 }
 
@@ -23,20 +44,22 @@ pub async fn get_payments(pool: web::Data<DbPool>) -> impl Responder {
 pub async fn get_payment_by_id(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    params: web::Query<ListParams>,
 ) -> impl Responder {
     let id = path.into_inner();
 
     let payment = query_as::<_, Payment>(
-        "SELECT * FROM payments WHERE id = $1"
+        "SELECT * FROM payments WHERE id = $1 AND ($2 OR deleted_at IS NULL)"
     )
     .bind(id)
+    .bind(params.include_deleted)
     .fetch_optional(pool.get_ref())
     .await;
 
     match payment {
-        Ok(Some(pay)) => HttpResponse::Ok().json(pay),
-        Ok(None) => HttpResponse::NotFound().body("Pagamento não encontrado"),
-        Err(_) => HttpResponse::InternalServerError().body("Erro ao buscar pagamento"),
+        Ok(Some(pay)) => HttpResponse::Ok().insert_header(etag(pay.version)).json(pay),
+        Ok(None) => not_found("Pagamento não encontrado"),
+        Err(err) => internal_error("Erro ao buscar pagamento", err),
     }
 }
 
@@ -45,23 +68,48 @@ pub async fn get_payment_by_id(
 pub async fn create_payment(
     pool: web::Data<DbPool>,
     payment: web::Json<CreatePayment>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let new_id = Uuid::new_v4();
 
-    let result = query!(
-        "INSERT INTO payments (id, expense_id, payment_date, amount, method) VALUES ($1, $2, $3, $4, $5)",
-        new_id,
-        payment.expense_id,
-        payment.payment_date,
-        payment.amount,
-        payment.method
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[business_date(payment.payment_date)]).await {
+        return res;
+    }
+
+    let created = query_as::<_, Payment>(
+        "INSERT INTO payments (id, expense_id, payment_date, amount, method, account_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
     )
-    .execute(pool.get_ref())
+    .bind(new_id)
+    .bind(payment.expense_id)
+    .bind(payment.payment_date)
+    .bind(payment.amount)
+    .bind(&payment.method)
+    .bind(payment.account_id)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Created().json(new_id),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    let created = match created {
+        Ok(pay) => pay,
+        Err(err) if is_missing_account(&err) => return bad_request("Conta não encontrada"),
+        Err(err) => return internal_error("Erro ao criar pagamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "payment", new_id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "payment", new_id, ledger::payment_entry(&created)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(new_id),
+        Err(err) => internal_error("Erro ao criar pagamento", err),
     }
 }
 
@@ -71,60 +119,197 @@ pub async fn update_payment(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
     payment_update: web::Json<UpdatePayment>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
 ) -> impl Responder {
     let id = path.into_inner();
 
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
     // Buscar pagamento existente
     let existing = query_as::<_, Payment>(
-        "SELECT * FROM payments WHERE id = $1"
+        "SELECT * FROM payments WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
     )
     .bind(id)
-    .fetch_optional(pool.get_ref())
+    .fetch_optional(&mut tx)
     .await;
 
-    if let Ok(Some(existing)) = existing {
-        // Atualizar campos com fallback para valores existentes
-        let expense_id = payment_update.expense_id.or(existing.expense_id);
-        let payment_date = payment_update.payment_date.or(existing.payment_date);
-        let amount = payment_update.amount.or(Some(existing.amount)).unwrap();
-        let method = payment_update.method.clone().or(existing.method);
-
-        let result = query!(
-            "UPDATE payments SET expense_id = $1, payment_date = $2, amount = $3, method = $4 WHERE id = $5",
-            expense_id,
-            payment_date,
-            amount,
-            method,
-            id
-        )
-        .execute(pool.get_ref())
-        .await;
+    let existing = match existing {
+        Ok(Some(pay)) => pay,
+        Ok(None) => return not_found("Pagamento não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar pagamento", err),
+    };
 
-        match result {
-            Ok(_) => HttpResponse::Ok().body("Pagamento atualizado"),
-            Err(_) => HttpResponse::InternalServerError().body("Erro ao atualizar pagamento"),
-        }
-    } else {
-        HttpResponse::NotFound().body("Pagamento não encontrado")
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
+    }
+
+    // Atualizar campos com fallback para valores existentes
+    let expense_id = payment_update.expense_id.or(existing.expense_id);
+    let payment_date = payment_update.payment_date.or(existing.payment_date);
+    let amount = payment_update.amount.unwrap_or(existing.amount);
+    let method = payment_update.method.clone().or_else(|| existing.method.clone());
+    let account_id = payment_update.account_id.unwrap_or(existing.account_id);
+
+    let dates = [business_date(existing.payment_date), business_date(payment_date)];
+    if let Err(res) = periods::ensure_open(&mut tx, &dates).await {
+        return res;
+    }
+
+    let updated = query_as::<_, Payment>(
+        "UPDATE payments SET expense_id = $1, payment_date = $2, amount = $3, method = $4, account_id = $5, version = version + 1 WHERE id = $6 RETURNING *"
+    )
+    .bind(expense_id)
+    .bind(payment_date)
+    .bind(amount)
+    .bind(method)
+    .bind(account_id)
+    .bind(id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let updated = match updated {
+        Ok(pay) => pay,
+        Err(err) if is_missing_account(&err) => return bad_request("Conta não encontrada"),
+        Err(err) => return internal_error("Erro ao atualizar pagamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "payment", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "payment", id, ledger::payment_entry(&updated)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).body("Pagamento atualizado"),
+        Err(err) => internal_error("Erro ao atualizar pagamento", err),
     }
 }
 
-// DELETE /api/payments/{id}
+// DELETE /api/payments/{id} - exclusão lógica
 #[delete("/payments/{id}")]
 pub async fn delete_payment(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let result = query!("DELETE FROM payments WHERE id = $1", id)
-        .execute(pool.get_ref())
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
         .await;
 
-    match result {
+    let existing = match existing {
+        Ok(Some(pay)) => pay,
+        Ok(None) => return not_found("Pagamento não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar pagamento para deletar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[business_date(existing.payment_date)]).await {
+        return res;
+    }
+
+    let deleted = query_as::<_, Payment>("UPDATE payments SET deleted_at = NOW(), version = version + 1 WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let deleted = match deleted {
+        Ok(pay) => pay,
+        Err(err) => return internal_error("Erro ao deletar pagamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "payment", id, Change::SoftDeleted(&existing, &deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "payment", id, ledger::payment_entry(&deleted)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
         Ok(_) => HttpResponse::Ok().body("Pagamento deletado"),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        Err(err) => internal_error("Erro ao deletar pagamento", err),
+    }
+}
+
+// POST /api/payments/{id}/restore - desfaz a exclusão lógica
+#[post("/payments/{id}/restore")]
+pub async fn restore_payment(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(pay)) => pay,
+        Ok(None) => return not_found("Pagamento excluído não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar pagamento para restaurar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[business_date(existing.payment_date)]).await {
+        return res;
+    }
+
+    let restored = query_as::<_, Payment>("UPDATE payments SET deleted_at = NULL, version = version + 1 WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let restored = match restored {
+        Ok(pay) => pay,
+        Err(err) => return internal_error("Erro ao restaurar pagamento", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "payment", id, Change::Restored(&existing, &restored)).await {
+        return internal_error("Erro ao registrar auditoria", err);
     }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "payment", id, ledger::payment_entry(&restored)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(restored.version)).json(restored),
+        Err(err) => internal_error("Erro ao restaurar pagamento", err),
+    }
+}
+
+/// Violação da chave estrangeira de `account_id`.
+fn is_missing_account(err: &sqlx::Error) -> bool {
+    matches!(err, sqlx::Error::Database(db_err) if db_err.constraint() == Some("payments_account_id_fkey"))
+}
+
+// Pagamentos sem data contam como feitos hoje, como no lançamento contábil
+fn business_date(payment_date: Option<NaiveDate>) -> NaiveDate {
+    payment_date.unwrap_or_else(|| Local::now().date_naive())
 }
 
 // Configura as rotas
@@ -133,5 +318,6 @@ pub fn config_payments(cfg: &mut web::ServiceConfig) {
        .service(get_payment_by_id)
        .service(create_payment)
        .service(update_payment)
-       .service(delete_payment);
+       .service(delete_payment)
+       .service(restore_payment);
 }
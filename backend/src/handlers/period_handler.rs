@@ -0,0 +1,286 @@
+// Fechamento e reabertura de períodos contábeis
+// src/handlers/period_handler.rs
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::{Local, NaiveDate};
+use sqlx::query_as;
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, Change},
+    auth::AdminUser,
+    db::DbPool,
+    errors::{bad_request, conflict, internal_error, not_found},
+    handlers::ledger_handler::NATURAL_BALANCE,
+    models::period::{FiscalPeriod, PeriodBalance},
+    periods::{self, month_end, parse_month},
+    versioning::etag,
+};
+
+// GET /api/periods - meses já fechados ou reabertos
+#[get("/periods")]
+pub async fn get_periods(pool: web::Data<DbPool>) -> impl Responder {
+    let periods = query_as::<_, FiscalPeriod>("SELECT * FROM fiscal_periods ORDER BY month DESC")
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match periods {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar períodos", err),
+    }
+}
+
+// GET /api/periods/{month}/balances - saldos gravados no fechamento (month = AAAA-MM)
+#[get("/periods/{month}/balances")]
+pub async fn get_period_balances(pool: web::Data<DbPool>, path: web::Path<String>) -> impl Responder {
+    let month = match parse_month(&path.into_inner()) {
+        Some(month) => month,
+        None => return bad_request("Mês inválido; use AAAA-MM"),
+    };
+
+    let period = query_as::<_, FiscalPeriod>("SELECT * FROM fiscal_periods WHERE month = $1 AND status = 'closed'")
+        .bind(month)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    let period = match period {
+        Ok(Some(period)) => period,
+        Ok(None) => return not_found("Período fechado não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar período", err),
+    };
+
+    let balances = query_as::<_, PeriodBalance>(&format!(
+        r#"
+        SELECT la.id AS ledger_account_id, la.code, la.name, la.kind, pb.debit, pb.credit,
+            {} * (pb.debit - pb.credit) AS balance
+        FROM period_balances pb
+        JOIN ledger_accounts la ON la.id = pb.ledger_account_id
+        WHERE pb.period_id = $1
+        ORDER BY la.code
+        "#,
+        NATURAL_BALANCE
+    ))
+    .bind(period.id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match balances {
+        Ok(balances) => HttpResponse::Ok().json(serde_json::json!({
+            "period": period,
+            "as_of": month_end(month),
+            "accounts": balances,
+        })),
+        Err(err) => internal_error("Erro ao buscar saldos do período", err),
+    }
+}
+
+// POST /api/periods/{month}/close - fecha o mês e grava os saldos (somente admin)
+#[post("/periods/{month}/close")]
+pub async fn close_period(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+    AdminUser(admin): AdminUser,
+) -> impl Responder {
+    let month = match parse_month(&path.into_inner()) {
+        Some(month) => month,
+        None => return bad_request("Mês inválido; use AAAA-MM"),
+    };
+    let end = month_end(month);
+
+    if end >= Local::now().date_naive() {
+        return bad_request("Só é possível fechar meses já encerrados");
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    if let Err(err) = periods::lock_exclusive(&mut tx).await {
+        return internal_error("Erro ao bloquear períodos", err);
+    }
+
+    let last_closed = match periods::last_closed(&mut tx).await {
+        Ok(last_closed) => last_closed,
+        Err(err) => return internal_error("Erro ao buscar períodos", err),
+    };
+
+    if let Some(closed) = last_closed.filter(|closed| *closed >= month) {
+        return conflict(format!(
+            "Os períodos até {} já estão fechados",
+            closed.format("%m/%Y")
+        ));
+    }
+
+    // Meses anteriores com movimento precisam ser fechados antes
+    let pending = sqlx::query_scalar::<_, Option<NaiveDate>>(
+        "SELECT MIN(entry_date) FROM journal_entries WHERE entry_date < $1 AND ($2::date IS NULL OR entry_date > $2)"
+    )
+    .bind(month)
+    .bind(last_closed.map(month_end))
+    .fetch_one(&mut tx)
+    .await;
+
+    match pending {
+        Ok(Some(date)) => {
+            return conflict(format!(
+                "Feche antes o período {}",
+                date.format("%m/%Y")
+            ))
+        }
+        Ok(None) => {}
+        Err(err) => return internal_error("Erro ao verificar períodos anteriores", err),
+    }
+
+    let existing = query_as::<_, FiscalPeriod>("SELECT * FROM fiscal_periods WHERE month = $1 FOR UPDATE")
+        .bind(month)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(existing) => existing,
+        Err(err) => return internal_error("Erro ao buscar período", err),
+    };
+
+    let closed = query_as::<_, FiscalPeriod>(
+        r#"
+        INSERT INTO fiscal_periods (id, month, status, closed_at, closed_by)
+        VALUES ($1, $2, 'closed', NOW(), $3)
+        ON CONFLICT (month) DO UPDATE
+        SET status = 'closed', closed_at = NOW(), closed_by = $3, version = fiscal_periods.version + 1
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(month)
+    .bind(&admin.id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let closed = match closed {
+        Ok(period) => period,
+        Err(err) => return internal_error("Erro ao fechar período", err),
+    };
+
+    let snapshot = sqlx::query(
+        r#"
+        INSERT INTO period_balances (period_id, ledger_account_id, debit, credit)
+        SELECT $1, l.ledger_account_id, SUM(l.debit), SUM(l.credit)
+        FROM journal_lines l
+        JOIN journal_entries e ON e.id = l.entry_id
+        WHERE e.entry_date <= $2
+        GROUP BY l.ledger_account_id
+        "#
+    )
+    .bind(closed.id)
+    .bind(end)
+    .execute(&mut tx)
+    .await;
+
+    if let Err(err) = snapshot {
+        return internal_error("Erro ao gravar saldos do período", err);
+    }
+
+    let change = match &existing {
+        Some(before) => Change::Updated(before, &closed),
+        None => Change::Created(&closed),
+    };
+    if let Err(err) = audit::record(&mut tx, Some(&admin), "fiscal_period", closed.id, change).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => {
+            log::info!("Período {} fechado por {}", month.format("%m/%Y"), admin.id);
+            HttpResponse::Ok().insert_header(etag(closed.version)).json(closed)
+        }
+        Err(err) => internal_error("Erro ao fechar período", err),
+    }
+}
+
+// POST /api/periods/{month}/reopen - reabre o último mês fechado (somente admin)
+#[post("/periods/{month}/reopen")]
+pub async fn reopen_period(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+    AdminUser(admin): AdminUser,
+) -> impl Responder {
+    let month = match parse_month(&path.into_inner()) {
+        Some(month) => month,
+        None => return bad_request("Mês inválido; use AAAA-MM"),
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    if let Err(err) = periods::lock_exclusive(&mut tx).await {
+        return internal_error("Erro ao bloquear períodos", err);
+    }
+
+    let existing = query_as::<_, FiscalPeriod>("SELECT * FROM fiscal_periods WHERE month = $1 AND status = 'closed' FOR UPDATE")
+        .bind(month)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(period)) => period,
+        Ok(None) => return not_found("Período fechado não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar período", err),
+    };
+
+    match periods::last_closed(&mut tx).await {
+        Ok(Some(last)) if last > month => {
+            return conflict(format!("Reabra antes o período {}", last.format("%m/%Y")))
+        }
+        Ok(_) => {}
+        Err(err) => return internal_error("Erro ao buscar períodos", err),
+    }
+
+    let reopened = query_as::<_, FiscalPeriod>(
+        r#"
+        UPDATE fiscal_periods
+        SET status = 'open', reopened_at = NOW(), reopened_by = $2, version = version + 1
+        WHERE id = $1
+        RETURNING *
+        "#
+    )
+    .bind(existing.id)
+    .bind(&admin.id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let reopened = match reopened {
+        Ok(period) => period,
+        Err(err) => return internal_error("Erro ao reabrir período", err),
+    };
+
+    // Os saldos gravados deixam de valer; serão refeitos no próximo fechamento
+    if let Err(err) = sqlx::query("DELETE FROM period_balances WHERE period_id = $1")
+        .bind(existing.id)
+        .execute(&mut tx)
+        .await
+    {
+        return internal_error("Erro ao remover saldos do período", err);
+    }
+
+    if let Err(err) = audit::record(&mut tx, Some(&admin), "fiscal_period", existing.id, Change::Updated(&existing, &reopened)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => {
+            log::warn!("Período {} reaberto por {}", month.format("%m/%Y"), admin.id);
+            HttpResponse::Ok().insert_header(etag(reopened.version)).json(reopened)
+        }
+        Err(err) => internal_error("Erro ao reabrir período", err),
+    }
+}
+
+// Configura as rotas
+pub fn config_periods(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_periods)
+       .service(get_period_balances)
+       .service(close_period)
+       .service(reopen_period);
+}
@@ -0,0 +1,156 @@
+// Previsão de vendas por produto pelo serviço ml_api, com heurística local
+// src/handlers/prediction_handler.rs
+use actix_web::{get, web, HttpResponse, Responder};
+use futures_util::{stream, StreamExt};
+use sqlx::query_as;
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    errors::{internal_error, not_found},
+    ml_client::{MlClient, MlError},
+    models::prediction::{PredictionSource, ProductFeatures, ProductPrediction},
+};
+
+// Chamadas simultâneas ao ml_api na previsão de todos os produtos
+const MAX_CONCURRENT_PREDICTIONS: usize = 8;
+
+// Estoque e preço atuais e unidades vendidas nos últimos 30 e 90 dias
+const PRODUCT_FEATURES: &str = r#"
+    SELECT p.id AS product_id, p.name, p.stock, p.price,
+        COALESCE(SUM(s.quantity) FILTER (
+            WHERE s.created_at >= (NOW() AT TIME ZONE 'UTC') - interval '30 days'
+        ), 0)::float8 AS units_last_30_days,
+        COALESCE(SUM(s.quantity), 0)::float8 AS units_last_90_days
+    FROM products p
+    LEFT JOIN sales s
+        ON s.product_id = p.id
+       AND s.deleted_at IS NULL
+       AND s.created_at >= (NOW() AT TIME ZONE 'UTC') - interval '90 days'
+    WHERE $1::uuid IS NULL OR p.id = $1
+    GROUP BY p.id
+    ORDER BY p.name
+"#;
+
+async fn predict(ml: &MlClient, features: ProductFeatures) -> ProductPrediction {
+    match ml.predict(&features.to_vector()).await {
+        Ok(prediction) if prediction.cached => result(features, prediction.value, PredictionSource::Cache, None),
+        Ok(prediction) => result(features, prediction.value, PredictionSource::MlApi, None),
+        Err(err) => heuristic(features, &err),
+    }
+}
+
+fn heuristic(features: ProductFeatures, err: &MlError) -> ProductPrediction {
+    let value = features.heuristic();
+    result(features, value, PredictionSource::Heuristic, Some(err.to_string()))
+}
+
+fn result(
+    features: ProductFeatures,
+    value: f64,
+    source: PredictionSource,
+    fallback_reason: Option<String>,
+) -> ProductPrediction {
+    ProductPrediction {
+        features,
+        predicted_sales: (value.max(0.0) * 100.0).round() / 100.0,
+        source,
+        fallback_reason,
+    }
+}
+
+// GET /api/predictions/products
+#[get("/predictions/products")]
+pub async fn get_product_predictions(pool: web::Data<DbPool>, ml: web::Data<MlClient>) -> impl Responder {
+    let products = query_as::<_, ProductFeatures>(PRODUCT_FEATURES)
+        .bind(None::<Uuid>)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    let products = match products {
+        Ok(products) => products,
+        Err(err) => return internal_error("Erro ao buscar dados para previsão", err),
+    };
+
+    // Circuito aberto: nenhuma chamada sairia, todos vão direto para a heurística
+    let predictions = if ml.is_open() {
+        products
+            .into_iter()
+            .map(|features| heuristic(features, &MlError::CircuitOpen))
+            .collect::<Vec<_>>()
+    } else {
+        stream::iter(products)
+            .map(|features| predict(&ml, features))
+            .buffered(MAX_CONCURRENT_PREDICTIONS)
+            .collect::<Vec<_>>()
+            .await
+    };
+
+    HttpResponse::Ok().json(predictions)
+}
+
+// GET /api/predictions/products/{id}
+#[get("/predictions/products/{id}")]
+pub async fn get_product_prediction(
+    pool: web::Data<DbPool>,
+    ml: web::Data<MlClient>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let features = query_as::<_, ProductFeatures>(PRODUCT_FEATURES)
+        .bind(Some(path.into_inner()))
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    match features {
+        Ok(Some(features)) => HttpResponse::Ok().json(predict(&ml, features).await),
+        Ok(None) => not_found("Produto não encontrado"),
+        Err(err) => internal_error("Erro ao buscar dados para previsão", err),
+    }
+}
+
+// Configura as rotas
+pub fn config_predictions(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_product_predictions)
+       .service(get_product_prediction);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_client::stub::{Reply, Stub};
+
+    fn features() -> ProductFeatures {
+        ProductFeatures {
+            product_id: Uuid::new_v4(),
+            name: "Café".to_string(),
+            stock: 10,
+            price: 12.5,
+            units_last_30_days: 4.0,
+            units_last_90_days: 10.0,
+        }
+    }
+
+    #[actix_web::test]
+    async fn falls_back_to_heuristic_when_ml_api_fails() {
+        let stub = Stub::start(vec![Reply::Status(500)]).await;
+        let prediction = predict(&stub.client(), features()).await;
+
+        assert!(matches!(prediction.source, PredictionSource::Heuristic));
+        assert_eq!(prediction.predicted_sales, 3.33);
+        assert!(prediction.fallback_reason.is_some());
+    }
+
+    #[actix_web::test]
+    async fn uses_ml_api_then_cache() {
+        let stub = Stub::start(vec![Reply::Value(6.789)]).await;
+        let client = stub.client();
+
+        let first = predict(&client, features()).await;
+        let second = predict(&client, features()).await;
+
+        assert!(matches!(first.source, PredictionSource::MlApi));
+        assert_eq!(first.predicted_sales, 6.79);
+        assert!(matches!(second.source, PredictionSource::Cache));
+        assert_eq!(stub.hits(), 1);
+    }
+}
@@ -1,20 +1,38 @@
 // Handler de produtos
 // src/handlers/product_handler.rs
-use actix_web::{get, post, patch, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, patch, delete, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
-use sqlx::{query, query_as};
-use crate::{db::DbPool, models::product::{Product, UpdateProduct, CreateProduct}};
+use sqlx::query_as;
+use crate::{audit::{self, Change}, auth::OptionalUser, db::DbPool, errors::{bad_request, conflict, internal_error, not_found}, export::{self, ExportParams}, ledger, models::product::{Product, UpdateProduct, CreateProduct}, versioning::{self, etag}};
 
 // GET /api/products
 #[get("/products")]
-pub async fn get_products(pool: web::Data<DbPool>) -> impl Responder {
-    let products = query_as::<_, Product>("SELECT * FROM products")
+pub async fn get_products(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = "SELECT * FROM products ORDER BY name";
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("produtos");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Product>(sql).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let products = query_as::<_, Product>(sql)
         .fetch_all(pool.get_ref())
         .await;
 
     match products {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        Err(err) => internal_error("Erro ao buscar produtos", err),
     }
 }
 
@@ -34,9 +52,9 @@ pub async fn get_product_by_id(
     .await;
 
     match product {
-        Ok(Some(prod)) => HttpResponse::Ok().json(prod),
-        Ok(None) => HttpResponse::NotFound().body("Produto não encontrado"),
-        Err(_) => HttpResponse::InternalServerError().body("Erro ao buscar produto"),
+        Ok(Some(prod)) => HttpResponse::Ok().insert_header(etag(prod.version)).json(prod),
+        Ok(None) => not_found("Produto não encontrado"),
+        Err(err) => internal_error("Erro ao buscar produto", err),
     }
 }
 
@@ -45,23 +63,47 @@ pub async fn get_product_by_id(
 pub async fn create_product(
     pool: web::Data<DbPool>,
     product: web::Json<CreateProduct>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
+    if product.cost.is_some_and(|cost| cost < 0.0) {
+        return bad_request("O custo não pode ser negativo");
+    }
+
     let new_id = Uuid::new_v4();
 
-    let result = query!(
-        "INSERT INTO products (id, name, description, price, stock) VALUES ($1, $2, $3, $4, $5)",
-        new_id,
-        product.name,
-        product.description,
-        product.price,
-        product.stock
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let created = query_as::<_, Product>(
+        "INSERT INTO products (id, name, description, price, stock, cost) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"
     )
-    .execute(pool.get_ref())
+    .bind(new_id)
+    .bind(&product.name)
+    .bind(&product.description)
+    .bind(product.price)
+    .bind(product.stock)
+    .bind(product.cost)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Created().json(new_id),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    let created = match created {
+        Ok(prod) => prod,
+        Err(err) => return internal_error("Erro ao criar produto", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "product", new_id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::post_stock(&mut tx, user.as_ref(), &created, created.stock).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(new_id),
+        Err(err) => internal_error("Erro ao criar produto", err),
     }
 }
 
@@ -71,23 +113,73 @@ pub async fn update_product(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
     product: web::Json<UpdateProduct>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let result = query!(
-        "UPDATE products SET name = $1, description = $2, price = $3, stock = $4 WHERE id = $5",
-        product.name,
-        product.description,
-        product.price,
-        product.stock,
-        id
+    if product.cost.is_some_and(|cost| cost < 0.0) {
+        return bad_request("O custo não pode ser negativo");
+    }
+
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(prod)) => prod,
+        Ok(None) => return not_found("Produto não encontrado"),
+        Err(err) => return internal_error("Erro ao buscar produto", err),
+    };
+
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
+    }
+
+    let updated = query_as::<_, Product>(
+        "UPDATE products SET name = $1, description = $2, price = $3, stock = $4, cost = $5, version = version + 1 WHERE id = $6 RETURNING *"
     )
-    .execute(pool.get_ref())
+    .bind(&product.name)
+    .bind(&product.description)
+    .bind(product.price)
+    .bind(product.stock)
+    .bind(product.cost)
+    .bind(id)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Ok().body("Produto atualizado"),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    let updated = match updated {
+        Ok(prod) => prod,
+        Err(err) => return internal_error("Erro ao atualizar produto", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "product", id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::post_revaluation(&mut tx, user.as_ref(), &existing, &updated).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    let movement = updated.stock - existing.stock;
+    if let Err(err) = ledger::post_stock(&mut tx, user.as_ref(), &updated, movement).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).body("Produto atualizado"),
+        Err(err) => internal_error("Erro ao atualizar produto", err),
     }
 }
 
@@ -96,16 +188,55 @@ pub async fn update_product(
 pub async fn delete_product(
     pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let result = query!("DELETE FROM products WHERE id = $1", id)
-        .execute(pool.get_ref())
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    // Vendas (mesmo excluídas logicamente) guardam o histórico de receita
+    let has_sales = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM sales WHERE product_id = $1)")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    match has_sales {
+        Ok(true) => {
+            return conflict("Produto possui vendas registradas e não pode ser excluído")
+        }
+        Ok(false) => {}
+        Err(err) => return internal_error("Erro ao verificar vendas do produto", err),
+    }
+
+    let deleted = query_as::<_, Product>("DELETE FROM products WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_optional(&mut tx)
         .await;
 
-    match result {
+    let deleted = match deleted {
+        Ok(Some(prod)) => prod,
+        Ok(None) => return not_found("Produto não encontrado"),
+        Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("23503") => {
+            return conflict("Produto possui vendas registradas e não pode ser excluído")
+        }
+        Err(err) => return internal_error("Erro ao deletar produto", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "product", id, Change::Deleted(&deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    // Baixa do estoque restante
+    if let Err(err) = ledger::post_stock(&mut tx, user.as_ref(), &deleted, -deleted.stock).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
         Ok(_) => HttpResponse::Ok().body("Produto deletado"),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        Err(err) => internal_error("Erro ao deletar produto", err),
     }
 }
 
@@ -1,16 +1,74 @@
-use actix_web::{get, web, HttpResponse, Responder};
-use chrono::{NaiveDate, NaiveDateTime};
-use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
-
-use crate::db::DbPool;
-
-#[derive(Serialize, FromRow)]
-pub struct SalesReport {
-    pub product_name: String,
-    pub total_quantity: i64,
-    pub total_revenue: f64,
-}
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+
+use crate::{
+    abc,
+    config::Config,
+    db::DbPool,
+    errors::{bad_request, internal_error},
+    export::{self, ExportParams},
+    models::abc::{AbcParams, AbcRow, ClientAbcQuery, ClientRanking, ProductAbcQuery, ProductRanking},
+    models::report::{Comparison, ComparisonReport, ComparisonTotals, DateWindow, ProductComparison, SalesReport},
+    pdf,
+};
+
+const SALES_REPORT: &str = r#"
+    SELECT
+        p.name AS product_name,
+        SUM(s.quantity) AS total_quantity,
+        SUM(s.total_price) AS total_revenue
+    FROM sales s
+    JOIN products p ON s.product_id = p.id
+    WHERE s.deleted_at IS NULL
+    GROUP BY p.name
+    ORDER BY total_revenue DESC
+"#;
+
+const SALES_BY_DATE_REPORT: &str = r#"
+    SELECT
+        p.name AS product_name,
+        SUM(s.quantity) AS total_quantity,
+        SUM(s.total_price) AS total_revenue
+    FROM sales s
+    JOIN products p ON s.product_id = p.id
+    WHERE s.created_at BETWEEN $1 AND $2
+      AND s.deleted_at IS NULL
+    GROUP BY p.name
+    ORDER BY total_revenue DESC
+"#;
+
+// Produtos com as vendas do intervalo ($1 e $2 opcionais, fim incluído);
+// margem vazia sem custo
+const ABC_PRODUCTS: &str = r#"
+    SELECT p.id, p.name,
+        COALESCE(SUM(s.total_price), 0) AS revenue,
+        COALESCE(SUM(s.quantity), 0) AS quantity,
+        COUNT(s.id) AS sales_count,
+        COALESCE(SUM(s.total_price), 0) - COALESCE(SUM(s.quantity), 0) * p.cost AS margin
+    FROM products p
+    LEFT JOIN sales s
+        ON s.product_id = p.id
+       AND s.deleted_at IS NULL
+       AND ($1::date IS NULL OR s.created_at >= $1::date)
+       AND ($2::date IS NULL OR s.created_at < $2::date + 1)
+    GROUP BY p.id, p.name, p.cost
+"#;
+
+const ABC_CLIENTS: &str = r#"
+    SELECT c.id, c.name,
+        COALESCE(SUM(s.total_price), 0) AS revenue,
+        COALESCE(SUM(s.quantity), 0) AS quantity,
+        COUNT(s.id) AS sales_count,
+        NULL::float8 AS margin
+    FROM clients c
+    LEFT JOIN sales s
+        ON s.client_id = c.id
+       AND s.deleted_at IS NULL
+       AND ($1::date IS NULL OR s.created_at >= $1::date)
+       AND ($2::date IS NULL OR s.created_at < $2::date + 1)
+    GROUP BY c.id, c.name
+"#;
 
 #[derive(Debug, Deserialize)]
 pub struct ReportDateRange {
@@ -20,28 +78,30 @@ pub struct ReportDateRange {
 
 /// GET /api/reports/sales
 #[get("/reports/sales")]
-pub async fn sales_report(pool: web::Data<DbPool>) -> impl Responder {
-    let result = sqlx::query_as::<_, SalesReport>(
-        r#"
-        SELECT 
-            p.name AS product_name,
-            SUM(s.quantity) AS total_quantity,
-            SUM(s.total_price) AS total_revenue
-        FROM sales s
-        JOIN products p ON s.product_id = p.id
-        GROUP BY p.name
-        ORDER BY total_revenue DESC
-        "#
-    )
-    .fetch_all(pool.get_ref())
-    .await;
+pub async fn sales_report(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("relatorio_vendas");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, |pool| {
+                sqlx::query_as::<_, SalesReport>(SALES_REPORT).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let result = sqlx::query_as::<_, SalesReport>(SALES_REPORT)
+        .fetch_all(pool.get_ref())
+        .await;
 
     match result {
         Ok(report) => HttpResponse::Ok().json(report),
-        Err(err) => {
-            eprintln!("Erro ao gerar relatório: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => internal_error("Erro ao gerar relatório", err),
     }
 }
 
@@ -50,39 +110,425 @@ pub async fn sales_report(pool: web::Data<DbPool>) -> impl Responder {
 pub async fn sales_by_date(
     pool: web::Data<DbPool>,
     query: web::Query<ReportDateRange>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
 ) -> impl Responder {
     let start = query.start_date.and_hms_opt(0, 0, 0).unwrap_or(NaiveDateTime::MIN);
     let end = query.end_date.and_hms_opt(23, 59, 59).unwrap_or(NaiveDateTime::MAX);
 
-    let result = sqlx::query_as::<_, SalesReport>(
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = format!("relatorio_vendas_{}_a_{}", query.start_date, query.end_date);
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                sqlx::query_as::<_, SalesReport>(SALES_BY_DATE_REPORT)
+                    .bind(start)
+                    .bind(end)
+                    .fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let result = sqlx::query_as::<_, SalesReport>(SALES_BY_DATE_REPORT)
+        .bind(start)
+        .bind(end)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao gerar relatório por data", err),
+    }
+}
+
+/// GET /api/reports/sales_by_date/pdf?start_date=2025-01-01&end_date=2025-01-31
+#[get("/reports/sales_by_date/pdf")]
+pub async fn sales_by_date_pdf(
+    pool: web::Data<DbPool>,
+    query: web::Query<ReportDateRange>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let (start_date, end_date) = (query.start_date, query.end_date);
+    if start_date > end_date {
+        return bad_request("start_date deve ser anterior a end_date");
+    }
+
+    let start = start_date.and_hms_opt(0, 0, 0).unwrap_or(NaiveDateTime::MIN);
+    let end = end_date.and_hms_opt(23, 59, 59).unwrap_or(NaiveDateTime::MAX);
+
+    let rows = sqlx::query_as::<_, SalesReport>(SALES_BY_DATE_REPORT)
+        .bind(start)
+        .bind(end)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(err) => return internal_error("Erro ao gerar relatório por data", err),
+    };
+
+    let company = config.company.clone();
+    let filename = format!("relatorio_vendas_{}_a_{}.pdf", start_date, end_date);
+
+    match web::block(move || pdf::sales_report(&company, start_date, end_date, &rows)).await {
+        Ok(Ok(bytes)) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", filename)))
+            .body(bytes),
+        Ok(Err(err)) => internal_error("Erro ao gerar PDF do relatório", err),
+        Err(err) => internal_error("Erro ao gerar PDF do relatório", err),
+    }
+}
+
+/// Período anterior e mesmo período do ano passado. Meses inteiros comparam
+/// com os meses anteriores (março com fevereiro inteiro); outros intervalos,
+/// com o mesmo número de dias logo antes.
+fn comparison_windows(start: NaiveDate, end: NaiveDate) -> Option<(DateWindow, DateWindow)> {
+    let after_end = end.succ_opt()?;
+    let whole_months = start.day() == 1 && after_end.day() == 1;
+
+    let previous_start = if whole_months {
+        let months = (after_end.year() - start.year()) * 12 + after_end.month() as i32 - start.month() as i32;
+        start.checked_sub_months(Months::new(months as u32))?
+    } else {
+        start.checked_sub_signed(after_end - start)?
+    };
+    let previous = DateWindow {
+        start_date: previous_start,
+        end_date: start.pred_opt()?,
+    };
+
+    // Pelo dia seguinte ao fim, para que fevereiro inteiro caia em fevereiro inteiro
+    let year = Months::new(12);
+    let last_year = DateWindow {
+        start_date: start.checked_sub_months(year)?,
+        end_date: after_end.checked_sub_months(year)?.pred_opt()?,
+    };
+    Some((previous, last_year))
+}
+
+/// GET /api/reports/comparison?start_date=2026-03-01&end_date=2026-03-31
+///
+/// Vendas por produto e totais do intervalo, comparados com o período
+/// anterior e com o mesmo período do ano passado. Em CSV/XLSX, só as linhas
+/// por produto.
+#[get("/reports/comparison")]
+pub async fn comparison_report(
+    pool: web::Data<DbPool>,
+    query: web::Query<ReportDateRange>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let format = match export.file_format(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+    let (start_date, end_date) = (query.start_date, query.end_date);
+    if start_date > end_date {
+        return bad_request("start_date deve ser anterior a end_date");
+    }
+    let current = DateWindow { start_date, end_date };
+    let (previous, last_year) = match comparison_windows(start_date, end_date) {
+        Some(windows) => windows,
+        None => return bad_request("Intervalo fora das datas suportadas"),
+    };
+
+    // Limites como [início, dia seguinte ao fim)
+    let bounds = |window: DateWindow| (window.start_date, window.end_date + Duration::days(1));
+    let (current_start, current_end) = bounds(current);
+    let (previous_start, previous_end) = bounds(previous);
+    let (last_year_start, last_year_end) = bounds(last_year);
+
+    let products = sqlx::query!(
         r#"
-        SELECT 
+        SELECT
+            p.id AS product_id,
             p.name AS product_name,
-            SUM(s.quantity) AS total_quantity,
-            SUM(s.total_price) AS total_revenue
+            COALESCE(SUM(s.total_price) FILTER (WHERE s.created_at >= $1::date AND s.created_at < $2::date), 0) AS "current_revenue!",
+            COALESCE(SUM(s.total_price) FILTER (WHERE s.created_at >= $3::date AND s.created_at < $4::date), 0) AS "previous_revenue!",
+            COALESCE(SUM(s.total_price) FILTER (WHERE s.created_at >= $5::date AND s.created_at < $6::date), 0) AS "last_year_revenue!",
+            COALESCE(SUM(s.quantity) FILTER (WHERE s.created_at >= $1 AND s.created_at < $2), 0) AS "current_quantity!",
+            COALESCE(SUM(s.quantity) FILTER (WHERE s.created_at >= $3 AND s.created_at < $4), 0) AS "previous_quantity!",
+            COALESCE(SUM(s.quantity) FILTER (WHERE s.created_at >= $5 AND s.created_at < $6), 0) AS "last_year_quantity!"
         FROM sales s
         JOIN products p ON s.product_id = p.id
-        WHERE s.created_at BETWEEN $1 AND $2
-        GROUP BY p.name
-        ORDER BY total_revenue DESC
-        "#
+        WHERE s.deleted_at IS NULL
+          AND ((s.created_at >= $1 AND s.created_at < $2)
+            OR (s.created_at >= $3 AND s.created_at < $4)
+            OR (s.created_at >= $5 AND s.created_at < $6))
+        GROUP BY p.id, p.name
+        ORDER BY 3 DESC, p.name
+        "#,
+        current_start,
+        current_end,
+        previous_start,
+        previous_end,
+        last_year_start,
+        last_year_end
     )
-    .bind(start)
-    .bind(end)
     .fetch_all(pool.get_ref())
     .await;
 
-    match result {
-        Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("Erro ao gerar relatório por data: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+    let products = match products {
+        Ok(products) => products,
+        Err(err) => return internal_error("Erro ao gerar relatório comparativo", err),
+    };
+
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM sales WHERE deleted_at IS NULL AND created_at >= $1::date AND created_at < $2::date) AS "current_sales!",
+            (SELECT COUNT(*) FROM sales WHERE deleted_at IS NULL AND created_at >= $3::date AND created_at < $4::date) AS "previous_sales!",
+            (SELECT COUNT(*) FROM sales WHERE deleted_at IS NULL AND created_at >= $5::date AND created_at < $6::date) AS "last_year_sales!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses WHERE deleted_at IS NULL AND due_date >= $1 AND due_date < $2) AS "current_expenses!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses WHERE deleted_at IS NULL AND due_date >= $3 AND due_date < $4) AS "previous_expenses!",
+            (SELECT COALESCE(SUM(amount), 0) FROM expenses WHERE deleted_at IS NULL AND due_date >= $5 AND due_date < $6) AS "last_year_expenses!"
+        "#,
+        current_start,
+        current_end,
+        previous_start,
+        previous_end,
+        last_year_start,
+        last_year_end
+    )
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let totals = match totals {
+        Ok(totals) => totals,
+        Err(err) => return internal_error("Erro ao gerar relatório comparativo", err),
+    };
+
+    let products: Vec<ProductComparison> = products
+        .into_iter()
+        .map(|p| ProductComparison {
+            product_id: p.product_id,
+            product_name: p.product_name,
+            revenue: Comparison::new(p.current_revenue, p.previous_revenue, p.last_year_revenue),
+            quantity: Comparison::new(
+                p.current_quantity as f64,
+                p.previous_quantity as f64,
+                p.last_year_quantity as f64,
+            ),
+        })
+        .collect();
+
+    if let Some(format) = format {
+        let filename = format!("relatorio_comparativo_{}_a_{}", start_date, end_date);
+        return export::respond_rows(pool.get_ref(), format, export.locale, &filename, products).await;
+    }
+
+    let sum = |metric: fn(&ProductComparison) -> &Comparison| {
+        products.iter().map(metric).fold((0.0, 0.0, 0.0), |(current, previous, last_year), c| {
+            (current + c.current, previous + c.previous, last_year + c.last_year)
+        })
+    };
+    let revenue = sum(|p| &p.revenue);
+    let quantity = sum(|p| &p.quantity);
+    let expenses = (totals.current_expenses, totals.previous_expenses, totals.last_year_expenses);
+
+    let totals = ComparisonTotals {
+        revenue: Comparison::new(revenue.0, revenue.1, revenue.2),
+        quantity: Comparison::new(quantity.0, quantity.1, quantity.2),
+        sales_count: Comparison::new(
+            totals.current_sales as f64,
+            totals.previous_sales as f64,
+            totals.last_year_sales as f64,
+        ),
+        expenses: Comparison::new(expenses.0, expenses.1, expenses.2),
+        profit: Comparison::new(revenue.0 - expenses.0, revenue.1 - expenses.1, revenue.2 - expenses.2),
+    };
+
+    HttpResponse::Ok().json(ComparisonReport {
+        current,
+        previous,
+        last_year,
+        totals,
+        products,
+    })
+}
+
+/// Valida os parâmetros comuns e busca as linhas da curva ABC.
+async fn abc_rows(pool: &DbPool, sql: &str, params: &AbcParams) -> Result<Vec<AbcRow>, HttpResponse> {
+    if !(0.0 < params.a && params.a < params.b && params.b <= 100.0) {
+        return Err(bad_request("Os limites devem satisfazer 0 < a < b <= 100"));
+    }
+    if let (Some(start), Some(end)) = (params.start_date, params.end_date) {
+        if start > end {
+            return Err(bad_request("start_date deve ser anterior a end_date"));
         }
     }
+
+    sqlx::query_as::<_, AbcRow>(sql)
+        .bind(params.start_date)
+        .bind(params.end_date)
+        .fetch_all(pool)
+        .await
+        .map_err(|err| internal_error("Erro ao gerar curva ABC", err))
+}
+
+/// GET /api/reports/abc/products?by=revenue|margin&start_date=&end_date=&a=80&b=95
+///
+/// Produtos sem venda no intervalo ficam em C; na margem, os sem custo
+/// cadastrado ficam fora da classificação, em `unranked` (que não entra no
+/// CSV/XLSX).
+#[get("/reports/abc/products")]
+pub async fn abc_products(
+    pool: web::Data<DbPool>,
+    params: web::Query<AbcParams>,
+    query: web::Query<ProductAbcQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let format = match export.file_format(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+    let rows = match abc_rows(pool.get_ref(), ABC_PRODUCTS, &params).await {
+        Ok(rows) => rows,
+        Err(response) => return response,
+    };
+
+    let (rows, unranked): (Vec<AbcRow>, Vec<AbcRow>) = match query.by {
+        ProductRanking::Revenue => (rows, Vec::new()),
+        ProductRanking::Margin => rows.into_iter().partition(|row| row.margin.is_some()),
+    };
+    let (items, total, classes) = match query.by {
+        ProductRanking::Revenue => abc::classify(rows, |row| row.revenue, params.a, params.b),
+        ProductRanking::Margin => abc::classify(rows, |row| row.margin.unwrap_or(0.0), params.a, params.b),
+    };
+
+    if let Some(format) = format {
+        let filename = export::filename("curva_abc_produtos");
+        return export::respond_rows(pool.get_ref(), format, export.locale, &filename, items).await;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "by": query.by,
+        "start_date": params.start_date,
+        "end_date": params.end_date,
+        "thresholds": { "a": params.a, "b": params.b },
+        "total": total,
+        "classes": classes,
+        "items": items,
+        "unranked": unranked,
+    }))
+}
+
+/// GET /api/reports/abc/clients?by=revenue|sales&start_date=&end_date=&a=80&b=95
+///
+/// Vendas sem cliente (consumidor final) não entram.
+#[get("/reports/abc/clients")]
+pub async fn abc_clients(
+    pool: web::Data<DbPool>,
+    params: web::Query<AbcParams>,
+    query: web::Query<ClientAbcQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let format = match export.file_format(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+    let rows = match abc_rows(pool.get_ref(), ABC_CLIENTS, &params).await {
+        Ok(rows) => rows,
+        Err(response) => return response,
+    };
+
+    let (items, total, classes) = match query.by {
+        ClientRanking::Revenue => abc::classify(rows, |row| row.revenue, params.a, params.b),
+        ClientRanking::Sales => abc::classify(rows, |row| row.sales_count as f64, params.a, params.b),
+    };
+
+    if let Some(format) = format {
+        let filename = export::filename("curva_abc_clientes");
+        return export::respond_rows(pool.get_ref(), format, export.locale, &filename, items).await;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "by": query.by,
+        "start_date": params.start_date,
+        "end_date": params.end_date,
+        "thresholds": { "a": params.a, "b": params.b },
+        "total": total,
+        "classes": classes,
+        "items": items,
+    }))
 }
 
 /// Configura as rotas dos relatórios para uso no mod.rs
 pub fn config_reports(cfg: &mut web::ServiceConfig) {
     cfg.service(sales_report)
-       .service(sales_by_date);
-}
\ No newline at end of file
+       .service(sales_by_date)
+       .service(sales_by_date_pdf)
+       .service(comparison_report)
+       .service(abc_products)
+       .service(abc_clients);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn windows(start: NaiveDate, end: NaiveDate) -> [(NaiveDate, NaiveDate); 2] {
+        let (previous, last_year) = comparison_windows(start, end).unwrap();
+        [(previous.start_date, previous.end_date), (last_year.start_date, last_year.end_date)]
+    }
+
+    #[test]
+    fn whole_months_compare_with_whole_months() {
+        assert_eq!(
+            windows(date(2026, 3, 1), date(2026, 3, 31)),
+            [(date(2026, 2, 1), date(2026, 2, 28)), (date(2025, 3, 1), date(2025, 3, 31))]
+        );
+        assert_eq!(
+            windows(date(2026, 1, 1), date(2026, 3, 31)),
+            [(date(2025, 10, 1), date(2025, 12, 31)), (date(2025, 1, 1), date(2025, 3, 31))]
+        );
+        // Fevereiro bissexto contra fevereiro de 28 dias
+        assert_eq!(
+            windows(date(2024, 2, 1), date(2024, 2, 29)),
+            [(date(2024, 1, 1), date(2024, 1, 31)), (date(2023, 2, 1), date(2023, 2, 28))]
+        );
+        assert_eq!(
+            windows(date(2025, 3, 1), date(2025, 3, 31)),
+            [(date(2025, 2, 1), date(2025, 2, 28)), (date(2024, 3, 1), date(2024, 3, 31))]
+        );
+    }
+
+    #[test]
+    fn other_ranges_compare_with_the_same_number_of_days() {
+        assert_eq!(
+            windows(date(2026, 3, 10), date(2026, 3, 19)),
+            [(date(2026, 2, 28), date(2026, 3, 9)), (date(2025, 3, 10), date(2025, 3, 19))]
+        );
+        // Termina no fim do mês, mas não começa no dia 1
+        assert_eq!(
+            windows(date(2026, 3, 15), date(2026, 3, 31)),
+            [(date(2026, 2, 26), date(2026, 3, 14)), (date(2025, 3, 15), date(2025, 3, 31))]
+        );
+    }
+
+    #[test]
+    fn leap_day_falls_back_to_february_28() {
+        assert_eq!(
+            windows(date(2024, 2, 29), date(2024, 2, 29)),
+            [(date(2024, 2, 28), date(2024, 2, 28)), (date(2023, 2, 28), date(2023, 2, 28))]
+        );
+        assert_eq!(
+            windows(date(2024, 2, 25), date(2024, 3, 5)),
+            [(date(2024, 2, 15), date(2024, 2, 24)), (date(2023, 2, 25), date(2023, 3, 5))]
+        );
+    }
+
+    #[test]
+    fn dates_out_of_range_have_no_windows() {
+        assert!(comparison_windows(NaiveDate::MAX, NaiveDate::MAX).is_none());
+        assert!(comparison_windows(NaiveDate::MIN, NaiveDate::MIN).is_none());
+    }
+}
@@ -1,95 +1,152 @@
 // Handler de vendas
-use actix_web::{get, post, patch, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, patch, delete, http::header, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
-use chrono::Utc;
-use sqlx::query_as;
+use chrono::{NaiveDate, Utc};
+use sqlx::{query_as, query_scalar, PgConnection};
+
+use crate::{audit::{self, Change}, auth::OptionalUser, config::Config, db::DbPool, errors::{bad_request, conflict, internal_error, not_found}, export::{self, ExportParams}, ledger, models::{client::Client, sale::Sale}, pdf, periods, schema::{CreateSale, ListParams, UpdateSale}, versioning::{self, etag}};
+
+// Venda com os nomes do produto e do cliente, como exposta pela API
+pub(crate) const SALE_SELECT: &str = r#"
+    SELECT s.id, s.product_id, p.name AS product_name, s.client_id, c.name AS client_name,
+        s.account_id, a.name AS account_name,
+        s.quantity, s.total_price, s.unit_cost, s.created_at, s.deleted_at, s.version
+    FROM sales s
+    JOIN products p ON s.product_id = p.id
+    LEFT JOIN clients c ON s.client_id = c.id
+    JOIN accounts a ON s.account_id = a.id
+"#;
+
+// Listagem de vendas; $1 inclui as excluídas logicamente
+const SALE_LIST: &str = r#"
+    SELECT s.id, s.product_id, p.name AS product_name, s.client_id, c.name AS client_name,
+        s.account_id, a.name AS account_name,
+        s.quantity, s.total_price, s.unit_cost, s.created_at, s.deleted_at, s.version
+    FROM sales s
+    JOIN products p ON s.product_id = p.id
+    LEFT JOIN clients c ON s.client_id = c.id
+    JOIN accounts a ON s.account_id = a.id
+    WHERE $1 OR s.deleted_at IS NULL
+    ORDER BY s.created_at DESC
+"#;
+
+// Equivalente a SALE_SELECT para INSERT/UPDATE/DELETE ... RETURNING
+pub(crate) const SALE_RETURNING: &str = r#"
+    RETURNING id, product_id,
+        (SELECT name FROM products WHERE products.id = product_id) AS product_name,
+        client_id,
+        (SELECT name FROM clients WHERE clients.id = client_id) AS client_name,
+        account_id,
+        (SELECT name FROM accounts WHERE accounts.id = account_id) AS account_name,
+        quantity, total_price, unit_cost, created_at, deleted_at, version
+"#;
 
-use crate::{db::DbPool, models::sale::Sale, schema::{CreateSale, UpdateSale}};
-
-#[get("/count")]
-pub async fn count_sales(pool: web::Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!("SELECT COUNT(*) as count FROM sales")
-        .fetch_one(pool.get_ref())
-        .await;
-
-    match result {
-        Ok(row) => HttpResponse::Ok().json(serde_json::json!({ "count": row.count.unwrap_or(0) })),
-        Err(err) => {
-            eprintln!("Erro ao contar vendas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+#[get("/sales")]
+pub async fn get_sales(
+    pool: web::Data<DbPool>,
+    params: web::Query<ListParams>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let include_deleted = params.include_deleted;
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("vendas");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Sale>(SALE_LIST).bind(include_deleted).fetch(pool)
+            })
+            .await;
         }
+        Ok(None) => {}
     }
-}
 
-#[get("/revenue")]
-pub async fn revenue_sales(pool: web::Data<DbPool>) -> impl Responder {
-    let result = sqlx::query!("SELECT COALESCE(SUM(total_price), 0) as revenue FROM sales")
-        .fetch_one(pool.get_ref())
+    let sales = query_as::<_, Sale>(SALE_LIST)
+        .bind(include_deleted)
+        .fetch_all(pool.get_ref())
         .await;
 
-    match result {
-        Ok(row) => HttpResponse::Ok().json(serde_json::json!({ "revenue": row.revenue.unwrap_or(0.0) })),
-        Err(err) => {
-            eprintln!("Erro ao calcular receita: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
-    }
-}
-
-#[get("/sales")]
-pub async fn get_sales(pool: web::Data<DbPool>) -> impl Responder {
-    let sales = sqlx::query_as!(
-        Sale,
-        r#"
-        SELECT 
-            s.id,
-            s.product_id,
-            p.name as product_name,
-            s.quantity,
-            s.total_price,
-            s.created_at as "created_at?"
-        FROM sales s
-        JOIN products p ON s.product_id = p.id
-        ORDER BY s.created_at DESC
-        "#
-    )
-    .fetch_all(pool.get_ref())
-    .await;
-
     match sales {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("Erro ao buscar vendas: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => internal_error("Erro ao buscar vendas", err),
     }
 }
 
 // Note o regex abaixo para aceitar só UUID no parâmetro id
-#[get("/sales/{id:^[0-9a-fA-F\\-]{36}$}")]
+#[get("/sales/{id:[0-9a-fA-F\\-]{36}}")]
 pub async fn get_sale_by_id(
-    path: web::Path<String>, 
+    path: web::Path<String>,
     pool: web::Data<DbPool>,
+    params: web::Query<ListParams>,
 ) -> impl Responder {
     let id_str = path.into_inner();
 
     let sale_id = match Uuid::parse_str(&id_str) {
         Ok(uuid) => uuid,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid UUID"),
+        Err(_) => return bad_request("Invalid UUID"),
     };
 
-    let sale = query_as::<_, Sale>("SELECT * FROM sales WHERE id = $1")
+    let sale = query_as::<_, Sale>(&format!("{} WHERE s.id = $1 AND ($2 OR s.deleted_at IS NULL)", SALE_SELECT))
         .bind(sale_id)
+        .bind(params.include_deleted)
         .fetch_optional(pool.get_ref())
         .await;
 
     match sale {
-        Ok(Some(s)) => HttpResponse::Ok().json(s),
-        Ok(None) => HttpResponse::NotFound().body("Sale not found"),
-        Err(err) => {
-            eprintln!("Erro ao buscar venda por id: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+        Ok(Some(s)) => HttpResponse::Ok().insert_header(etag(s.version)).json(s),
+        Ok(None) => not_found("Sale not found"),
+        Err(err) => internal_error("Erro ao buscar venda por id", err),
+    }
+}
+
+// Recibo em PDF; vendas canceladas também podem ser reimpressas
+#[get("/sales/{id:[0-9a-fA-F\\-]{36}}/receipt")]
+pub async fn get_sale_receipt(
+    path: web::Path<String>,
+    pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let sale_id = match Uuid::parse_str(&path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(_) => return bad_request("Invalid UUID"),
+    };
+
+    let sale = query_as::<_, Sale>(&format!("{} WHERE s.id = $1", SALE_SELECT))
+        .bind(sale_id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    let sale = match sale {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Sale not found"),
+        Err(err) => return internal_error("Erro ao buscar venda para o recibo", err),
+    };
+
+    let client = match sale.client_id {
+        Some(client_id) => {
+            let client = query_as::<_, Client>("SELECT id, name, email, phone, document, version FROM clients WHERE id = $1")
+                .bind(client_id)
+                .fetch_optional(pool.get_ref())
+                .await;
+            match client {
+                Ok(client) => client,
+                Err(err) => return internal_error("Erro ao buscar cliente do recibo", err),
+            }
         }
+        None => None,
+    };
+
+    let company = config.company.clone();
+    let filename = format!("recibo_{}.pdf", pdf::receipt_number(&sale));
+
+    match web::block(move || pdf::sale_receipt(&company, &sale, client.as_ref())).await {
+        Ok(Ok(bytes)) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", filename)))
+            .body(bytes),
+        Ok(Err(err)) => internal_error("Erro ao gerar recibo", err),
+        Err(err) => internal_error("Erro ao gerar recibo", err),
     }
 }
 
@@ -97,140 +154,384 @@ pub async fn get_sale_by_id(
 pub async fn create_sale(
     pool: web::Data<DbPool>,
     sale: web::Json<CreateSale>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id = Uuid::new_v4();
 
-    let product = sqlx::query!("SELECT price FROM products WHERE id = $1", sale.product_id)
-        .fetch_one(pool.get_ref())
-        .await;
+    if sale.quantity <= 0 {
+        return bad_request("A quantidade deve ser positiva");
+    }
+
+    if let Some(client_id) = sale.client_id {
+        match sqlx::query!("SELECT id FROM clients WHERE id = $1", client_id)
+            .fetch_optional(pool.get_ref())
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return bad_request("Client not found"),
+            Err(err) => return internal_error("Erro ao buscar cliente da venda", err),
+        }
+    }
+
+    match sqlx::query!("SELECT id FROM accounts WHERE id = $1", sale.account_id)
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => return bad_request("Account not found"),
+        Err(err) => return internal_error("Erro ao buscar conta da venda", err),
+    }
+
+    let now = Utc::now().naive_utc();
 
-    let price = match product {
-        Ok(p) => p.price,
-        Err(_) => return HttpResponse::BadRequest().body("Product not found"),
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
     };
 
+    if let Err(res) = periods::ensure_open(&mut tx, &[now.date()]).await {
+        return res;
+    }
+
+    let (price, unit_cost) = match take_stock(&mut tx, sale.product_id, sale.quantity).await {
+        Ok(product) => product,
+        Err(res) => return res,
+    };
     let total_price = price * sale.quantity as f64;
-    let now = Utc::now().naive_utc();
 
-    let result = sqlx::query!(
-        "INSERT INTO sales (id, product_id, quantity, total_price, created_at) VALUES ($1, $2, $3, $4, $5)",
-        id,
-        sale.product_id,
-        sale.quantity,
-        total_price,
-        now
-    )
-    .execute(pool.get_ref())
+    let created = query_as::<_, Sale>(&format!(
+        "INSERT INTO sales (id, product_id, client_id, account_id, quantity, total_price, unit_cost, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) {}",
+        SALE_RETURNING
+    ))
+    .bind(id)
+    .bind(sale.product_id)
+    .bind(sale.client_id)
+    .bind(sale.account_id)
+    .bind(sale.quantity)
+    .bind(total_price)
+    .bind(unit_cost)
+    .bind(now)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Created().json(id),
-        Err(err) => {
-            eprintln!("Erro ao criar venda: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let created = match created {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao criar venda", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "sale", id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync_sale(&mut tx, user.as_ref(), &created).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(id),
+        Err(err) => internal_error("Erro ao criar venda", err),
     }
 }
 
-#[patch("/sales/{id:^[0-9a-fA-F\\-]{36}$}")]
+#[patch("/sales/{id:[0-9a-fA-F\\-]{36}}")]
 pub async fn update_sale(
     path: web::Path<String>,
     sale_update: web::Json<UpdateSale>,
     pool: web::Data<DbPool>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
 ) -> impl Responder {
     let id_str = path.into_inner();
 
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
     let sale_id = match Uuid::parse_str(&id_str) {
         Ok(uuid) => uuid,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid UUID"),
+        Err(_) => return bad_request("Invalid UUID"),
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
     };
 
-    let existing = query_as::<_, Sale>("SELECT * FROM sales WHERE id = $1")
+    let existing = query_as::<_, Sale>(&format!("{} WHERE s.id = $1 AND s.deleted_at IS NULL FOR UPDATE OF s", SALE_SELECT))
         .bind(sale_id)
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(&mut tx)
         .await;
 
-    if let Err(err) = existing {
-        eprintln!("Erro ao buscar venda para atualizar: {:?}", err);
-        return HttpResponse::InternalServerError().finish();
+    let existing = match existing {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Sale not found"),
+        Err(err) => return internal_error("Erro ao buscar venda para atualizar", err),
+    };
+
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
     }
 
-    let existing = existing.unwrap();
+    if let Err(res) = periods::ensure_open(&mut tx, &sale_dates(&existing)).await {
+        return res;
+    }
 
-    if existing.is_none() {
-        return HttpResponse::NotFound().body("Sale not found");
+    let product_id = sale_update.product_id.unwrap_or(existing.product_id);
+    let quantity = sale_update.quantity.unwrap_or(existing.quantity);
+    let client_id = sale_update.client_id.or(existing.client_id);
+    let account_id = sale_update.account_id.unwrap_or(existing.account_id);
+
+    if quantity <= 0 {
+        return bad_request("A quantidade deve ser positiva");
     }
 
-    let product_id = sale_update.product_id.unwrap_or(existing.as_ref().unwrap().product_id);
-    let quantity = sale_update.quantity.unwrap_or(existing.as_ref().unwrap().quantity);
+    if let Some(client_id) = sale_update.client_id {
+        match sqlx::query!("SELECT id FROM clients WHERE id = $1", client_id)
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return bad_request("Client not found"),
+            Err(err) => return internal_error("Erro ao buscar cliente da venda", err),
+        }
+    }
 
-    let product = sqlx::query!("SELECT price FROM products WHERE id = $1", product_id)
-        .fetch_one(pool.get_ref())
-        .await;
+    if let Some(account_id) = sale_update.account_id {
+        match sqlx::query!("SELECT id FROM accounts WHERE id = $1", account_id)
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return bad_request("Account not found"),
+            Err(err) => return internal_error("Erro ao buscar conta da venda", err),
+        }
+    }
 
-    let price = match product {
-        Ok(p) => p.price,
-        Err(_) => return HttpResponse::BadRequest().body("Product not found"),
+    // Devolve o que a venda tinha baixado e baixa de novo com os novos valores
+    if let Err(err) = return_stock(&mut tx, existing.product_id, existing.quantity).await {
+        return internal_error("Erro ao devolver estoque da venda", err);
+    }
+    let (price, cost) = match take_stock(&mut tx, product_id, quantity).await {
+        Ok(product) => product,
+        Err(res) => return res,
     };
 
     let total_price = price * quantity as f64;
+    // O custo da venda só muda junto com o produto
+    let unit_cost = if product_id == existing.product_id { existing.unit_cost } else { cost };
 
-    let result = sqlx::query!(
+    let updated = query_as::<_, Sale>(&format!(
         r#"
         UPDATE sales
-        SET product_id = $1, quantity = $2, total_price = $3
-        WHERE id = $4
+        SET product_id = $1, quantity = $2, total_price = $3, unit_cost = $4, client_id = $5, account_id = $6, version = version + 1
+        WHERE id = $7
+        {}
         "#,
-        product_id,
-        quantity,
-        total_price,
-        sale_id
-    )
-    .execute(pool.get_ref())
+        SALE_RETURNING
+    ))
+    .bind(product_id)
+    .bind(quantity)
+    .bind(total_price)
+    .bind(unit_cost)
+    .bind(client_id)
+    .bind(account_id)
+    .bind(sale_id)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Ok().body("Sale updated successfully"),
-        Err(err) => {
-            eprintln!("Erro ao atualizar venda: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let updated = match updated {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao atualizar venda", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "sale", sale_id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync_sale(&mut tx, user.as_ref(), &updated).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).body("Sale updated successfully"),
+        Err(err) => internal_error("Erro ao atualizar venda", err),
     }
 }
 
-#[delete("/sales/{id:^[0-9a-fA-F\\-]{36}$}")]
+#[delete("/sales/{id:[0-9a-fA-F\\-]{36}}")]
 pub async fn delete_sale(
     path: web::Path<String>,
     pool: web::Data<DbPool>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id_str = path.into_inner();
 
     let sale_id = match Uuid::parse_str(&id_str) {
         Ok(uuid) => uuid,
-        Err(_) => return HttpResponse::BadRequest().body("Invalid UUID"),
+        Err(_) => return bad_request("Invalid UUID"),
     };
 
-    let result = sqlx::query!("DELETE FROM sales WHERE id = $1", sale_id)
-        .execute(pool.get_ref())
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Sale>(&format!("{} WHERE s.id = $1 AND s.deleted_at IS NULL FOR UPDATE OF s", SALE_SELECT))
+        .bind(sale_id)
+        .fetch_optional(&mut tx)
         .await;
 
-    match result {
-        Ok(res) if res.rows_affected() > 0 => HttpResponse::NoContent().finish(),
-        Ok(_) => HttpResponse::NotFound().body("Sale not found"),
-        Err(err) => {
-            eprintln!("Erro ao deletar venda: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let existing = match existing {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Sale not found"),
+        Err(err) => return internal_error("Erro ao buscar venda para deletar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &sale_dates(&existing)).await {
+        return res;
+    }
+
+    if let Err(err) = return_stock(&mut tx, existing.product_id, existing.quantity).await {
+        return internal_error("Erro ao devolver estoque da venda", err);
+    }
+
+    // Exclusão lógica: a venda continua no histórico com deleted_at
+    let deleted = query_as::<_, Sale>(&format!("UPDATE sales SET deleted_at = NOW(), version = version + 1 WHERE id = $1 {}", SALE_RETURNING))
+        .bind(sale_id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let deleted = match deleted {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao deletar venda", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "sale", sale_id, Change::SoftDeleted(&existing, &deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync_sale(&mut tx, user.as_ref(), &deleted).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => internal_error("Erro ao deletar venda", err),
     }
 }
 
+#[post("/sales/{id:[0-9a-fA-F\\-]{36}}/restore")]
+pub async fn restore_sale(
+    path: web::Path<String>,
+    pool: web::Data<DbPool>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id_str = path.into_inner();
+
+    let sale_id = match Uuid::parse_str(&id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => return bad_request("Invalid UUID"),
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Sale>(&format!("{} WHERE s.id = $1 AND s.deleted_at IS NOT NULL FOR UPDATE OF s", SALE_SELECT))
+        .bind(sale_id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Deleted sale not found"),
+        Err(err) => return internal_error("Erro ao buscar venda para restaurar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &sale_dates(&existing)).await {
+        return res;
+    }
+
+    if let Err(res) = take_stock(&mut tx, existing.product_id, existing.quantity).await {
+        return res;
+    }
+
+    let restored = query_as::<_, Sale>(&format!("UPDATE sales SET deleted_at = NULL, version = version + 1 WHERE id = $1 {}", SALE_RETURNING))
+        .bind(sale_id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let restored = match restored {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao restaurar venda", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "sale", sale_id, Change::Restored(&existing, &restored)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync_sale(&mut tx, user.as_ref(), &restored).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(restored.version)).json(restored),
+        Err(err) => internal_error("Erro ao restaurar venda", err),
+    }
+}
+
+/// Baixa `quantity` unidades do estoque do produto e devolve o preço e o
+/// custo atuais. 400 se o produto não existe; 409 se o estoque não basta.
+async fn take_stock(conn: &mut PgConnection, product_id: Uuid, quantity: i32) -> Result<(f64, Option<f64>), HttpResponse> {
+    let taken = query_as::<_, (f64, Option<f64>)>(
+        "UPDATE products SET stock = stock - $2, version = version + 1 WHERE id = $1 AND stock >= $2 RETURNING price, cost",
+    )
+    .bind(product_id)
+    .bind(quantity)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|err| internal_error("Erro ao baixar estoque", err))?;
+
+    if let Some(taken) = taken {
+        return Ok(taken);
+    }
+
+    let stock = query_scalar::<_, i32>("SELECT stock FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|err| internal_error("Erro ao baixar estoque", err))?;
+
+    match stock {
+        Some(stock) => Err(conflict(format!("Estoque insuficiente: {} unidade(s) disponível(is)", stock))),
+        None => Err(bad_request("Product not found")),
+    }
+}
+
+/// Devolve ao estoque o que uma venda tinha baixado.
+async fn return_stock(conn: &mut PgConnection, product_id: Uuid, quantity: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE products SET stock = stock + $2, version = version + 1 WHERE id = $1")
+        .bind(product_id)
+        .bind(quantity)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// Data de negócio da venda, para o bloqueio de períodos fechados
+fn sale_dates(sale: &Sale) -> Vec<NaiveDate> {
+    sale.created_at.map(|d| d.date()).into_iter().collect()
+}
+
 pub fn config_sale(cfg: &mut web::ServiceConfig) {
-    cfg.service(count_sales)
-        .service(revenue_sales)
-        .service(get_sales)
+    cfg.service(get_sales)
         .service(get_sale_by_id)
+        .service(get_sale_receipt)
         .service(create_sale)
         .service(update_sale)
-        .service(delete_sale);
+        .service(delete_sale)
+        .service(restore_sale);
 }
 
@@ -0,0 +1,138 @@
+// Busca global em clientes, produtos, fornecedores e despesas
+// src/handlers/search_handler.rs
+use actix_web::{get, web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::query_as;
+
+use crate::{
+    db::DbPool,
+    errors::{bad_request, internal_error},
+    models::search::{SearchQuery, SearchResult, SEARCH_TYPES},
+};
+
+// Os trechos vêm marcados com caracteres de controle, trocados por <mark>
+// depois de escapar o texto
+const START_MARK: char = '\u{2}';
+const STOP_MARK: char = '\u{3}';
+
+// $1 tsquery, $2 tipos (NULL = todos), $3 limite
+const SEARCH: &str = r#"
+    WITH q AS (
+        SELECT to_tsquery('pt_unaccent', $1) AS query,
+            E'StartSel=\u0002, StopSel=\u0003, MaxFragments=2, MinWords=5, MaxWords=20' AS options
+    )
+    SELECT * FROM (
+        SELECT 'client' AS kind, c.id, c.name AS title, c.email AS subtitle,
+            ts_rank(c.search_vector, q.query)::float8 AS rank,
+            ts_headline('pt_unaccent', c.name || ' · ' || c.email, q.query, q.options) AS highlight
+        FROM clients c, q
+        WHERE c.search_vector @@ q.query AND ($2::text[] IS NULL OR 'client' = ANY($2))
+        UNION ALL
+        SELECT 'product', p.id, p.name, p.description,
+            ts_rank(p.search_vector, q.query)::float8,
+            ts_headline('pt_unaccent', p.name || COALESCE(' · ' || p.description, ''), q.query, q.options)
+        FROM products p, q
+        WHERE p.search_vector @@ q.query AND ($2::text[] IS NULL OR 'product' = ANY($2))
+        UNION ALL
+        SELECT 'supplier', s.id, s.name, s.email,
+            ts_rank(s.search_vector, q.query)::float8,
+            ts_headline('pt_unaccent', s.name || COALESCE(' · ' || s.email, ''), q.query, q.options)
+        FROM suppliers s, q
+        WHERE s.search_vector @@ q.query AND ($2::text[] IS NULL OR 'supplier' = ANY($2))
+        UNION ALL
+        SELECT 'expense', e.id, e.description, e.category,
+            ts_rank(e.search_vector, q.query)::float8,
+            ts_headline('pt_unaccent', e.description || ' · ' || e.category, q.query, q.options)
+        FROM expenses e, q
+        WHERE e.search_vector @@ q.query AND e.deleted_at IS NULL
+          AND ($2::text[] IS NULL OR 'expense' = ANY($2))
+    ) results
+    ORDER BY rank DESC, title
+    LIMIT $3
+"#;
+
+/// Cada palavra vira um prefixo (`cafe:*`) e todas precisam aparecer; só
+/// letras e dígitos passam, para que a entrada nunca quebre a sintaxe do tsquery.
+fn to_tsquery(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{}:*", word))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            START_MARK => escaped.push_str("<mark>"),
+            STOP_MARK => escaped.push_str("</mark>"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// GET /api/search?q=cafe&types=product,expense&limit=20
+#[get("/search")]
+pub async fn search(pool: web::Data<DbPool>, query: web::Query<SearchQuery>) -> impl Responder {
+    if query.q.trim().chars().count() < 2 {
+        return bad_request("A busca precisa de ao menos 2 caracteres");
+    }
+    if !(1..=100).contains(&query.limit) {
+        return bad_request("limit deve estar entre 1 e 100");
+    }
+
+    let types: Option<Vec<String>> = query.types.as_deref().map(|types| {
+        types
+            .split(',')
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    });
+    if let Some(unknown) = types.iter().flatten().find(|t| !SEARCH_TYPES.contains(&t.as_str())) {
+        return bad_request(format!(
+            "Tipo desconhecido '{}': use {}",
+            unknown,
+            SEARCH_TYPES.join(", ")
+        ));
+    }
+    let types = types.filter(|types| !types.is_empty());
+
+    let tsquery = match to_tsquery(&query.q) {
+        Some(tsquery) => tsquery,
+        None => return bad_request("A busca precisa conter letras ou números"),
+    };
+
+    let results = query_as::<_, SearchResult>(SEARCH)
+        .bind(&tsquery)
+        .bind(&types)
+        .bind(query.limit)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match results {
+        Ok(mut results) => {
+            for result in &mut results {
+                result.highlight = escape_html(&result.highlight);
+            }
+            HttpResponse::Ok().json(json!({
+                "query": query.q,
+                "count": results.len(),
+                "results": results,
+            }))
+        }
+        Err(err) => internal_error("Erro ao buscar", err),
+    }
+}
+
+// Configura as rotas
+pub fn config_search(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
@@ -1,22 +1,64 @@
 // Handler de fornecedores
-use actix_web::{get, post, patch, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, patch, delete, http::StatusCode, web, HttpRequest, HttpResponse, Responder};
+use serde_json::json;
 use uuid::Uuid;
-use sqlx::query_as;
+use sqlx::{query_as, PgConnection};
+use std::{cmp::Reverse, collections::HashMap};
 
-use crate::{db::DbPool, models::supplier::Supplier, schema::{CreateSupplier, UpdateSupplier}};
+use crate::{audit::{self, Change}, auth::{AdminUser, OptionalUser}, db::DbPool, duplicates::{self, Contact, DuplicateMatch, SearchKeys}, errors::{bad_request, error_with, internal_error, not_found}, export::{self, ExportParams}, models::{expense::Expense, supplier::Supplier}, schema::{CreateSupplier, MergeRequest, UpdateSupplier}, versioning::{self, etag}};
+
+fn contact(supplier: &Supplier) -> Contact<'_> {
+    Contact {
+        id: supplier.id,
+        name: &supplier.name,
+        email: supplier.email.as_deref(),
+        phone: supplier.phone.as_deref(),
+        document: supplier.document.as_deref(),
+    }
+}
+
+/// Outros fornecedores parecidos com `supplier`.
+async fn similar_suppliers(conn: &mut PgConnection, supplier: &Supplier) -> Result<Vec<DuplicateMatch>, sqlx::Error> {
+    let keys = SearchKeys::new(&contact(supplier));
+    let others = query_as::<_, Supplier>(&format!("SELECT * FROM suppliers WHERE {}", duplicates::CANDIDATE_FILTER))
+        .bind(supplier.id)
+        .bind(&keys.document)
+        .bind(&keys.email)
+        .bind(&keys.phone)
+        .bind(&keys.name)
+        .fetch_all(conn)
+        .await?;
+    let others: Vec<Contact> = others.iter().map(contact).collect();
+    Ok(duplicates::matches_for(&contact(supplier), &others))
+}
 
 #[get("/suppliers")]
-pub async fn get_suppliers(pool: web::Data<DbPool>) -> impl Responder {
-    let suppliers = query_as::<_, Supplier>("SELECT * FROM suppliers ORDER BY name")
+pub async fn get_suppliers(
+    pool: web::Data<DbPool>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = "SELECT * FROM suppliers ORDER BY name";
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("fornecedores");
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Supplier>(sql).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let suppliers = query_as::<_, Supplier>(sql)
         .fetch_all(pool.get_ref())
         .await;
 
     match suppliers {
         Ok(data) => HttpResponse::Ok().json(data),
-        Err(err) => {
-            eprintln!("Erro ao buscar fornecedores: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Err(err) => internal_error("Erro ao buscar fornecedores", err),
     }
 }
 
@@ -33,12 +75,9 @@ pub async fn get_supplier_by_id(
         .await;
 
     match supplier {
-        Ok(Some(s)) => HttpResponse::Ok().json(s),
-        Ok(None) => HttpResponse::NotFound().body("Supplier not found"),
-        Err(err) => {
-            eprintln!("Erro ao buscar fornecedor por id: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+        Ok(Some(s)) => HttpResponse::Ok().insert_header(etag(s.version)).json(s),
+        Ok(None) => not_found("Supplier not found"),
+        Err(err) => internal_error("Erro ao buscar fornecedor por id", err),
     }
 }
 
@@ -46,25 +85,55 @@ pub async fn get_supplier_by_id(
 pub async fn create_supplier(
     pool: web::Data<DbPool>,
     supplier: web::Json<CreateSupplier>,
+    OptionalUser(user): OptionalUser,
 ) -> impl Responder {
     let id = Uuid::new_v4();
 
-    let result = sqlx::query!(
-        "INSERT INTO suppliers (id, name, email, phone) VALUES ($1, $2, $3, $4)",
-        id,
-        supplier.name,
-        supplier.email,
-        supplier.phone,
+    let document = match duplicates::document_input(supplier.document.as_deref()) {
+        Ok(document) => document,
+        Err(message) => return bad_request(message),
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let created = query_as::<_, Supplier>(
+        "INSERT INTO suppliers (id, name, email, phone, document) VALUES ($1, $2, $3, $4, $5) RETURNING *"
     )
-    .execute(pool.get_ref())
+    .bind(id)
+    .bind(&supplier.name)
+    .bind(&supplier.email)
+    .bind(&supplier.phone)
+    .bind(&document)
+    .fetch_one(&mut tx)
     .await;
 
-    match result {
-        Ok(_) => HttpResponse::Created().json(id),
-        Err(err) => {
-            eprintln!("Erro ao criar fornecedor: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let created = match created {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao criar fornecedor", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "supplier", id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    // A resposta continua sendo só o id; os parecidos vão no cabeçalho
+    let possible_duplicates = match similar_suppliers(&mut tx, &created).await {
+        Ok(found) => found,
+        Err(err) => return internal_error("Erro ao procurar fornecedores duplicados", err),
+    };
+
+    let mut response = HttpResponse::Created();
+    response.insert_header(etag(created.version));
+    if let Some(header) = duplicates::warning_header(&possible_duplicates) {
+        response.insert_header(header);
+    }
+
+    match tx.commit().await {
+        Ok(_) => response.json(id),
+        Err(err) => internal_error("Erro ao criar fornecedor", err),
     }
 }
 
@@ -73,48 +142,76 @@ pub async fn update_supplier(
     path: web::Path<Uuid>,
     supplier_update: web::Json<UpdateSupplier>,
     pool: web::Data<DbPool>,
+    OptionalUser(user): OptionalUser,
+    req: HttpRequest,
 ) -> impl Responder {
     let supplier_id = path.into_inner();
 
+    let expected = match versioning::expected_versions(&req) {
+        Ok(expected) => expected,
+        Err(res) => return res,
+    };
+
+    let document = match duplicates::document_input(supplier_update.document.as_deref()) {
+        Ok(document) => document,
+        Err(message) => return bad_request(message),
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
     // Verifica existência
-    let existing = query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = $1")
+    let existing = query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = $1 FOR UPDATE")
         .bind(supplier_id)
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(&mut tx)
         .await;
 
-    if let Err(err) = existing {
-        eprintln!("Erro ao buscar fornecedor para atualizar: {:?}", err);
-        return HttpResponse::InternalServerError().finish();
-    }
+    let existing = match existing {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Supplier not found"),
+        Err(err) => return internal_error("Erro ao buscar fornecedor para atualizar", err),
+    };
 
-    let existing = existing.unwrap();
-    if existing.is_none() {
-        return HttpResponse::NotFound().body("Supplier not found");
+    if !expected.matches(existing.version) {
+        return versioning::precondition_failed(existing.version);
     }
 
-    let update_result = sqlx::query!(
+    let updated = query_as::<_, Supplier>(
         r#"
         UPDATE suppliers
         SET
             name = COALESCE($1, name),
             email = COALESCE($2, email),
-            phone = COALESCE($3, phone)
-        WHERE id = $4
-        "#,
-        supplier_update.name.as_ref(),
-        supplier_update.email.as_ref(),
-        supplier_update.phone.as_ref(),
-        supplier_id,
+            phone = COALESCE($3, phone),
+            document = CASE WHEN $4 THEN $5 ELSE document END,
+            version = version + 1
+        WHERE id = $6
+        RETURNING *
+        "#
     )
-    .execute(pool.get_ref())
+    .bind(supplier_update.name.as_ref())
+    .bind(supplier_update.email.as_ref())
+    .bind(supplier_update.phone.as_ref())
+    .bind(supplier_update.document.is_some())
+    .bind(&document)
+    .bind(supplier_id)
+    .fetch_one(&mut tx)
     .await;
 
-    match update_result {
-        Ok(_) => HttpResponse::Ok().body("Supplier updated successfully"),
-        Err(err) => {
-            eprintln!("Erro ao atualizar fornecedor: {:?}", err);
-            HttpResponse::InternalServerError().finish()
-        }
+    let updated = match updated {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao atualizar fornecedor", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "supplier", supplier_id, Change::Updated(&existing, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).body("Supplier updated successfully"),
+        Err(err) => internal_error("Erro ao atualizar fornecedor", err),
     }
 }
 
@@ -122,26 +219,241 @@ pub async fn update_supplier(
 pub async fn delete_supplier(
     path: web::Path<Uuid>,
     pool: web::Data<DbPool>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let supplier_id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let deleted = query_as::<_, Supplier>("DELETE FROM suppliers WHERE id = $1 RETURNING *")
+        .bind(supplier_id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let deleted = match deleted {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Supplier not found"),
+        Err(err) => return internal_error("Erro ao deletar fornecedor", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "supplier", supplier_id, Change::Deleted(&deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => internal_error("Erro ao deletar fornecedor", err),
+    }
+}
+
+// GET /api/suppliers/duplicates - grupos de cadastros provavelmente repetidos
+#[get("/suppliers/duplicates")]
+pub async fn get_supplier_duplicates(pool: web::Data<DbPool>) -> impl Responder {
+    // Só os cadastros de algum par candidato, filtrado pelos índices
+    let candidates: Vec<(Uuid, Uuid)> = match sqlx::query_as(&duplicates::candidate_pairs("suppliers"))
+        .fetch_all(pool.get_ref())
+        .await
+    {
+        Ok(candidates) => candidates,
+        Err(err) => return internal_error("Erro ao procurar fornecedores duplicados", err),
+    };
+    let ids: Vec<Uuid> = candidates.iter().flat_map(|(first, second)| [*first, *second]).collect();
+
+    let suppliers = query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = ANY($1) ORDER BY name")
+        .bind(&ids)
+        .fetch_all(pool.get_ref())
+        .await;
+    let expenses: Result<Vec<(Uuid, i64)>, _> = sqlx::query_as(
+        "SELECT supplier_id, COUNT(*) FROM expenses WHERE supplier_id IS NOT NULL GROUP BY supplier_id"
+    )
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let (suppliers, expenses) = match (suppliers, expenses) {
+        (Ok(suppliers), Ok(expenses)) => (suppliers, expenses.into_iter().collect::<HashMap<Uuid, i64>>()),
+        (Err(err), _) | (_, Err(err)) => return internal_error("Erro ao procurar fornecedores duplicados", err),
+    };
+
+    let by_id: HashMap<Uuid, &Supplier> = suppliers.iter().map(|supplier| (supplier.id, supplier)).collect();
+    let contacts: Vec<Contact> = suppliers.iter().map(contact).collect();
+
+    // Sugere manter o cadastro com mais despesas (no empate, o primeiro por nome)
+    let groups: Vec<_> = duplicates::find_groups(&contacts, &candidates)
+        .into_iter()
+        .map(|(ids, pairs)| {
+            let expenses_of = |id: &Uuid| expenses.get(id).copied().unwrap_or(0);
+            let survivor = ids.iter().min_by_key(|id| Reverse(expenses_of(id))).copied();
+            let records: Vec<_> = ids
+                .iter()
+                .map(|id| json!({ "supplier": by_id[id], "expenses": expenses_of(id) }))
+                .collect();
+            json!({
+                "suggested_survivor": survivor,
+                "suppliers": records,
+                "pairs": pairs,
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "count": groups.len(),
+        "groups": groups,
+    }))
+}
+
+// POST /api/suppliers/{id}/merge - absorve os duplicados no fornecedor do
+// caminho: as despesas passam para ele, dados em branco são completados e os
+// duplicados são removidos
+#[post("/suppliers/{id}/merge")]
+pub async fn merge_suppliers(
+    path: web::Path<Uuid>,
+    body: web::Json<MergeRequest>,
+    pool: web::Data<DbPool>,
+    AdminUser(admin): AdminUser,
 ) -> impl Responder {
     let supplier_id = path.into_inner();
+    let user = Some(admin);
 
-    let result = sqlx::query!("DELETE FROM suppliers WHERE id = $1", supplier_id)
-        .execute(pool.get_ref())
+    if body.duplicate_ids.is_empty() {
+        return bad_request("Informe os fornecedores duplicados");
+    }
+    if body.duplicate_ids.contains(&supplier_id) {
+        return bad_request("O fornecedor mantido não pode estar entre os duplicados");
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let survivor = query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = $1 FOR UPDATE")
+        .bind(supplier_id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let survivor = match survivor {
+        Ok(Some(s)) => s,
+        Ok(None) => return not_found("Supplier not found"),
+        Err(err) => return internal_error("Erro ao buscar fornecedor", err),
+    };
+
+    let merged = query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = ANY($1) ORDER BY name FOR UPDATE")
+        .bind(&body.duplicate_ids)
+        .fetch_all(&mut tx)
         .await;
 
-    match result {
-        Ok(res) if res.rows_affected() > 0 => HttpResponse::NoContent().finish(),
-        Ok(_) => HttpResponse::NotFound().body("Supplier not found"),
-        Err(err) => {
-            eprintln!("Erro ao deletar fornecedor: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+    let merged = match merged {
+        Ok(merged) => merged,
+        Err(err) => return internal_error("Erro ao buscar fornecedores duplicados", err),
+    };
+
+    let missing: Vec<Uuid> = body
+        .duplicate_ids
+        .iter()
+        .filter(|id| !merged.iter().any(|supplier| supplier.id == **id))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return error_with(
+            StatusCode::NOT_FOUND,
+            "Fornecedores duplicados não encontrados",
+            json!({ "missing": missing }),
+        );
+    }
+
+    // Despesa só muda de fornecedor, inclusive as excluídas logicamente;
+    // valores e vencimentos ficam como estão
+    let expenses = query_as::<_, Expense>("SELECT * FROM expenses WHERE supplier_id = ANY($1) FOR UPDATE")
+        .bind(&body.duplicate_ids)
+        .fetch_all(&mut tx)
+        .await;
+
+    let expenses = match expenses {
+        Ok(expenses) => expenses,
+        Err(err) => return internal_error("Erro ao buscar despesas dos duplicados", err),
+    };
+
+    let moved = query_as::<_, Expense>(
+        "UPDATE expenses SET supplier_id = $1, version = version + 1 WHERE supplier_id = ANY($2) RETURNING *"
+    )
+    .bind(supplier_id)
+    .bind(&body.duplicate_ids)
+    .fetch_all(&mut tx)
+    .await;
+
+    let moved: HashMap<Uuid, Expense> = match moved {
+        Ok(moved) => moved.into_iter().map(|expense| (expense.id, expense)).collect(),
+        Err(err) => return internal_error("Erro ao transferir despesas", err),
+    };
+
+    for before in &expenses {
+        let after = &moved[&before.id];
+        if let Err(err) = audit::record(&mut tx, user.as_ref(), "expense", before.id, Change::Updated(before, after)).await {
+            return internal_error("Erro ao registrar auditoria", err);
         }
     }
+
+    let email = merged.iter().find_map(|s| s.email.as_deref()).filter(|_| survivor.email.is_none());
+    let phone = merged.iter().find_map(|s| s.phone.as_deref()).filter(|_| survivor.phone.is_none());
+    let document = merged.iter().find_map(|s| s.document.as_deref()).filter(|_| survivor.document.is_none());
+
+    let updated = query_as::<_, Supplier>(
+        r#"
+        UPDATE suppliers
+        SET email = COALESCE($1, email), phone = COALESCE($2, phone), document = COALESCE($3, document),
+            version = version + 1
+        WHERE id = $4
+        RETURNING *
+        "#
+    )
+    .bind(email)
+    .bind(phone)
+    .bind(document)
+    .bind(supplier_id)
+    .fetch_one(&mut tx)
+    .await;
+
+    let updated = match updated {
+        Ok(s) => s,
+        Err(err) => return internal_error("Erro ao atualizar fornecedor", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "supplier", supplier_id, Change::Updated(&survivor, &updated)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = sqlx::query("DELETE FROM suppliers WHERE id = ANY($1)")
+        .bind(&body.duplicate_ids)
+        .execute(&mut tx)
+        .await
+    {
+        return internal_error("Erro ao remover fornecedores duplicados", err);
+    }
+
+    for supplier in &merged {
+        if let Err(err) = audit::record(&mut tx, user.as_ref(), "supplier", supplier.id, Change::Deleted(supplier)).await {
+            return internal_error("Erro ao registrar auditoria", err);
+        }
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(updated.version)).json(json!({
+            "supplier": updated,
+            "merged": merged.len(),
+            "reassigned_expenses": moved.len(),
+        })),
+        Err(err) => internal_error("Erro ao mesclar fornecedores", err),
+    }
 }
 
 // Configura rotas para este handler
 pub fn config_suppliers(cfg: &mut web::ServiceConfig) {
     cfg.service(get_suppliers)
+        .service(get_supplier_duplicates)
+        .service(merge_suppliers)
         .service(get_supplier_by_id)
         .service(create_supplier)
         .service(update_supplier)
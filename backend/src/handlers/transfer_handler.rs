@@ -0,0 +1,260 @@
+// Transferências entre contas
+// src/handlers/transfer_handler.rs
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::Local;
+use sqlx::query_as;
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, Change},
+    auth::OptionalUser,
+    db::DbPool,
+    errors::{bad_request, internal_error, not_found},
+    export::{self, ExportParams},
+    ledger,
+    models::transfer::{CreateTransfer, Transfer, TransferQuery},
+    periods,
+    schema::ListParams,
+    versioning::etag,
+};
+
+// GET /api/transfers?account_id=
+#[get("/transfers")]
+pub async fn get_transfers(
+    pool: web::Data<DbPool>,
+    params: web::Query<TransferQuery>,
+    req: HttpRequest,
+    export: web::Query<ExportParams>,
+) -> impl Responder {
+    let sql = r#"
+        SELECT * FROM transfers
+        WHERE ($1::uuid IS NULL OR from_account_id = $1 OR to_account_id = $1)
+          AND ($2 OR deleted_at IS NULL)
+        ORDER BY transferred_on DESC, created_at DESC
+    "#;
+
+    match export.file_format(&req) {
+        Err(response) => return response,
+        Ok(Some(format)) => {
+            let filename = export::filename("transferencias");
+            let (account_id, include_deleted) = (params.account_id, params.include_deleted);
+            return export::respond(pool.get_ref(), format, export.locale, &filename, move |pool| {
+                query_as::<_, Transfer>(sql).bind(account_id).bind(include_deleted).fetch(pool)
+            })
+            .await;
+        }
+        Ok(None) => {}
+    }
+
+    let transfers = query_as::<_, Transfer>(sql)
+        .bind(params.account_id)
+        .bind(params.include_deleted)
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match transfers {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => internal_error("Erro ao buscar transferências", err),
+    }
+}
+
+// GET /api/transfers/{id}
+#[get("/transfers/{id}")]
+pub async fn get_transfer_by_id(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    params: web::Query<ListParams>,
+) -> impl Responder {
+    let transfer = query_as::<_, Transfer>(
+        "SELECT * FROM transfers WHERE id = $1 AND ($2 OR deleted_at IS NULL)"
+    )
+    .bind(path.into_inner())
+    .bind(params.include_deleted)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match transfer {
+        Ok(Some(transfer)) => HttpResponse::Ok().insert_header(etag(transfer.version)).json(transfer),
+        Ok(None) => not_found("Transferência não encontrada"),
+        Err(err) => internal_error("Erro ao buscar transferência", err),
+    }
+}
+
+// POST /api/transfers
+#[post("/transfers")]
+pub async fn create_transfer(
+    pool: web::Data<DbPool>,
+    transfer: web::Json<CreateTransfer>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    if !(transfer.amount.is_finite() && transfer.amount > 0.0) {
+        return bad_request("O valor da transferência deve ser positivo");
+    }
+    if transfer.from_account_id == transfer.to_account_id {
+        return bad_request("As contas de origem e destino devem ser diferentes");
+    }
+
+    let new_id = Uuid::new_v4();
+    let transferred_on = transfer.transferred_on.unwrap_or_else(|| Local::now().date_naive());
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[transferred_on]).await {
+        return res;
+    }
+
+    let created = query_as::<_, Transfer>(
+        r#"
+        INSERT INTO transfers (id, from_account_id, to_account_id, amount, transferred_on, description)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#
+    )
+    .bind(new_id)
+    .bind(transfer.from_account_id)
+    .bind(transfer.to_account_id)
+    .bind(transfer.amount)
+    .bind(transferred_on)
+    .bind(&transfer.description)
+    .fetch_one(&mut tx)
+    .await;
+
+    let created = match created {
+        Ok(transfer) => transfer,
+        Err(sqlx::Error::Database(db_err)) if db_err.code().as_deref() == Some("23503") => {
+            return bad_request("Conta não encontrada");
+        }
+        Err(err) => return internal_error("Erro ao criar transferência", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "transfer", new_id, Change::Created(&created)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "transfer", new_id, ledger::transfer_entry(&created)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Created().insert_header(etag(created.version)).json(created),
+        Err(err) => internal_error("Erro ao criar transferência", err),
+    }
+}
+
+// DELETE /api/transfers/{id} - exclusão lógica
+#[delete("/transfers/{id}")]
+pub async fn delete_transfer(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Transfer>("SELECT * FROM transfers WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(transfer)) => transfer,
+        Ok(None) => return not_found("Transferência não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar transferência para deletar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[existing.transferred_on]).await {
+        return res;
+    }
+
+    let deleted = query_as::<_, Transfer>("UPDATE transfers SET deleted_at = NOW(), version = version + 1 WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let deleted = match deleted {
+        Ok(transfer) => transfer,
+        Err(err) => return internal_error("Erro ao deletar transferência", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "transfer", id, Change::SoftDeleted(&existing, &deleted)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "transfer", id, ledger::transfer_entry(&deleted)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().body("Transferência deletada"),
+        Err(err) => internal_error("Erro ao deletar transferência", err),
+    }
+}
+
+// POST /api/transfers/{id}/restore - desfaz a exclusão lógica
+#[post("/transfers/{id}/restore")]
+pub async fn restore_transfer(
+    pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    OptionalUser(user): OptionalUser,
+) -> impl Responder {
+    let id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return internal_error("Erro ao iniciar transação", err),
+    };
+
+    let existing = query_as::<_, Transfer>("SELECT * FROM transfers WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await;
+
+    let existing = match existing {
+        Ok(Some(transfer)) => transfer,
+        Ok(None) => return not_found("Transferência excluída não encontrada"),
+        Err(err) => return internal_error("Erro ao buscar transferência para restaurar", err),
+    };
+
+    if let Err(res) = periods::ensure_open(&mut tx, &[existing.transferred_on]).await {
+        return res;
+    }
+
+    let restored = query_as::<_, Transfer>("UPDATE transfers SET deleted_at = NULL, version = version + 1 WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await;
+
+    let restored = match restored {
+        Ok(transfer) => transfer,
+        Err(err) => return internal_error("Erro ao restaurar transferência", err),
+    };
+
+    if let Err(err) = audit::record(&mut tx, user.as_ref(), "transfer", id, Change::Restored(&existing, &restored)).await {
+        return internal_error("Erro ao registrar auditoria", err);
+    }
+
+    if let Err(err) = ledger::sync(&mut tx, user.as_ref(), "transfer", id, ledger::transfer_entry(&restored)).await {
+        return internal_error("Erro ao gerar lançamento contábil", err);
+    }
+
+    match tx.commit().await {
+        Ok(_) => HttpResponse::Ok().insert_header(etag(restored.version)).json(restored),
+        Err(err) => internal_error("Erro ao restaurar transferência", err),
+    }
+}
+
+// Configura as rotas
+pub fn config_transfers(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_transfers)
+       .service(get_transfer_by_id)
+       .service(create_transfer)
+       .service(delete_transfer)
+       .service(restore_transfer);
+}
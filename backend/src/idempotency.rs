@@ -0,0 +1,351 @@
+// Chaves de idempotência para os POST
+// src/idempotency.rs
+//
+// Um POST com `Idempotency-Key` tem a resposta guardada por 24h. Uma
+// retentativa com a mesma chave e o mesmo corpo recebe a resposta original
+// (com `Idempotent-Replayed: true`) em vez de criar outra venda/pagamento.
+// A chave vale por usuário e rota: a mesma chave em outro endpoint, ou
+// enviada por outra pessoa, é uma requisição independente.
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    middleware::Next,
+    web, Error, HttpMessage, HttpResponse,
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+
+use crate::{auth, db::DbPool, errors, upload::MAX_FILE_BYTES};
+
+pub const HEADER: &str = "idempotency-key";
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+const TTL_HOURS: i64 = 24;
+/// Depois deste tempo uma requisição ainda "em processamento" é tida como
+/// abandonada (ex.: o servidor caiu) e a chave pode ser reutilizada.
+const LOCK_TIMEOUT_SECS: i64 = 300;
+/// Limite do corpo lido para o hash. Cobre a importação de CSV (o maior POST
+/// da API) com folga para o envelope multipart.
+const MAX_BODY_BYTES: usize = MAX_FILE_BYTES + 1024 * 1024;
+
+/// Identifica uma chave: quem enviou, para qual rota e com qual valor.
+struct Scope {
+    user_id: String,
+    route: String,
+    key: String,
+}
+
+struct StoredResponse {
+    status_code: i16,
+    content_type: Option<String>,
+    etag: Option<String>,
+    body: Vec<u8>,
+}
+
+enum Acquired {
+    /// Chave nova (ou abandonada): esta requisição deve ser processada.
+    New,
+    /// A chave já foi usada com outro corpo.
+    Mismatch,
+    InProgress,
+    Completed(StoredResponse),
+}
+
+pub async fn replay(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    if req.method() != Method::POST || !req.headers().contains_key(HEADER) {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let key = match req
+        .headers()
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid(value))
+    {
+        Some(key) => key.to_string(),
+        None => {
+            return Ok(req.into_response(errors::bad_request(
+                "Idempotency-Key inválida (use até 255 caracteres visíveis)",
+            )))
+        }
+    };
+
+    let pool = match req.app_data::<web::Data<DbPool>>() {
+        Some(pool) => pool.clone(),
+        None => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+
+    let user_id = match auth::optional_user(req.request()) {
+        Ok(user) => user.map(|user| user.id).unwrap_or_default(),
+        // Token inválido: o handler responde 401 e não há o que guardar
+        Err(_) => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+    let scope = Scope {
+        user_id,
+        route: format!("{} {}", req.method(), req.path()),
+        key,
+    };
+
+    if req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok())
+        .is_some_and(|length| length > MAX_BODY_BYTES)
+    {
+        return Ok(req.into_response(too_large()));
+    }
+
+    // O corpo é lido aqui, em blocos, para compor o hash e devolvido à requisição
+    let mut payload = req.take_payload();
+    let mut hasher = Sha256::new();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Ok(req.into_response(too_large()));
+        }
+        hasher.update(&chunk);
+        body.extend_from_slice(&chunk);
+    }
+    let hash = hex::encode(hasher.finalize());
+    req.set_payload(body.freeze().into());
+
+    match acquire(&pool, &scope, &hash).await {
+        Ok(Acquired::New) => {}
+        Ok(Acquired::Mismatch) => {
+            return Ok(req.into_response(errors::unprocessable(
+                "Idempotency-Key já utilizada com outra requisição",
+            )))
+        }
+        Ok(Acquired::InProgress) => {
+            return Ok(req.into_response(errors::conflict(
+                "Requisição com esta Idempotency-Key ainda em processamento",
+            )))
+        }
+        Ok(Acquired::Completed(stored)) => {
+            log::info!("Idempotency-Key {} reaproveitada em {}", scope.key, scope.route);
+            return Ok(req.into_response(stored_response(stored)));
+        }
+        Err(err) => {
+            return Ok(req.into_response(errors::internal_error(
+                "Erro ao verificar Idempotency-Key",
+                err,
+            )))
+        }
+    }
+
+    let res = match next.call(req).await {
+        Ok(res) => res,
+        Err(err) => {
+            release(&pool, &scope).await;
+            return Err(err);
+        }
+    };
+
+    // Falhas do servidor não são guardadas: o cliente pode tentar de novo
+    if res.status().is_server_error() {
+        release(&pool, &scope).await;
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (http_req, response) = res.into_parts();
+    let (response, body) = response.into_parts();
+    let body = match body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(err) => {
+            release(&pool, &scope).await;
+            let err: Box<dyn std::error::Error> = err.into();
+            return Err(actix_web::error::ErrorInternalServerError(err.to_string()));
+        }
+    };
+
+    let stored = StoredResponse {
+        status_code: response.status().as_u16() as i16,
+        content_type: header_string(&response, header::CONTENT_TYPE),
+        etag: header_string(&response, header::ETAG),
+        body: body.to_vec(),
+    };
+    if let Err(err) = store(&pool, &scope, &stored).await {
+        // A resposta segue para o cliente; só a proteção contra retentativa se perde
+        log::error!("Erro ao guardar resposta da Idempotency-Key {}: {:?}", scope.key, err);
+        release(&pool, &scope).await;
+    }
+
+    let response = response.set_body(body).map_into_boxed_body();
+    Ok(ServiceResponse::new(http_req, response))
+}
+
+async fn acquire(pool: &DbPool, scope: &Scope, hash: &str) -> Result<Acquired, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    sqlx::query!("DELETE FROM idempotency_keys WHERE expires_at < $1", now)
+        .execute(pool)
+        .await?;
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO idempotency_keys (user_id, route, key, request_hash, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, route, key) DO NOTHING
+        RETURNING key
+        "#,
+        scope.user_id,
+        scope.route,
+        scope.key,
+        hash,
+        now,
+        now + Duration::hours(TTL_HOURS),
+    )
+    .fetch_optional(pool)
+    .await?;
+    if inserted.is_some() {
+        return Ok(Acquired::New);
+    }
+
+    let existing = sqlx::query!(
+        r#"
+        SELECT request_hash, status_code, content_type, etag, response_body, created_at
+        FROM idempotency_keys
+        WHERE user_id = $1 AND route = $2 AND key = $3
+        "#,
+        scope.user_id,
+        scope.route,
+        scope.key,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let existing = match existing {
+        Some(existing) => existing,
+        // Expirou entre o INSERT e o SELECT; trata como chave em uso
+        None => return Ok(Acquired::InProgress),
+    };
+
+    if existing.request_hash != hash {
+        return Ok(Acquired::Mismatch);
+    }
+
+    match existing.status_code {
+        Some(status_code) => Ok(Acquired::Completed(StoredResponse {
+            status_code,
+            content_type: existing.content_type,
+            etag: existing.etag,
+            body: existing.response_body.unwrap_or_default(),
+        })),
+        None if is_abandoned(existing.created_at, now) => take_over(pool, scope, now).await,
+        None => Ok(Acquired::InProgress),
+    }
+}
+
+fn is_abandoned(created_at: NaiveDateTime, now: NaiveDateTime) -> bool {
+    now - created_at > Duration::seconds(LOCK_TIMEOUT_SECS)
+}
+
+async fn take_over(pool: &DbPool, scope: &Scope, now: NaiveDateTime) -> Result<Acquired, sqlx::Error> {
+    let taken = sqlx::query!(
+        r#"
+        UPDATE idempotency_keys
+        SET created_at = $4, expires_at = $5
+        WHERE user_id = $1 AND route = $2 AND key = $3
+          AND status_code IS NULL AND created_at < $6
+        RETURNING key
+        "#,
+        scope.user_id,
+        scope.route,
+        scope.key,
+        now,
+        now + Duration::hours(TTL_HOURS),
+        now - Duration::seconds(LOCK_TIMEOUT_SECS),
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match taken {
+        Some(_) => Acquired::New,
+        None => Acquired::InProgress,
+    })
+}
+
+async fn store(pool: &DbPool, scope: &Scope, stored: &StoredResponse) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE idempotency_keys
+        SET status_code = $4, content_type = $5, etag = $6, response_body = $7
+        WHERE user_id = $1 AND route = $2 AND key = $3
+        "#,
+        scope.user_id,
+        scope.route,
+        scope.key,
+        stored.status_code,
+        stored.content_type,
+        stored.etag,
+        stored.body,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Libera a chave para que uma nova tentativa seja processada.
+async fn release(pool: &DbPool, scope: &Scope) {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE user_id = $1 AND route = $2 AND key = $3 AND status_code IS NULL
+        "#,
+        scope.user_id,
+        scope.route,
+        scope.key,
+    )
+    .execute(pool)
+    .await;
+
+    if let Err(err) = result {
+        log::error!("Erro ao liberar Idempotency-Key {}: {:?}", scope.key, err);
+    }
+}
+
+fn stored_response(stored: StoredResponse) -> HttpResponse {
+    let status = StatusCode::from_u16(stored.status_code as u16).unwrap_or(StatusCode::OK);
+    let mut builder = HttpResponse::build(status);
+    if let Some(content_type) = stored.content_type {
+        builder.insert_header((header::CONTENT_TYPE, content_type));
+    }
+    if let Some(etag) = stored.etag {
+        builder.insert_header((header::ETAG, etag));
+    }
+    builder.insert_header((
+        HeaderName::from_static(REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    ));
+    builder.body(stored.body)
+}
+
+fn too_large() -> HttpResponse {
+    errors::error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!("Corpo maior que o limite de {} MB", MAX_BODY_BYTES / (1024 * 1024)),
+    )
+}
+
+fn header_string<B>(response: &HttpResponse<B>, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+fn is_valid(value: &str) -> bool {
+    !value.is_empty() && value.len() <= 255 && value.chars().all(|c| c.is_ascii_graphic())
+}
@@ -0,0 +1,456 @@
+// Lançamentos contábeis em partidas dobradas
+// src/ledger.rs
+//
+// Cada operação (venda, despesa, pagamento, transferência, saldo inicial)
+// mantém um lançamento ativo, identificado por `source`/`source_id`. Quando a
+// operação muda, o lançamento ativo é estornado e um novo é gerado, de modo
+// que o razão nunca é reescrito. Movimentos de estoque são só acrescentados.
+use chrono::{Local, NaiveDate};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthUser,
+    models::{
+        bank::Account, expense::Expense, payment::Payment, product::Product, sale::Sale,
+        transfer::Transfer,
+    },
+};
+
+// Plano de contas (migração 0010)
+pub const CASH_AND_BANKS: &str = "1.1.01";
+pub const INVENTORY: &str = "1.1.02";
+pub const PAYABLES: &str = "2.1.01";
+pub const OPENING_EQUITY: &str = "3.1.01";
+pub const STOCK_ADJUSTMENTS: &str = "3.2.01";
+pub const SALES_REVENUE: &str = "4.1.01";
+pub const OPERATING_EXPENSES: &str = "5.1.01";
+pub const COST_OF_GOODS_SOLD: &str = "5.1.02"; // migração 0019
+
+/// Diferença máxima aceita entre débitos e créditos (arredondamento).
+const TOLERANCE: f64 = 0.005;
+
+/// Conta contábil de uma partida.
+pub enum Target {
+    /// Conta do plano, pelo código
+    Code(&'static str),
+    /// Conta contábil da conta financeira (caixa/banco)
+    Financial(Uuid),
+    Id(Uuid),
+}
+
+pub struct Posting {
+    pub target: Target,
+    pub debit: f64,
+    pub credit: f64,
+}
+
+impl Posting {
+    pub fn debit(target: Target, amount: f64) -> Self {
+        Posting { target, debit: amount, credit: 0.0 }
+    }
+
+    pub fn credit(target: Target, amount: f64) -> Self {
+        Posting { target, debit: 0.0, credit: amount }
+    }
+}
+
+pub struct NewEntry {
+    pub date: NaiveDate,
+    pub description: String,
+    pub lines: Vec<Posting>,
+}
+
+/// Confere as partidas: ao menos duas, cada uma só a débito ou só a crédito,
+/// com valores positivos, e total de débitos igual ao de créditos.
+pub fn validate(lines: &[Posting]) -> Result<(), String> {
+    if lines.len() < 2 {
+        return Err("O lançamento precisa de ao menos duas partidas".to_string());
+    }
+    for (index, line) in lines.iter().enumerate() {
+        let valid = line.debit.is_finite()
+            && line.credit.is_finite()
+            && line.debit >= 0.0
+            && line.credit >= 0.0
+            && ((line.debit > 0.0) != (line.credit > 0.0));
+        if !valid {
+            return Err(format!(
+                "Partida {}: informe um valor positivo só no débito ou só no crédito",
+                index + 1
+            ));
+        }
+    }
+
+    let debits: f64 = lines.iter().map(|l| l.debit).sum();
+    let credits: f64 = lines.iter().map(|l| l.credit).sum();
+    if (debits - credits).abs() > TOLERANCE {
+        return Err(format!(
+            "Lançamento desbalanceado: débitos {:.2}, créditos {:.2}",
+            debits, credits
+        ));
+    }
+    Ok(())
+}
+
+/// Grava um lançamento novo. Deve ser chamada na transação da operação.
+pub async fn post(
+    conn: &mut PgConnection,
+    user: Option<&AuthUser>,
+    source: &str,
+    source_id: Option<Uuid>,
+    entry: NewEntry,
+) -> Result<Uuid, sqlx::Error> {
+    validate(&entry.lines).map_err(sqlx::Error::Protocol)?;
+
+    let mut resolved = Vec::with_capacity(entry.lines.len());
+    for line in &entry.lines {
+        resolved.push((resolve(conn, &line.target).await?, line.debit, line.credit));
+    }
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO journal_entries (id, entry_date, description, source, source_id, user_id) VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(id)
+    .bind(entry.date)
+    .bind(&entry.description)
+    .bind(source)
+    .bind(source_id)
+    .bind(user.map(|u| u.id.as_str()))
+    .execute(&mut *conn)
+    .await?;
+
+    insert_lines(conn, id, &resolved).await?;
+    Ok(id)
+}
+
+/// Deixa o lançamento ativo da operação igual a `entry`: estorna o atual (se
+/// diferente) e grava o novo. `None` apenas estorna (operação excluída).
+pub async fn sync(
+    conn: &mut PgConnection,
+    user: Option<&AuthUser>,
+    source: &str,
+    source_id: Uuid,
+    entry: Option<NewEntry>,
+) -> Result<(), sqlx::Error> {
+    let active = sqlx::query_as::<_, (Uuid, NaiveDate)>(
+        r#"
+        SELECT id, entry_date FROM journal_entries
+        WHERE source = $1 AND source_id = $2 AND reverses IS NULL AND reversed_by IS NULL
+        ORDER BY created_at
+        "#
+    )
+    .bind(source)
+    .bind(source_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if let (Some(entry), [(current, date)]) = (&entry, active.as_slice()) {
+        if *date == entry.date && same_lines(conn, *current, &entry.lines).await? {
+            return Ok(());
+        }
+    }
+
+    for (current, _) in &active {
+        reverse(conn, user, *current).await?;
+    }
+    if let Some(entry) = entry {
+        post(conn, user, source, Some(source_id), entry).await?;
+    }
+    Ok(())
+}
+
+/// Estorno: lançamento com as mesmas partidas invertidas, na mesma data.
+async fn reverse(conn: &mut PgConnection, user: Option<&AuthUser>, entry_id: Uuid) -> Result<(), sqlx::Error> {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO journal_entries (id, entry_date, description, source, source_id, reverses, user_id)
+        SELECT $1, entry_date, 'Estorno: ' || description, source, source_id, id, $3
+        FROM journal_entries WHERE id = $2
+        "#
+    )
+    .bind(id)
+    .bind(entry_id)
+    .bind(user.map(|u| u.id.as_str()))
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO journal_lines (entry_id, ledger_account_id, debit, credit)
+        SELECT $1, ledger_account_id, credit, debit FROM journal_lines WHERE entry_id = $2
+        "#
+    )
+    .bind(id)
+    .bind(entry_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE journal_entries SET reversed_by = $1 WHERE id = $2")
+        .bind(id)
+        .bind(entry_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn same_lines(conn: &mut PgConnection, entry_id: Uuid, lines: &[Posting]) -> Result<bool, sqlx::Error> {
+    let mut current = sqlx::query_as::<_, (Uuid, f64, f64)>(
+        "SELECT ledger_account_id, debit, credit FROM journal_lines WHERE entry_id = $1"
+    )
+    .bind(entry_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut wanted = Vec::with_capacity(lines.len());
+    for line in lines {
+        wanted.push((resolve(conn, &line.target).await?, line.debit, line.credit));
+    }
+
+    let key = |line: &(Uuid, f64, f64)| (line.0, cents(line.1), cents(line.2));
+    current.sort_by_key(key);
+    wanted.sort_by_key(key);
+    Ok(current.len() == wanted.len() && current.iter().zip(&wanted).all(|(a, b)| key(a) == key(b)))
+}
+
+async fn insert_lines(conn: &mut PgConnection, entry_id: Uuid, lines: &[(Uuid, f64, f64)]) -> Result<(), sqlx::Error> {
+    for (account, debit, credit) in lines {
+        sqlx::query("INSERT INTO journal_lines (entry_id, ledger_account_id, debit, credit) VALUES ($1, $2, $3, $4)")
+            .bind(entry_id)
+            .bind(account)
+            .bind(debit)
+            .bind(credit)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn resolve(conn: &mut PgConnection, target: &Target) -> Result<Uuid, sqlx::Error> {
+    match target {
+        Target::Id(id) => Ok(*id),
+        Target::Code(code) => {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM ledger_accounts WHERE code = $1")
+                .bind(code)
+                .fetch_one(conn)
+                .await
+        }
+        Target::Financial(account_id) => {
+            let existing = sqlx::query_scalar::<_, Uuid>("SELECT id FROM ledger_accounts WHERE financial_account_id = $1")
+                .bind(account_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+            match existing {
+                Some(id) => Ok(id),
+                None => create_financial_account(conn, *account_id).await,
+            }
+        }
+    }
+}
+
+/// Conta contábil (1.1.01.NN) de uma conta financeira, com o mesmo nome.
+async fn create_financial_account(conn: &mut PgConnection, account_id: Uuid) -> Result<Uuid, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO ledger_accounts (id, code, name, kind, financial_account_id)
+        SELECT $1,
+            $3 || '.' || lpad((COALESCE(
+                (SELECT MAX(split_part(code, '.', 4)::int) FROM ledger_accounts WHERE code LIKE $3 || '.%'),
+                0) + 1)::text, 2, '0'),
+            a.name, 'asset', a.id
+        FROM accounts a WHERE a.id = $2
+        RETURNING id
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(account_id)
+    .bind(CASH_AND_BANKS)
+    .fetch_one(conn)
+    .await
+}
+
+/// Cria ou renomeia a conta contábil da conta financeira.
+pub async fn sync_financial_account(conn: &mut PgConnection, account: &Account) -> Result<(), sqlx::Error> {
+    resolve(conn, &Target::Financial(account.id)).await?;
+    sqlx::query("UPDATE ledger_accounts SET name = $1 WHERE financial_account_id = $2")
+        .bind(&account.name)
+        .bind(account.id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+fn cents(value: f64) -> i64 {
+    (value * 100.0).round() as i64
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// Lançamentos de cada operação. `None` quando não há o que contabilizar
+// (registro excluído ou valor zero).
+
+/// Venda: D caixa/banco de recebimento, C receita de vendas; e a baixa do
+/// estoque vendido: D custo das mercadorias vendidas, C estoque, pelo custo
+/// unitário guardado na venda. Sem custo, só a receita.
+pub fn sale_entry(sale: &Sale) -> Option<NewEntry> {
+    let cost = sale.quantity as f64 * sale.unit_cost.unwrap_or(0.0);
+    if sale.deleted_at.is_some() || (cents(sale.total_price) == 0 && cents(cost) == 0) {
+        return None;
+    }
+
+    let mut lines = Vec::with_capacity(4);
+    if cents(sale.total_price) != 0 {
+        lines.push(Posting::debit(Target::Financial(sale.account_id), sale.total_price));
+        lines.push(Posting::credit(Target::Code(SALES_REVENUE), sale.total_price));
+    }
+    if cents(cost) != 0 {
+        lines.push(Posting::debit(Target::Code(COST_OF_GOODS_SOLD), cost));
+        lines.push(Posting::credit(Target::Code(INVENTORY), cost));
+    }
+
+    Some(NewEntry {
+        date: sale.created_at.map(|d| d.date()).unwrap_or_else(today),
+        description: format!("Venda: {} x{}", sale.product_name, sale.quantity),
+        lines,
+    })
+}
+
+/// Mantém o lançamento ativo da venda.
+pub async fn sync_sale(conn: &mut PgConnection, user: Option<&AuthUser>, sale: &Sale) -> Result<(), sqlx::Error> {
+    sync(conn, user, "sale", sale.id, sale_entry(sale)).await
+}
+
+/// Despesa (competência no vencimento): D despesas, C fornecedores a pagar.
+pub fn expense_entry(expense: &Expense) -> Option<NewEntry> {
+    if expense.deleted_at.is_some() || cents(expense.amount) == 0 {
+        return None;
+    }
+    Some(NewEntry {
+        date: expense.due_date,
+        description: format!("Despesa: {}", expense.description),
+        lines: vec![
+            Posting::debit(Target::Code(OPERATING_EXPENSES), expense.amount),
+            Posting::credit(Target::Code(PAYABLES), expense.amount),
+        ],
+    })
+}
+
+/// Pagamento: D fornecedores a pagar, C caixa/banco de saída.
+pub fn payment_entry(payment: &Payment) -> Option<NewEntry> {
+    if payment.deleted_at.is_some() || cents(payment.amount) == 0 {
+        return None;
+    }
+    Some(NewEntry {
+        date: payment.payment_date.unwrap_or_else(today),
+        description: match &payment.method {
+            Some(method) => format!("Pagamento ({})", method),
+            None => "Pagamento".to_string(),
+        },
+        lines: vec![
+            Posting::debit(Target::Code(PAYABLES), payment.amount),
+            Posting::credit(Target::Financial(payment.account_id), payment.amount),
+        ],
+    })
+}
+
+/// Transferência: D conta de destino, C conta de origem.
+pub fn transfer_entry(transfer: &Transfer) -> Option<NewEntry> {
+    if transfer.deleted_at.is_some() {
+        return None;
+    }
+    Some(NewEntry {
+        date: transfer.transferred_on,
+        description: transfer
+            .description
+            .clone()
+            .unwrap_or_else(|| "Transferência entre contas".to_string()),
+        lines: vec![
+            Posting::debit(Target::Financial(transfer.to_account_id), transfer.amount),
+            Posting::credit(Target::Financial(transfer.from_account_id), transfer.amount),
+        ],
+    })
+}
+
+/// Saldo inicial da conta financeira contra o patrimônio líquido.
+pub fn opening_balance_entry(account: &Account) -> Option<NewEntry> {
+    let amount = account.opening_balance.abs();
+    if cents(amount) == 0 {
+        return None;
+    }
+    let (cash, equity) = (Target::Financial(account.id), Target::Code(OPENING_EQUITY));
+    let lines = if account.opening_balance > 0.0 {
+        vec![Posting::debit(cash, amount), Posting::credit(equity, amount)]
+    } else {
+        vec![Posting::debit(equity, amount), Posting::credit(cash, amount)]
+    };
+    Some(NewEntry {
+        date: account.created_at.date(),
+        description: format!("Saldo inicial: {}", account.name),
+        lines,
+    })
+}
+
+// O estoque é avaliado pelo custo cadastrado do produto; produtos sem custo
+// ficam fora do razão. As vendas dão baixa pelo custo guardado nelas (ver
+// `sale_entry`), e os ajustes manuais do cadastro passam por `post_stock`.
+
+/// Lança um ajuste manual de estoque do produto (quantidade com sinal).
+pub async fn post_stock(
+    conn: &mut PgConnection,
+    user: Option<&AuthUser>,
+    product: &Product,
+    quantity: i32,
+) -> Result<(), sqlx::Error> {
+    let entry = product.cost.and_then(|cost| stock_entry(&product.name, quantity, cost));
+    if let Some(entry) = entry {
+        post(conn, user, "stock", Some(product.id), entry).await?;
+    }
+    Ok(())
+}
+
+/// Reavalia o estoque de `existing` quando o custo do produto muda, para que
+/// o saldo continue igual a estoque × custo.
+pub async fn post_revaluation(
+    conn: &mut PgConnection,
+    user: Option<&AuthUser>,
+    existing: &Product,
+    updated: &Product,
+) -> Result<(), sqlx::Error> {
+    let difference = existing.stock as f64 * (updated.cost.unwrap_or(0.0) - existing.cost.unwrap_or(0.0));
+    if let Some(entry) = adjustment_entry(format!("Reavaliação de estoque: {}", updated.name), difference) {
+        post(conn, user, "stock", Some(updated.id), entry).await?;
+    }
+    Ok(())
+}
+
+/// Entrada (+) ou saída (-) de estoque, avaliada pelo custo unitário.
+pub fn stock_entry(product_name: &str, quantity: i32, unit_cost: f64) -> Option<NewEntry> {
+    let kind = if quantity > 0 { "Entrada" } else { "Saída" };
+    let description = format!("{} de estoque: {} ({} un.)", kind, product_name, quantity.abs());
+    adjustment_entry(description, quantity as f64 * unit_cost)
+}
+
+/// Estoque de um produto ainda sem lançamentos, na reconstrução do razão:
+/// o saldo atual mais o que as vendas já lançadas ou a lançar vão baixar.
+pub fn opening_stock_entry(product_name: &str, value: f64) -> Option<NewEntry> {
+    adjustment_entry(format!("Estoque inicial: {}", product_name), value)
+}
+
+/// Ajuste de estoque contra a conta de ajustes: valor positivo aumenta o
+/// estoque, negativo reduz.
+fn adjustment_entry(description: String, value: f64) -> Option<NewEntry> {
+    let amount = value.abs();
+    if cents(amount) == 0 {
+        return None;
+    }
+    let (inventory, adjustments) = (Target::Code(INVENTORY), Target::Code(STOCK_ADJUSTMENTS));
+    let lines = if value > 0.0 {
+        vec![Posting::debit(inventory, amount), Posting::credit(adjustments, amount)]
+    } else {
+        vec![Posting::debit(adjustments, amount), Posting::credit(inventory, amount)]
+    };
+    Some(NewEntry { date: today(), description, lines })
+}
@@ -0,0 +1,184 @@
+// Painel ao vivo: indicadores enviados por Server-Sent Events
+// src/live.rs
+//
+// Gatilhos nas tabelas de vendas, despesas e pagamentos avisam pelo canal
+// `dashboard_events` (LISTEN/NOTIFY). Uma tarefa escuta o canal, junta os
+// avisos que chegam em sequência, recalcula os indicadores e publica só o
+// que mudou para todos os painéis abertos, em qualquer worker. Os mesmos
+// avisos invalidam o cache do resumo do painel.
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::postgres::PgListener;
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tokio::{
+    sync::broadcast,
+    time::{sleep, timeout_at, Instant},
+};
+
+use crate::{
+    db::DbPool,
+    handlers::dashboard_handler::{load_kpis, Kpis},
+    models::dashboard::{DashboardSummary, Period},
+};
+
+pub const CHANNEL: &str = "dashboard_events";
+
+// Janela em que avisos seguidos viram um só recálculo
+const DEBOUNCE: Duration = Duration::from_millis(250);
+// Espera antes de reconectar quando a escuta falha
+const RETRY: Duration = Duration::from_secs(5);
+// Eventos guardados para painéis lentos antes de descartarem os mais antigos
+const BUFFER: usize = 64;
+// Validade do resumo em cache; cobre também o que muda só com o passar do
+// tempo (despesas que vencem) e avisos perdidos com a escuta fora do ar
+const SUMMARY_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Cause {
+    entity: String,
+    action: String,
+}
+
+pub struct LiveDashboard {
+    sender: broadcast::Sender<String>,
+    latest: Mutex<Option<Kpis>>,
+    summaries: Mutex<Summaries>,
+}
+
+#[derive(Default)]
+struct Summaries {
+    /// Incrementada a cada invalidação, para descartar resumos calculados antes dela
+    generation: u64,
+    entries: HashMap<Period, (Instant, DashboardSummary)>,
+}
+
+impl LiveDashboard {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BUFFER);
+        LiveDashboard {
+            sender,
+            latest: Mutex::new(None),
+            summaries: Mutex::new(Summaries::default()),
+        }
+    }
+
+    /// Resumo ainda válido do período, ou a geração atual do cache para
+    /// guardar o que for calculado com `store_summary`.
+    pub fn cached_summary(&self, period: Period) -> Result<DashboardSummary, u64> {
+        let summaries = self.summaries.lock().unwrap();
+        match summaries.entries.get(&period) {
+            Some((stored, summary)) if stored.elapsed() < SUMMARY_TTL => Ok(summary.clone()),
+            _ => Err(summaries.generation),
+        }
+    }
+
+    /// Guarda o resumo, a menos que o cache tenha sido invalidado desde `generation`.
+    pub fn store_summary(&self, generation: u64, summary: DashboardSummary) {
+        let mut summaries = self.summaries.lock().unwrap();
+        if summaries.generation == generation {
+            summaries.entries.insert(summary.period, (Instant::now(), summary));
+        }
+    }
+
+    fn invalidate_summaries(&self) {
+        let mut summaries = self.summaries.lock().unwrap();
+        summaries.generation += 1;
+        summaries.entries.clear();
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+
+    /// Recalcula os indicadores e publica as diferenças, se houver.
+    async fn refresh(&self, pool: &DbPool, causes: Vec<Cause>) -> Result<(), sqlx::Error> {
+        let kpis = load_kpis(pool).await?;
+
+        let previous = self.latest.lock().unwrap().replace(kpis.clone());
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return Ok(()),
+        };
+
+        let changes = diff(&previous, &kpis);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let payload = json!({ "changes": changes, "causes": causes });
+        // Sem painéis abertos o envio falha, e tudo bem
+        let _ = self.sender.send(frame("delta", &payload));
+        Ok(())
+    }
+}
+
+/// Evento SSE pronto para enviar.
+pub fn frame(event: &str, data: &Value) -> String {
+    format!("event: {}\ndata: {}\n\n", event, data)
+}
+
+/// Indicadores que mudaram, com o valor novo e a variação.
+fn diff(previous: &Kpis, current: &Kpis) -> Map<String, Value> {
+    let mut changes = Map::new();
+    let mut scalar = |name: &str, before: f64, after: f64| {
+        if (after - before).abs() > f64::EPSILON {
+            let delta = ((after - before) * 100.0).round() / 100.0;
+            changes.insert(name.to_string(), json!({ "value": after, "delta": delta }));
+        }
+    };
+    scalar("sales_count", previous.sales_count as f64, current.sales_count as f64);
+    scalar("revenue", previous.revenue, current.revenue);
+    scalar("expenses_total", previous.expenses_total, current.expenses_total);
+    scalar("payments_total", previous.payments_total, current.payments_total);
+    scalar("open_payables", previous.open_payables, current.open_payables);
+
+    if previous.monthly_sales != current.monthly_sales {
+        changes.insert("monthly_sales".to_string(), json!({ "value": current.monthly_sales }));
+    }
+    changes
+}
+
+/// Escuta o canal enquanto o servidor estiver de pé, reconectando após falhas.
+pub async fn run(pool: DbPool, live: web::Data<LiveDashboard>) {
+    loop {
+        if let Err(err) = listen(&pool, &live).await {
+            log::error!("Painel ao vivo: escuta de {} falhou: {}", CHANNEL, err);
+        }
+        sleep(RETRY).await;
+    }
+}
+
+async fn listen(pool: &DbPool, live: &LiveDashboard) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+
+    // Base para as diferenças; avisos perdidos durante uma reconexão
+    // aparecem aqui como mudança sem causa
+    live.invalidate_summaries();
+    live.refresh(pool, Vec::new()).await?;
+
+    loop {
+        let first = listener.recv().await?;
+        live.invalidate_summaries();
+        let mut causes = Vec::new();
+        add_cause(&mut causes, first.payload());
+
+        let deadline = Instant::now() + DEBOUNCE;
+        while let Ok(next) = timeout_at(deadline, listener.recv()).await {
+            add_cause(&mut causes, next?.payload());
+        }
+
+        // De novo, caso um resumo tenha sido calculado no meio da rajada
+        live.invalidate_summaries();
+        live.refresh(pool, causes).await?;
+    }
+}
+
+fn add_cause(causes: &mut Vec<Cause>, payload: &str) {
+    match serde_json::from_str::<Cause>(payload) {
+        Ok(cause) if !causes.contains(&cause) => causes.push(cause),
+        Ok(_) => {}
+        Err(err) => log::warn!("Painel ao vivo: aviso inválido '{}': {}", payload, err),
+    }
+}
@@ -0,0 +1,53 @@
+// Inicialização do log
+// src/logging.rs
+use std::io::Write;
+
+use crate::{config::LogFormat, request_id};
+
+/// Configura o env_logger no formato escolhido, anexando o id da requisição
+/// corrente a cada linha.
+pub fn init(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+
+    match format {
+        LogFormat::Text => {
+            builder.format(|buf, record| {
+                let timestamp = buf.timestamp_millis();
+                let level = buf.default_styled_level(record.level());
+                match request_id::current() {
+                    Some(id) => writeln!(
+                        buf,
+                        "[{} {} {} request_id={}] {}",
+                        timestamp,
+                        level,
+                        record.target(),
+                        id,
+                        record.args()
+                    ),
+                    None => writeln!(
+                        buf,
+                        "[{} {} {}] {}",
+                        timestamp,
+                        level,
+                        record.target(),
+                        record.args()
+                    ),
+                }
+            });
+        }
+        LogFormat::Json => {
+            builder.format(|buf, record| {
+                let line = serde_json::json!({
+                    "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "request_id": request_id::current(),
+                    "message": record.args().to_string(),
+                });
+                writeln!(buf, "{}", line)
+            });
+        }
+    }
+
+    builder.init();
+}
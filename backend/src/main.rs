@@ -1,49 +1,161 @@
-use actix_web::{web, App, HttpServer, middleware::Logger};
+use actix_web::{web, App, HttpServer, middleware::{self, Logger}};
 use actix_cors::Cors;
 use dotenv::dotenv;
-use std::env;
+use std::{env, process};
 
+mod config;
 mod db;
 mod schema;
 mod auth;
+mod audit;
 mod models;
 mod handlers;
+mod errors;
+mod logging;
+mod metrics;
+mod request_id;
+mod versioning;
+mod idempotency;
+mod export;
+mod format;
+mod pdf;
+mod parsing;
+mod upload;
+mod statement;
+mod reconciliation;
+mod ledger;
+mod periods;
+mod forecast;
+mod ml_client;
+mod anomalies;
+mod duplicates;
+mod live;
+mod abc;
+
+use config::Config;
+
+// Formato padrão do actix acrescido do id da requisição
+const ACCESS_LOG_FORMAT: &str =
+    r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}i"#;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("🚀 Iniciando o servidor...");
-
     dotenv().ok();
 
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("❌ {:#}", err);
+            process::exit(1);
+        }
+    };
+
     if env::var_os("RUST_LOG").is_none() {
-        env::set_var("RUST_LOG", "actix_web=info,sqlx=warn");
+        env::set_var("RUST_LOG", "backend=info,actix_web=info,sqlx=warn");
     }
 
-    env_logger::Builder::from_default_env()
-        .format_timestamp_millis()
-        .init();
+    logging::init(config.log.format);
+    log::info!("🚀 Iniciando o servidor...");
+
+    let db_pool = match db::init(&config.database).await {
+        Ok(pool) => pool,
+        Err(err) => {
+            log::error!("❌ Falha ao conectar ao banco de dados: {}", err);
+            process::exit(1);
+        }
+    };
+    log::info!("✅ Banco de dados conectado com sucesso.");
+
+    if config.database.auto_migrate {
+        if let Err(err) = db::run_migrations(&db_pool).await {
+            log::error!("❌ Falha ao aplicar migrações: {}", err);
+            process::exit(1);
+        }
+    } else {
+        match db::pending_migrations(&db_pool).await {
+            Ok(pending) if !pending.is_empty() => {
+                log::warn!("⚠️ Migrações pendentes: {:?}; o /ready responde 503 até que sejam aplicadas", pending)
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("⚠️ Não foi possível verificar migrações pendentes: {}", err),
+        }
+    }
+
+    let metrics = match metrics::Metrics::new() {
+        Ok(metrics) => web::Data::new(metrics),
+        Err(err) => {
+            log::error!("❌ Falha ao registrar métricas: {}", err);
+            process::exit(1);
+        }
+    };
+
+    // Um só cliente, para que disjuntor e cache valham para todos os workers
+    let ml_client = match ml_client::MlClient::new(&config.ml_api) {
+        Ok(client) => web::Data::new(client),
+        Err(err) => {
+            log::error!("❌ Falha ao criar o cliente do ml_api: {}", err);
+            process::exit(1);
+        }
+    };
 
-    let db_pool = db::init().await;
-    println!("✅ Banco de dados conectado com sucesso.");
+    let live = web::Data::new(live::LiveDashboard::new());
+    actix_web::rt::spawn(live::run(db_pool.clone(), live.clone()));
 
-    start_http_server(db_pool).await
+    start_http_server(config, db_pool, metrics, ml_client, live).await
 }
 
-async fn start_http_server(pool: sqlx::PgPool) -> std::io::Result<()> {
-    HttpServer::new(move || {
+fn cors(config: &Config) -> Cors {
+    let cors = Cors::default()
+        .allow_any_method()
+        .allow_any_header()
+        .expose_headers([request_id::HEADER, "etag", idempotency::REPLAYED_HEADER, duplicates::WARNING_HEADER])
+        .max_age(3600);
+
+    if config.allows_any_origin() {
+        return cors.allow_any_origin();
+    }
+
+    config
+        .cors
+        .allowed_origins
+        .iter()
+        .fold(cors, |cors, origin| cors.allowed_origin(origin))
+}
+
+async fn start_http_server(
+    config: Config,
+    pool: sqlx::PgPool,
+    metrics: web::Data<metrics::Metrics>,
+    ml_client: web::Data<ml_client::MlClient>,
+    live: web::Data<live::LiveDashboard>,
+) -> std::io::Result<()> {
+    let bind = (config.server.host.clone(), config.server.port);
+    let workers = config.server.workers;
+    let config = web::Data::new(config);
+
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(Logger::default())
-            .wrap(
-                Cors::default()
-                    .allow_any_origin()
-                    .allow_any_method()
-                    .allow_any_header()
-                    .max_age(3600),
-            )
+            .wrap(middleware::from_fn(idempotency::replay))
+            .wrap(Logger::new(ACCESS_LOG_FORMAT))
+            .wrap(cors(&config))
+            .wrap(middleware::from_fn(metrics::track_requests))
+            // Por fora de todos, para que até as recusas do CORS levem o id
+            .wrap(middleware::from_fn(request_id::propagate))
+            .app_data(web::JsonConfig::default().error_handler(errors::extractor_error))
+            .app_data(web::QueryConfig::default().error_handler(errors::extractor_error))
+            .app_data(web::PathConfig::default().error_handler(errors::extractor_error))
+            .app_data(config.clone())
+            .app_data(metrics.clone())
+            .app_data(ml_client.clone())
+            .app_data(live.clone())
             .app_data(web::Data::new(pool.clone()))
             .configure(handlers::config)
-    })
-    .bind(("0.0.0.0", 8080))?
-    .run()
-    .await
+    });
+
+    let server = match workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+
+    server.bind(bind)?.run().await
 }
@@ -0,0 +1,122 @@
+// Métricas Prometheus
+// src/metrics.rs
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, Error,
+};
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::time::Instant;
+
+use crate::db::DbPool;
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests_total: IntCounterVec,
+    http_request_duration_seconds: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    pub open_payables_total: Gauge,
+    pub open_payables_count: IntGauge,
+    pub overdue_payables_count: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("financeflow".to_string()), None)?;
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "Requisições HTTP atendidas"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Latência das requisições HTTP"),
+            &["method", "route"],
+        )?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Conexões do pool do banco por estado"),
+            &["state"],
+        )?;
+        let open_payables_total = Gauge::new(
+            "open_payables_total",
+            "Soma das despesas ainda não pagas",
+        )?;
+        let open_payables_count = IntGauge::new(
+            "open_payables_count",
+            "Quantidade de despesas ainda não pagas",
+        )?;
+        let overdue_payables_count = IntGauge::new(
+            "overdue_payables_count",
+            "Quantidade de despesas vencidas e não pagas",
+        )?;
+
+        registry.register(Box::new(http_requests_total.clone()))?;
+        registry.register(Box::new(http_request_duration_seconds.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(open_payables_total.clone()))?;
+        registry.register(Box::new(open_payables_count.clone()))?;
+        registry.register(Box::new(overdue_payables_count.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            db_pool_connections,
+            open_payables_total,
+            open_payables_count,
+            overdue_payables_count,
+        })
+    }
+
+    /// Atualiza os gauges do pool a partir do estado atual.
+    pub fn observe_pool(&self, pool: &DbPool, max_connections: u32) {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+
+        self.db_pool_connections.with_label_values(&["max"]).set(max_connections as i64);
+        self.db_pool_connections.with_label_values(&["open"]).set(size);
+        self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        self.db_pool_connections.with_label_values(&["in_use"]).set(size - idle);
+    }
+
+    /// Serializa todas as métricas no formato texto do Prometheus.
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// Middleware que conta requisições e mede a latência por rota.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let method = req.method().to_string();
+    // Usa o padrão da rota ("/api/sales/{id}") para não explodir a cardinalidade
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+
+    let result = next.call(req).await;
+
+    if let Some(metrics) = metrics {
+        let status = match &result {
+            Ok(res) => res.status().as_u16().to_string(),
+            Err(err) => err.as_response_error().status_code().as_u16().to_string(),
+        };
+        metrics
+            .http_requests_total
+            .with_label_values(&[&method, &route, &status])
+            .inc();
+        metrics
+            .http_request_duration_seconds
+            .with_label_values(&[&method, &route])
+            .observe(started.elapsed().as_secs_f64());
+    }
+
+    result
+}
@@ -0,0 +1,457 @@
+// Cliente do serviço de previsão em Python (ml_api)
+// src/ml_client.rs
+//
+// O modelo recebe `[estoque, preço]` em POST /predict e responde
+// `{"prediction": [vendas]}`. Cada chamada tem timeout; falhas de rede,
+// timeouts e respostas 5xx são repetidas com espera crescente e contam para o
+// disjuntor, que deixa de chamar o serviço por um tempo após falhas seguidas.
+// Respostas válidas ficam em cache pelas próprias features.
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::config::MlApiConfig;
+
+// Espera antes da primeira nova tentativa; dobra a cada uma
+const BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Serialize)]
+struct PredictRequest<'a> {
+    features: &'a [f64],
+}
+
+#[derive(Deserialize)]
+struct PredictResponse {
+    prediction: Vec<f64>,
+}
+
+#[derive(Debug)]
+pub enum MlError {
+    /// Sem `ml_api.url` configurada
+    Disabled,
+    CircuitOpen,
+    Timeout,
+    Unavailable(String),
+    /// 4xx: as features foram recusadas; repetir não adianta
+    Rejected(String),
+    InvalidResponse(String),
+}
+
+impl fmt::Display for MlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MlError::Disabled => write!(f, "serviço de previsão não configurado"),
+            MlError::CircuitOpen => write!(f, "serviço de previsão suspenso após falhas seguidas"),
+            MlError::Timeout => write!(f, "serviço de previsão não respondeu a tempo"),
+            MlError::Unavailable(reason) => write!(f, "serviço de previsão indisponível: {}", reason),
+            MlError::Rejected(reason) => write!(f, "serviço de previsão recusou as features: {}", reason),
+            MlError::InvalidResponse(reason) => write!(f, "resposta inválida do serviço de previsão: {}", reason),
+        }
+    }
+}
+
+impl MlError {
+    fn retryable(&self) -> bool {
+        matches!(self, MlError::Timeout | MlError::Unavailable(_))
+    }
+}
+
+pub struct Prediction {
+    pub value: f64,
+    pub cached: bool,
+}
+
+/// Fechado conta as falhas seguidas; aberto recusa chamadas até `until`.
+/// Passado o prazo, uma chamada de teste segue e o prazo é renovado, de modo
+/// que só ela chega ao serviço; o sucesso fecha o circuito.
+enum Breaker {
+    Closed { failures: u32 },
+    Open { until: Instant },
+}
+
+type CacheKey = Vec<u64>;
+
+pub struct MlClient {
+    http: reqwest::Client,
+    url: Option<String>,
+    retries: u32,
+    failure_threshold: u32,
+    open_for: Duration,
+    cache_ttl: Duration,
+    breaker: Mutex<Breaker>,
+    cache: Mutex<HashMap<CacheKey, (f64, Instant)>>,
+}
+
+impl MlClient {
+    pub fn new(config: &MlApiConfig) -> Result<Self, reqwest::Error> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        Ok(MlClient {
+            http,
+            url: config
+                .url
+                .as_ref()
+                .map(|url| format!("{}/predict", url.trim_end_matches('/'))),
+            retries: config.retries,
+            failure_threshold: config.failure_threshold,
+            open_for: Duration::from_secs(config.open_secs),
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            breaker: Mutex::new(Breaker::Closed { failures: 0 }),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn predict(&self, features: &[f64]) -> Result<Prediction, MlError> {
+        let url = self.url.as_deref().ok_or(MlError::Disabled)?;
+
+        let key: CacheKey = features.iter().map(|f| f.to_bits()).collect();
+        if let Some(value) = self.cached(&key) {
+            return Ok(Prediction { value, cached: true });
+        }
+
+        if !self.allow_request() {
+            return Err(MlError::CircuitOpen);
+        }
+
+        let mut attempt = 0;
+        let result = loop {
+            match self.call(url, features).await {
+                // Outras chamadas simultâneas já abriram o circuito: não insiste
+                Err(err) if err.retryable() && attempt < self.retries && !self.is_open() => {
+                    log::warn!("ml_api: tentativa {} falhou: {}", attempt + 1, err);
+                    actix_web::rt::time::sleep(BACKOFF * 2u32.pow(attempt)).await;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+
+        match &result {
+            Ok(value) => {
+                self.record_success();
+                self.store(key, *value);
+            }
+            // Recusa das features é problema da requisição, não do serviço
+            Err(MlError::Rejected(_)) => {}
+            Err(err) => {
+                log::warn!("ml_api: {}", err);
+                self.record_failure();
+            }
+        }
+
+        result.map(|value| Prediction { value, cached: false })
+    }
+
+    async fn call(&self, url: &str, features: &[f64]) -> Result<f64, MlError> {
+        let response = self
+            .http
+            .post(url)
+            .json(&PredictRequest { features })
+            .send()
+            .await
+            .map_err(|err| {
+                if err.is_timeout() {
+                    MlError::Timeout
+                } else {
+                    MlError::Unavailable(err.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if status.is_server_error() {
+            return Err(MlError::Unavailable(format!("HTTP {}", status.as_u16())));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(MlError::Rejected(format!("HTTP {} {}", status.as_u16(), body.trim())));
+        }
+
+        let body: PredictResponse = response.json().await.map_err(|err| {
+            if err.is_timeout() {
+                MlError::Timeout
+            } else {
+                MlError::InvalidResponse(err.to_string())
+            }
+        })?;
+
+        match body.prediction.first() {
+            Some(value) if value.is_finite() => Ok(*value),
+            _ => Err(MlError::InvalidResponse("prediction vazio ou não numérico".to_string())),
+        }
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<f64> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|(_, stored_at)| stored_at.elapsed() < self.cache_ttl)
+            .map(|(value, _)| *value)
+    }
+
+    fn store(&self, key: CacheKey, value: f64) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (_, stored_at)| stored_at.elapsed() < self.cache_ttl);
+        cache.insert(key, (value, Instant::now()));
+    }
+
+    /// Circuito aberto e ainda dentro do prazo: chamadas seriam recusadas.
+    pub fn is_open(&self) -> bool {
+        matches!(*self.breaker.lock().unwrap(), Breaker::Open { until } if Instant::now() < until)
+    }
+
+    fn allow_request(&self) -> bool {
+        let mut breaker = self.breaker.lock().unwrap();
+        match *breaker {
+            Breaker::Closed { .. } => true,
+            Breaker::Open { until } if Instant::now() < until => false,
+            Breaker::Open { .. } => {
+                *breaker = Breaker::Open { until: Instant::now() + self.open_for };
+                true
+            }
+        }
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        if matches!(*breaker, Breaker::Open { .. }) {
+            log::info!("ml_api: serviço de previsão respondeu, circuito fechado");
+        }
+        *breaker = Breaker::Closed { failures: 0 };
+    }
+
+    fn record_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        let failures = match *breaker {
+            Breaker::Closed { failures } => failures + 1,
+            Breaker::Open { .. } => self.failure_threshold,
+        };
+        *breaker = if failures >= self.failure_threshold {
+            log::warn!("ml_api: circuito aberto por {}s", self.open_for.as_secs());
+            Breaker::Open { until: Instant::now() + self.open_for }
+        } else {
+            Breaker::Closed { failures }
+        };
+    }
+}
+
+/// Servidor HTTP local que imita o ml_api, para os testes.
+#[cfg(test)]
+pub(crate) mod stub {
+    use actix_web::{http::StatusCode, web, App, HttpResponse, HttpServer};
+    use serde_json::json;
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use crate::config::MlApiConfig;
+
+    use super::MlClient;
+
+    /// Resposta roteirizada; a última do roteiro se repete.
+    #[derive(Clone)]
+    pub enum Reply {
+        Value(f64),
+        Status(u16),
+        /// Responde só depois do tempo dado (ms)
+        Slow(u64),
+    }
+
+    pub struct Stub {
+        pub url: String,
+        hits: Arc<AtomicUsize>,
+        script: Arc<Mutex<VecDeque<Reply>>>,
+    }
+
+    impl Stub {
+        pub async fn start(replies: Vec<Reply>) -> Stub {
+            let script = Arc::new(Mutex::new(VecDeque::from(replies)));
+            let hits = Arc::new(AtomicUsize::new(0));
+
+            let (shared_script, shared_hits) = (script.clone(), hits.clone());
+            let server = HttpServer::new(move || {
+                let (script, hits) = (shared_script.clone(), shared_hits.clone());
+                App::new().route(
+                    "/predict",
+                    web::post().to(move || {
+                        let (script, hits) = (script.clone(), hits.clone());
+                        async move {
+                            hits.fetch_add(1, Ordering::SeqCst);
+                            let reply = {
+                                let mut script = script.lock().unwrap();
+                                if script.len() > 1 {
+                                    script.pop_front().unwrap()
+                                } else {
+                                    script[0].clone()
+                                }
+                            };
+                            match reply {
+                                Reply::Value(value) => HttpResponse::Ok().json(json!({ "prediction": [value] })),
+                                Reply::Status(code) => {
+                                    HttpResponse::build(StatusCode::from_u16(code).unwrap()).body("falha simulada")
+                                }
+                                Reply::Slow(ms) => {
+                                    actix_web::rt::time::sleep(Duration::from_millis(ms)).await;
+                                    HttpResponse::Ok().json(json!({ "prediction": [0.0] }))
+                                }
+                            }
+                        }
+                    }),
+                )
+            })
+            .workers(1)
+            .disable_signals()
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+
+            let url = format!("http://{}", server.addrs()[0]);
+            actix_web::rt::spawn(server.run());
+            Stub { url, hits, script }
+        }
+
+        pub fn hits(&self) -> usize {
+            self.hits.load(Ordering::SeqCst)
+        }
+
+        /// Troca o roteiro das próximas respostas.
+        pub fn reply(&self, replies: Vec<Reply>) {
+            *self.script.lock().unwrap() = VecDeque::from(replies);
+        }
+
+        /// Cliente com timeout curto, 2 novas tentativas e disjuntor que abre
+        /// após 2 falhas por 300ms.
+        pub fn client(&self) -> MlClient {
+            let mut client = MlClient::new(&MlApiConfig {
+                url: Some(self.url.clone()),
+                timeout_ms: 200,
+                retries: 2,
+                failure_threshold: 2,
+                open_secs: 0,
+                cache_ttl_secs: 60,
+            })
+            .unwrap();
+            client.open_for = Duration::from_millis(300);
+            client
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stub::{Reply, Stub};
+    use super::*;
+
+    #[actix_web::test]
+    async fn timeout_is_retried() {
+        let stub = Stub::start(vec![Reply::Slow(500), Reply::Value(3.0)]).await;
+        let prediction = stub.client().predict(&[1.0, 2.0]).await.unwrap();
+
+        assert_eq!(prediction.value, 3.0);
+        assert!(!prediction.cached);
+        assert_eq!(stub.hits(), 2);
+    }
+
+    #[actix_web::test]
+    async fn server_error_is_retried() {
+        let stub = Stub::start(vec![Reply::Status(503), Reply::Status(500), Reply::Value(4.0)]).await;
+        let prediction = stub.client().predict(&[1.0, 2.0]).await.unwrap();
+
+        assert_eq!(prediction.value, 4.0);
+        assert_eq!(stub.hits(), 3);
+    }
+
+    #[actix_web::test]
+    async fn client_error_is_not_retried_nor_counted() {
+        let stub = Stub::start(vec![Reply::Status(422)]).await;
+        let client = stub.client();
+
+        for _ in 0..3 {
+            let result = client.predict(&[1.0, 2.0]).await;
+            assert!(matches!(result, Err(MlError::Rejected(_))));
+        }
+        assert_eq!(stub.hits(), 3);
+        assert!(!client.is_open());
+    }
+
+    #[actix_web::test]
+    async fn breaker_opens_after_failures_and_half_opens() {
+        let stub = Stub::start(vec![Reply::Status(500)]).await;
+        let client = stub.client();
+
+        // Cada chamada: a primeira tentativa e 2 novas, todas com 500
+        for _ in 0..2 {
+            assert!(matches!(client.predict(&[1.0, 2.0]).await, Err(MlError::Unavailable(_))));
+        }
+        assert_eq!(stub.hits(), 6);
+        assert!(client.is_open());
+
+        // Aberto: recusa sem chamar o serviço
+        assert!(matches!(client.predict(&[1.0, 2.0]).await, Err(MlError::CircuitOpen)));
+        assert_eq!(stub.hits(), 6);
+
+        // Passado o prazo só uma chamada de teste segue; o sucesso fecha o circuito
+        stub.reply(vec![Reply::Value(7.0)]);
+        actix_web::rt::time::sleep(Duration::from_millis(350)).await;
+        assert_eq!(client.predict(&[1.0, 2.0]).await.unwrap().value, 7.0);
+        assert_eq!(stub.hits(), 7);
+        assert!(!client.is_open());
+        assert!(client.allow_request());
+    }
+
+    #[actix_web::test]
+    async fn half_open_lets_a_single_probe_through() {
+        let stub = Stub::start(vec![Reply::Status(500)]).await;
+        let client = stub.client();
+        for _ in 0..2 {
+            let _ = client.predict(&[1.0, 2.0]).await;
+        }
+
+        actix_web::rt::time::sleep(Duration::from_millis(350)).await;
+        assert!(client.allow_request());
+        assert!(!client.allow_request());
+    }
+
+    #[actix_web::test]
+    async fn failed_probe_reopens_the_breaker() {
+        let stub = Stub::start(vec![Reply::Status(500)]).await;
+        let client = stub.client();
+        for _ in 0..2 {
+            let _ = client.predict(&[1.0, 2.0]).await;
+        }
+
+        actix_web::rt::time::sleep(Duration::from_millis(350)).await;
+        assert!(client.predict(&[1.0, 2.0]).await.is_err());
+        assert!(client.is_open());
+    }
+
+    #[actix_web::test]
+    async fn repeated_features_are_served_from_cache() {
+        let stub = Stub::start(vec![Reply::Value(5.0), Reply::Value(9.0)]).await;
+        let client = stub.client();
+
+        let first = client.predict(&[1.0, 2.0]).await.unwrap();
+        let second = client.predict(&[1.0, 2.0]).await.unwrap();
+        let other = client.predict(&[2.0, 2.0]).await.unwrap();
+
+        assert_eq!((first.value, first.cached), (5.0, false));
+        assert_eq!((second.value, second.cached), (5.0, true));
+        assert_eq!((other.value, other.cached), (9.0, false));
+        assert_eq!(stub.hits(), 2);
+    }
+
+    #[actix_web::test]
+    async fn disabled_without_url() {
+        let client = MlClient::new(&MlApiConfig::default()).unwrap();
+        assert!(matches!(client.predict(&[1.0, 2.0]).await, Err(MlError::Disabled)));
+    }
+}
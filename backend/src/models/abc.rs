@@ -0,0 +1,136 @@
+// Curva ABC de produtos e clientes
+// src/models/abc.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::NaiveDate;
+
+use crate::export::{Cell, Exportable};
+
+#[derive(Debug, Deserialize)]
+pub struct AbcParams {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    /// Participação acumulada, em %, até onde vai a classe A (padrão: 80)
+    #[serde(default = "default_a")]
+    pub a: f64,
+    /// Participação acumulada, em %, até onde vai a classe B (padrão: 95)
+    #[serde(default = "default_b")]
+    pub b: f64,
+}
+
+fn default_a() -> f64 {
+    80.0
+}
+
+fn default_b() -> f64 {
+    95.0
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProductRanking {
+    #[default]
+    Revenue,
+    /// Receita menos custo × quantidade; exige o custo do produto
+    Margin,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductAbcQuery {
+    #[serde(default)]
+    pub by: ProductRanking,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientRanking {
+    /// Valor comprado
+    #[default]
+    Revenue,
+    /// Número de compras
+    Sales,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientAbcQuery {
+    #[serde(default)]
+    pub by: ClientRanking,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum AbcClass {
+    A,
+    B,
+    C,
+}
+
+/// Vendas de um produto ou cliente no intervalo.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AbcRow {
+    pub id: Uuid,
+    pub name: String,
+    pub revenue: f64,
+    pub quantity: i64,
+    pub sales_count: i64,
+    /// Só para produtos com custo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AbcItem {
+    pub rank: usize,
+    #[serde(flatten)]
+    pub row: AbcRow,
+    /// Valor usado na classificação (receita, margem ou compras)
+    pub value: f64,
+    /// Participação no total, em %
+    pub share: f64,
+    /// Participação somada até este item, em %
+    pub cumulative_share: f64,
+    pub class: AbcClass,
+}
+
+impl Exportable for AbcItem {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "Posição",
+            "Nome",
+            "Classe",
+            "Valor",
+            "Participação (%)",
+            "Participação acumulada (%)",
+            "Receita",
+            "Quantidade",
+            "Vendas",
+            "Margem",
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        let class = match self.class {
+            AbcClass::A => "A",
+            AbcClass::B => "B",
+            AbcClass::C => "C",
+        };
+        vec![
+            (self.rank as i64).into(),
+            self.row.name.as_str().into(),
+            class.into(),
+            self.value.into(),
+            self.share.into(),
+            self.cumulative_share.into(),
+            self.row.revenue.into(),
+            self.row.quantity.into(),
+            self.row.sales_count.into(),
+            self.row.margin.into(),
+        ]
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ClassSummary {
+    pub items: usize,
+    pub value: f64,
+    pub share: f64,
+}
@@ -0,0 +1,47 @@
+// Modelo de auditoria
+// src/models/audit.rs
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use chrono::NaiveDateTime;
+use crate::export::{Cell, Exportable};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity: String,
+    pub entity_id: Uuid,
+    pub action: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub changes: Value,
+    pub user_id: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub entity: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+impl Exportable for AuditEntry {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Data", "Entidade", "Registro", "Ação", "Usuário", "Requisição", "Alterações"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.created_at.into(),
+            self.entity.as_str().into(),
+            self.entity_id.into(),
+            self.action.as_str().into(),
+            self.user_id.as_deref().into(),
+            self.request_id.as_deref().into(),
+            self.changes.to_string().into(),
+        ]
+    }
+}
@@ -0,0 +1,165 @@
+// Contas bancárias e extratos para conciliação
+// src/models/bank.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::export::{Cell, Exportable};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Account {
+    pub id: Uuid,
+    pub name: String,
+    pub bank_code: Option<String>,
+    pub branch: Option<String>,
+    pub number: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub version: i32,
+    /// checking, savings, cash ou card_acquirer
+    pub kind: String,
+    pub opening_balance: f64,
+}
+
+pub const ACCOUNT_KINDS: &[&str] = &["checking", "savings", "cash", "card_acquirer"];
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAccount {
+    pub name: String,
+    pub bank_code: Option<String>,
+    pub branch: Option<String>,
+    pub number: Option<String>,
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub opening_balance: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateAccount {
+    pub name: Option<String>,
+    pub bank_code: Option<String>,
+    pub branch: Option<String>,
+    pub number: Option<String>,
+    pub kind: Option<String>,
+    pub opening_balance: Option<f64>,
+}
+
+fn default_kind() -> String {
+    "checking".to_string()
+}
+
+/// Saldo da conta no fim do dia `as_of`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AccountBalance {
+    pub account_id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub as_of: NaiveDate,
+    pub balance: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceQuery {
+    /// Data do saldo; hoje quando ausente
+    pub as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceHistoryQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Movimento líquido e saldo de fechamento de um dia.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DailyBalance {
+    pub date: NaiveDate,
+    pub net_change: f64,
+    pub balance: f64,
+}
+
+/// Linha do extrato bancário. `amount` é negativo nos débitos.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StatementLine {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub fitid: String,
+    pub posted_on: NaiveDate,
+    pub amount: f64,
+    pub description: String,
+    /// unmatched, auto, manual ou ignored
+    pub status: String,
+    pub payment_id: Option<Uuid>,
+    pub sale_id: Option<Uuid>,
+    pub imported_at: NaiveDateTime,
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementLinesQuery {
+    pub status: Option<String>,
+}
+
+/// Vínculo manual: um pagamento (débito) ou uma venda (crédito).
+#[derive(Debug, Deserialize)]
+pub struct MatchRequest {
+    pub payment_id: Option<Uuid>,
+    pub sale_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconcileParams {
+    /// Diferença máxima de valor, em reais
+    #[serde(default = "default_amount_tolerance")]
+    pub amount_tolerance: f64,
+    /// Diferença máxima entre as datas, em dias
+    #[serde(default = "default_date_tolerance")]
+    pub date_tolerance_days: i64,
+}
+
+fn default_amount_tolerance() -> f64 {
+    0.01
+}
+
+fn default_date_tolerance() -> i64 {
+    3
+}
+
+impl Exportable for Account {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Nome", "Tipo", "Banco", "Agência", "Número", "Saldo inicial", "Criado em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.name.as_str().into(),
+            self.kind.as_str().into(),
+            self.bank_code.as_deref().into(),
+            self.branch.as_deref().into(),
+            self.number.as_deref().into(),
+            self.opening_balance.into(),
+            self.created_at.into(),
+        ]
+    }
+}
+
+impl Exportable for StatementLine {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Conta", "FITID", "Data", "Valor", "Descrição", "Situação", "Pagamento", "Venda", "Importado em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.account_id.into(),
+            self.fitid.as_str().into(),
+            self.posted_on.into(),
+            self.amount.into(),
+            self.description.as_str().into(),
+            self.status.as_str().into(),
+            self.payment_id.into(),
+            self.sale_id.into(),
+            self.imported_at.into(),
+        ]
+    }
+}
@@ -0,0 +1,87 @@
+// Orçamento mensal por categoria
+// src/models/budget.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::NaiveDateTime;
+use crate::export::{Cell, Exportable};
+
+pub const BUDGET_KINDS: &[&str] = &["expense", "revenue"];
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Budget {
+    pub id: Uuid,
+    pub year: i32,
+    pub month: i32,
+    /// expense | revenue
+    pub kind: String,
+    /// Categoria da despesa; vazia nas metas de vendas
+    pub category: Option<String>,
+    pub amount: f64,
+    pub created_at: NaiveDateTime,
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBudget {
+    pub year: i32,
+    pub month: i32,
+    pub kind: String,
+    pub category: Option<String>,
+    pub amount: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateBudget {
+    pub amount: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BudgetQuery {
+    pub year: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VarianceQuery {
+    /// Desvio percentual a partir do qual a categoria é sinalizada
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+    pub month: Option<i32>,
+}
+
+fn default_threshold() -> f64 {
+    10.0
+}
+
+/// Planejado x realizado de uma categoria no mês (ou no ano, sem `month`).
+#[derive(Debug, Serialize)]
+pub struct VarianceRow {
+    pub month: Option<i32>,
+    pub kind: String,
+    pub category: Option<String>,
+    pub planned: f64,
+    pub actual: f64,
+    /// Realizado menos planejado
+    pub variance: f64,
+    /// Variação sobre o planejado; vazio sem orçamento
+    pub percentage: Option<f64>,
+    /// Despesa acima do teto ou vendas abaixo da meta além do limite
+    pub flagged: bool,
+}
+
+impl Exportable for Budget {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Ano", "Mês", "Tipo", "Categoria", "Valor orçado", "Criado em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.year.into(),
+            self.month.into(),
+            self.kind.as_str().into(),
+            self.category.as_deref().into(),
+            self.amount.into(),
+            self.created_at.into(),
+        ]
+    }
+}
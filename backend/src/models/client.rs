@@ -2,6 +2,7 @@
 //src/models/client.rs
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
+use crate::export::{Cell, Exportable};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Client {
@@ -9,4 +10,17 @@ pub struct Client {
     pub name: String,
     pub email: String,
     pub phone: String,
+    /// CPF ou CNPJ, só dígitos
+    pub document: Option<String>,
+    pub version: i32,
+}
+
+impl Exportable for Client {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Nome", "E-mail", "Telefone", "CPF/CNPJ"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![self.id.into(), self.name.as_str().into(), self.email.as_str().into(), self.phone.as_str().into(), self.document.as_deref().into()]
+    }
 }
@@ -0,0 +1,256 @@
+// Resumo do painel
+// src/models/dashboard.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+
+/// Período do calendário que contém hoje.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Today,
+    /// Semana de segunda a domingo
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    Year,
+    /// Todo o histórico
+    All,
+}
+
+impl Period {
+    /// Início (inclusivo) e fim (exclusivo) do período; `None` em `All`.
+    pub fn range(self, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let month_start = today.with_day(1)?;
+        let (start, months) = match self {
+            Period::Today => return Some((today, today.succ_opt()?)),
+            Period::Week => {
+                let start = today.checked_sub_signed(Duration::days(today.weekday().num_days_from_monday() as i64))?;
+                return Some((start, start.checked_add_signed(Duration::days(7))?));
+            }
+            Period::Month => (month_start, 1),
+            Period::Quarter => (month_start.with_month0(today.month0() / 3 * 3)?, 3),
+            Period::Year => (month_start.with_month(1)?, 12),
+            Period::All => return None,
+        };
+        Some((start, start.checked_add_months(Months::new(months))?))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SummaryQuery {
+    #[serde(default)]
+    pub period: Period,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopProduct {
+    pub product_id: Uuid,
+    pub name: String,
+    pub quantity: i64,
+    pub revenue: f64,
+}
+
+/// Indicadores do período; pendências e clientes não dependem do período.
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardSummary {
+    pub period: Period,
+    pub start_date: Option<NaiveDate>,
+    /// Último dia do período
+    pub end_date: Option<NaiveDate>,
+    pub sales_count: i64,
+    pub revenue: f64,
+    /// Despesas com vencimento no período
+    pub expenses: f64,
+    pub profit: f64,
+    pub clients: i64,
+    /// Despesas ainda não pagas, de qualquer vencimento
+    pub open_payables: f64,
+    /// Despesas não pagas com vencimento antes de hoje
+    pub overdue_count: i64,
+    pub top_products: Vec<TopProduct>,
+    pub generated_at: NaiveDateTime,
+}
+
+pub const SERIES_METRICS: &[&str] = &["revenue", "quantity", "expenses", "profit"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Day,
+    /// Semana ISO, de segunda a domingo
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Granularity {
+    /// Nome da unidade para `date_trunc`.
+    pub fn unit(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+            Granularity::Quarter => "quarter",
+            Granularity::Year => "year",
+        }
+    }
+
+    /// Primeiro dia do período que contém `date`.
+    pub fn truncate(self, date: NaiveDate) -> NaiveDate {
+        let first_of = |month0: u32| date.with_day(1).and_then(|d| d.with_month0(month0)).unwrap_or(date);
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => date
+                .checked_sub_signed(Duration::days(date.weekday().num_days_from_monday() as i64))
+                .unwrap_or(date),
+            Granularity::Month => first_of(date.month0()),
+            Granularity::Quarter => first_of(date.month0() / 3 * 3),
+            Granularity::Year => first_of(0),
+        }
+    }
+
+    /// Primeiro dia do período seguinte ao que começa em `start`; `None`
+    /// depois da última data suportada.
+    pub fn next(self, start: NaiveDate) -> Option<NaiveDate> {
+        let months = match self {
+            Granularity::Day => return start.checked_add_signed(Duration::days(1)),
+            Granularity::Week => return start.checked_add_signed(Duration::days(7)),
+            Granularity::Month => 1,
+            Granularity::Quarter => 3,
+            Granularity::Year => 12,
+        };
+        start.checked_add_months(Months::new(months))
+    }
+
+    /// Chave ISO do período: 2026-10-19, 2026-W42, 2026-10, 2026-Q4 ou 2026.
+    pub fn key(self, start: NaiveDate) -> String {
+        match self {
+            Granularity::Day => start.format("%Y-%m-%d").to_string(),
+            Granularity::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Granularity::Month => start.format("%Y-%m").to_string(),
+            Granularity::Quarter => format!("{}-Q{}", start.year(), start.month0() / 3 + 1),
+            Granularity::Year => start.year().to_string(),
+        }
+    }
+
+    /// Início padrão sem `start_date`: os últimos 30 dias, 12 semanas,
+    /// 12 meses, 8 trimestres ou 5 anos até `end`, contando o período atual.
+    pub fn default_start(self, end: NaiveDate) -> NaiveDate {
+        let current = self.truncate(end);
+        let months = match self {
+            Granularity::Day => return current.checked_sub_signed(Duration::days(29)).unwrap_or(NaiveDate::MIN),
+            Granularity::Week => return current.checked_sub_signed(Duration::weeks(11)).unwrap_or(NaiveDate::MIN),
+            Granularity::Month => 11,
+            Granularity::Quarter => 21,
+            Granularity::Year => 48,
+        };
+        current.checked_sub_months(Months::new(months)).unwrap_or(NaiveDate::MIN)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeSeriesQuery {
+    #[serde(default)]
+    pub granularity: Granularity,
+    /// Primeiro dia incluído; padrão em `Granularity::default_start`
+    pub start_date: Option<NaiveDate>,
+    /// Último dia incluído; padrão: hoje no fuso `tz`
+    pub end_date: Option<NaiveDate>,
+    /// Métricas separadas por vírgula (revenue,quantity,expenses,profit); padrão: revenue
+    pub metrics: Option<String>,
+    /// Fuso IANA em que as vendas são agrupadas por dia (padrão: UTC)
+    #[serde(default = "default_tz")]
+    pub tz: String,
+}
+
+fn default_tz() -> String {
+    "UTC".to_string()
+}
+
+/// Totais de um período; só as métricas pedidas são enviadas.
+#[derive(Debug, Serialize)]
+pub struct TimeSeriesPoint {
+    pub period: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revenue: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expenses: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profit: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn week_truncation_crosses_the_year() {
+        // 01/01/2026 é quinta-feira: a semana ISO 1 começa em 29/12/2025
+        let start = Granularity::Week.truncate(date(2026, 1, 1));
+        assert_eq!(start, date(2025, 12, 29));
+        assert_eq!(Granularity::Week.key(start), "2026-W01");
+        assert_eq!(Granularity::Week.next(start), Some(date(2026, 1, 5)));
+
+        // 01/01/2021 ainda pertence à semana 53 de 2020
+        let start = Granularity::Week.truncate(date(2021, 1, 1));
+        assert_eq!(start, date(2020, 12, 28));
+        assert_eq!(Granularity::Week.key(start), "2020-W53");
+    }
+
+    #[test]
+    fn month_quarter_and_year_truncation() {
+        let end_of_year = date(2025, 12, 31);
+        assert_eq!(Granularity::Day.truncate(end_of_year), end_of_year);
+        assert_eq!(Granularity::Month.truncate(end_of_year), date(2025, 12, 1));
+        assert_eq!(Granularity::Month.next(date(2025, 12, 1)), Some(date(2026, 1, 1)));
+        assert_eq!(Granularity::Quarter.truncate(date(2025, 11, 15)), date(2025, 10, 1));
+        assert_eq!(Granularity::Quarter.key(date(2025, 10, 1)), "2025-Q4");
+        assert_eq!(Granularity::Quarter.next(date(2025, 10, 1)), Some(date(2026, 1, 1)));
+        assert_eq!(Granularity::Year.truncate(date(2024, 2, 29)), date(2024, 1, 1));
+        assert_eq!(Granularity::Day.next(date(2024, 2, 28)), Some(date(2024, 2, 29)));
+    }
+
+    #[test]
+    fn next_stops_at_the_last_supported_date() {
+        for granularity in [Granularity::Day, Granularity::Week, Granularity::Month, Granularity::Quarter, Granularity::Year] {
+            assert_eq!(granularity.next(granularity.truncate(NaiveDate::MAX)), None);
+        }
+    }
+
+    #[test]
+    fn default_start_counts_the_current_period() {
+        let end = date(2026, 10, 19);
+        assert_eq!(Granularity::Day.default_start(end), date(2026, 9, 20));
+        assert_eq!(Granularity::Week.default_start(end), date(2026, 8, 3));
+        assert_eq!(Granularity::Month.default_start(end), date(2025, 11, 1));
+        assert_eq!(Granularity::Quarter.default_start(end), date(2025, 1, 1));
+        assert_eq!(Granularity::Year.default_start(end), date(2022, 1, 1));
+    }
+
+    #[test]
+    fn period_ranges() {
+        let today = date(2026, 1, 1);
+        assert_eq!(Period::Today.range(today), Some((today, date(2026, 1, 2))));
+        assert_eq!(Period::Week.range(today), Some((date(2025, 12, 29), date(2026, 1, 5))));
+        assert_eq!(Period::Month.range(date(2024, 2, 29)), Some((date(2024, 2, 1), date(2024, 3, 1))));
+        assert_eq!(Period::Quarter.range(date(2026, 8, 31)), Some((date(2026, 7, 1), date(2026, 10, 1))));
+        assert_eq!(Period::Year.range(today), Some((today, date(2027, 1, 1))));
+        assert_eq!(Period::All.range(today), None);
+        assert_eq!(Period::Today.range(NaiveDate::MAX), None);
+    }
+}
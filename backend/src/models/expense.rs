@@ -1,21 +1,27 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{NaiveDate, NaiveDateTime};
+use crate::export::{Cell, Exportable};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Expense {
     pub id: Uuid,
     pub description: String,
+    pub category: String,
     pub supplier_id: Option<Uuid>,
     pub amount: f64,
     pub due_date: NaiveDate,
     pub paid: bool,
     pub created_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub version: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateExpense {
     pub description: String,
+    /// "Sem categoria" quando ausente
+    pub category: Option<String>,
     pub supplier_id: Option<Uuid>,
     pub amount: f64,
     pub due_date: NaiveDate,
@@ -24,8 +30,29 @@ pub struct CreateExpense {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateExpense {
     pub description: Option<String>,
+    pub category: Option<String>,
     pub supplier_id: Option<Uuid>,
     pub amount: Option<f64>,
     pub due_date: Option<NaiveDate>,
     pub paid: Option<bool>,
 }
+
+impl Exportable for Expense {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Descrição", "Categoria", "Fornecedor", "Valor", "Vencimento", "Paga", "Criada em", "Excluída em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.description.as_str().into(),
+            self.category.as_str().into(),
+            self.supplier_id.into(),
+            self.amount.into(),
+            self.due_date.into(),
+            self.paid.into(),
+            self.created_at.into(),
+            self.deleted_at.into(),
+        ]
+    }
+}
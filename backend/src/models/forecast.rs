@@ -0,0 +1,58 @@
+// Previsão de vendas
+// src/models/forecast.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::NaiveDate;
+
+use crate::forecast::Model;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    /// Soma de total_price
+    #[default]
+    Revenue,
+    Quantity,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    /// Períodos à frente (padrão: 14 dias ou 8 semanas)
+    pub horizon: Option<usize>,
+    #[serde(default)]
+    pub granularity: Granularity,
+    #[serde(default)]
+    pub metric: Metric,
+    /// Sem modelo, usa o de menor erro no backtest
+    pub model: Option<Model>,
+    /// Nível de confiança do intervalo: 80, 90, 95 ou 99
+    #[serde(default = "default_level")]
+    pub level: u8,
+    pub product_id: Option<Uuid>,
+}
+
+fn default_level() -> u8 {
+    95
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SeriesPoint {
+    pub period: NaiveDate,
+    pub value: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForecastPoint {
+    pub period: NaiveDate,
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
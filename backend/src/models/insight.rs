@@ -0,0 +1,77 @@
+// Anomalias nas despesas
+// src/models/insight.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::NaiveDate;
+
+#[derive(Debug, Deserialize)]
+pub struct AnomalyQuery {
+    /// Meses de histórico analisados (padrão: 12)
+    #[serde(default = "default_months")]
+    pub months: i32,
+    /// Distância máxima entre vencimentos de despesas duplicadas
+    #[serde(default = "default_duplicate_days")]
+    pub duplicate_days: i64,
+    /// Crescimento mensal da categoria, em %, a partir do qual é sinalizado
+    #[serde(default = "default_growth")]
+    pub growth_threshold: f64,
+    /// Só achados desta gravidade ou maior
+    pub min_severity: Option<Severity>,
+}
+
+fn default_months() -> i32 {
+    12
+}
+
+fn default_duplicate_days() -> i64 {
+    7
+}
+
+fn default_growth() -> f64 {
+    50.0
+}
+
+/// Despesa com o nome do fornecedor, para as explicações.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ExpenseRecord {
+    pub id: Uuid,
+    pub description: String,
+    pub category: String,
+    pub supplier_id: Option<Uuid>,
+    pub supplier_name: Option<String>,
+    pub amount: f64,
+    pub due_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Valor muito acima do habitual do fornecedor (ou da categoria)
+    Outlier,
+    Duplicate,
+    /// Crescimento atípico da categoria de um mês para o outro
+    Growth,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub severity: Severity,
+    pub reason: String,
+    pub expense_ids: Vec<Uuid>,
+    pub supplier_id: Option<Uuid>,
+    pub category: Option<String>,
+    /// Primeiro dia do mês, nos achados de crescimento
+    pub month: Option<NaiveDate>,
+    pub amount: f64,
+    /// Valor habitual (mediana ou mês anterior) usado na comparação
+    pub expected: Option<f64>,
+}
@@ -0,0 +1,158 @@
+// Plano de contas e lançamentos contábeis
+// src/models/ledger.rs
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::export::{Cell, Exportable};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LedgerAccount {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    /// asset, liability, equity, revenue ou expense
+    pub kind: String,
+    pub financial_account_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+pub const LEDGER_ACCOUNT_KINDS: &[&str] = &["asset", "liability", "equity", "revenue", "expense"];
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLedgerAccount {
+    pub code: String,
+    pub name: String,
+    pub kind: String,
+}
+
+/// Lançamento com as partidas (`lines`) já agregadas em JSON.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub source: String,
+    pub source_id: Option<Uuid>,
+    pub reverses: Option<Uuid>,
+    pub reversed_by: Option<Uuid>,
+    pub user_id: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub lines: Value,
+}
+
+/// Partida de um lançamento, uma por linha na exportação de `/ledger/entries`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct JournalExportLine {
+    pub entry_id: Uuid,
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub source: String,
+    pub source_id: Option<Uuid>,
+    pub code: String,
+    pub name: String,
+    pub debit: f64,
+    pub credit: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JournalQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub source: Option<String>,
+    pub source_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// Lançamento manual (ajustes do contador).
+#[derive(Debug, Deserialize)]
+pub struct CreateJournalEntry {
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub lines: Vec<CreateJournalLine>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateJournalLine {
+    pub ledger_account_id: Uuid,
+    #[serde(default)]
+    pub debit: f64,
+    #[serde(default)]
+    pub credit: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrialBalanceQuery {
+    /// Data do balancete; hoje quando ausente
+    pub as_of: Option<NaiveDate>,
+}
+
+/// Linha do balancete. `balance` segue a natureza da conta: devedora para
+/// ativo e despesa, credora para as demais.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TrialBalanceRow {
+    pub ledger_account_id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub kind: String,
+    pub debit: f64,
+    pub credit: f64,
+    pub balance: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountLedgerQuery {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Partida no razão da conta, com o saldo acumulado.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LedgerLine {
+    pub entry_id: Uuid,
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub source: String,
+    pub source_id: Option<Uuid>,
+    pub debit: f64,
+    pub credit: f64,
+    pub balance: f64,
+}
+
+impl Exportable for JournalExportLine {
+    fn headers() -> &'static [&'static str] {
+        &["Lançamento", "Data", "Histórico", "Origem", "ID da origem", "Código", "Conta", "Débito", "Crédito"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.entry_id.into(),
+            self.entry_date.into(),
+            self.description.as_str().into(),
+            self.source.as_str().into(),
+            self.source_id.into(),
+            self.code.as_str().into(),
+            self.name.as_str().into(),
+            self.debit.into(),
+            self.credit.into(),
+        ]
+    }
+}
+
+impl Exportable for TrialBalanceRow {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Código", "Conta", "Natureza", "Débito", "Crédito", "Saldo"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.ledger_account_id.into(),
+            self.code.as_str().into(),
+            self.name.as_str().into(),
+            self.kind.as_str().into(),
+            self.debit.into(),
+            self.credit.into(),
+            self.balance.into(),
+        ]
+    }
+}
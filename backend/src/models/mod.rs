@@ -3,4 +3,17 @@ pub mod product;
 pub mod sale;
 pub mod supplier;
 pub mod payment;
-pub mod expense;
\ No newline at end of file
+pub mod expense;
+pub mod audit;
+pub mod report;
+pub mod bank;
+pub mod transfer;
+pub mod ledger;
+pub mod period;
+pub mod budget;
+pub mod forecast;
+pub mod prediction;
+pub mod insight;
+pub mod search;
+pub mod dashboard;
+pub mod abc;
\ No newline at end of file
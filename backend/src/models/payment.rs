@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::export::{Cell, Exportable};
 
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct Payment {
@@ -9,6 +10,9 @@ pub struct Payment {
     pub payment_date: Option<NaiveDate>,
     pub amount: f64,
     pub method: Option<String>,
+    pub account_id: Uuid,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub version: i32,
 }
 
 #[derive(Deserialize)]
@@ -17,6 +21,8 @@ pub struct CreatePayment {
     pub payment_date: Option<NaiveDate>,
     pub amount: f64,
     pub method: Option<String>,
+    /// Conta de onde saiu o dinheiro
+    pub account_id: Uuid,
 }
 
 #[derive(Deserialize)]
@@ -25,4 +31,23 @@ pub struct UpdatePayment {
     pub payment_date: Option<NaiveDate>,
     pub amount: Option<f64>,
     pub method: Option<String>,
+    pub account_id: Option<Uuid>,
+}
+
+impl Exportable for Payment {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Despesa", "Data do pagamento", "Valor", "Forma", "Conta", "Excluído em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.expense_id.into(),
+            self.payment_date.into(),
+            self.amount.into(),
+            self.method.as_deref().into(),
+            self.account_id.into(),
+            self.deleted_at.into(),
+        ]
+    }
 }
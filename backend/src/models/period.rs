@@ -0,0 +1,31 @@
+// Períodos contábeis (fechamento mensal)
+// src/models/period.rs
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{NaiveDate, NaiveDateTime};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FiscalPeriod {
+    pub id: Uuid,
+    /// Primeiro dia do mês
+    pub month: NaiveDate,
+    /// closed | open (reaberto)
+    pub status: String,
+    pub closed_at: Option<NaiveDateTime>,
+    pub closed_by: Option<String>,
+    pub reopened_at: Option<NaiveDateTime>,
+    pub reopened_by: Option<String>,
+    pub version: i32,
+}
+
+/// Saldo da conta contábil gravado no fechamento do período.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PeriodBalance {
+    pub ledger_account_id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub kind: String,
+    pub debit: f64,
+    pub credit: f64,
+    pub balance: f64,
+}
@@ -0,0 +1,45 @@
+// Previsão de vendas por produto (ml_api)
+// src/models/prediction.rs
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Dados do produto e do histórico usados na previsão.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProductFeatures {
+    pub product_id: Uuid,
+    pub name: String,
+    pub stock: i32,
+    pub price: f64,
+    pub units_last_30_days: f64,
+    pub units_last_90_days: f64,
+}
+
+impl ProductFeatures {
+    /// O modelo foi treinado só com estoque e preço, nesta ordem.
+    pub fn to_vector(&self) -> [f64; 2] {
+        [self.stock as f64, self.price]
+    }
+
+    /// Estimativa local quando o serviço falha: média mensal dos últimos 90 dias.
+    pub fn heuristic(&self) -> f64 {
+        self.units_last_90_days / 3.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PredictionSource {
+    MlApi,
+    Cache,
+    Heuristic,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductPrediction {
+    #[serde(flatten)]
+    pub features: ProductFeatures,
+    pub predicted_sales: f64,
+    pub source: PredictionSource,
+    /// Por que a heurística foi usada
+    pub fallback_reason: Option<String>,
+}
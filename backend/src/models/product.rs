@@ -1,6 +1,7 @@
 // Modelo de produto
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::export::{Cell, Exportable};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Product {
@@ -9,6 +10,9 @@ pub struct Product {
     pub description: Option<String>,
     pub price: f64,
     pub stock: i32,
+    /// Custo unitário; sem ele o produto fica fora da curva ABC por margem
+    pub cost: Option<f64>,
+    pub version: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -17,6 +21,7 @@ pub struct CreateProduct {
     pub description: Option<String>,
     pub price: f64,
     pub stock: i32,
+    pub cost: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,4 +30,22 @@ pub struct UpdateProduct {
     pub description: Option<String>,
     pub price: f64,
     pub stock: i32,
+    pub cost: Option<f64>,
+}
+
+impl Exportable for Product {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Nome", "Descrição", "Preço", "Estoque", "Custo"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.name.as_str().into(),
+            self.description.as_deref().into(),
+            self.price.into(),
+            self.stock.into(),
+            self.cost.into(),
+        ]
+    }
 }
@@ -0,0 +1,132 @@
+// Modelos dos relatórios
+// src/models/report.rs
+use serde::Serialize;
+use sqlx::FromRow;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::export::{Cell, Exportable};
+
+#[derive(Serialize, FromRow)]
+pub struct SalesReport {
+    pub product_name: String,
+    pub total_quantity: i64,
+    pub total_revenue: f64,
+}
+
+impl Exportable for SalesReport {
+    fn headers() -> &'static [&'static str] {
+        &["Produto", "Quantidade", "Receita"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.product_name.as_str().into(),
+            self.total_quantity.into(),
+            self.total_revenue.into(),
+        ]
+    }
+}
+
+/// Intervalo comparado, com as duas datas incluídas.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DateWindow {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delta {
+    pub absolute: f64,
+    /// Variação sobre a base; vazio quando a base é zero
+    pub percentage: Option<f64>,
+}
+
+impl Delta {
+    pub fn between(current: f64, base: f64) -> Self {
+        let round = |value: f64| (value * 100.0).round() / 100.0;
+        Delta {
+            absolute: round(current - base),
+            percentage: (base != 0.0).then(|| round((current - base) / base.abs() * 100.0)),
+        }
+    }
+}
+
+/// Uma métrica no período pedido, no período anterior e no mesmo período do ano passado.
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub current: f64,
+    pub previous: f64,
+    pub last_year: f64,
+    pub vs_previous: Delta,
+    pub vs_last_year: Delta,
+}
+
+impl Comparison {
+    pub fn new(current: f64, previous: f64, last_year: f64) -> Self {
+        Comparison {
+            current,
+            previous,
+            last_year,
+            vs_previous: Delta::between(current, previous),
+            vs_last_year: Delta::between(current, last_year),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductComparison {
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub revenue: Comparison,
+    pub quantity: Comparison,
+}
+
+impl Exportable for ProductComparison {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "Produto",
+            "Receita",
+            "Receita anterior",
+            "Variação anterior (%)",
+            "Receita ano passado",
+            "Variação ano passado (%)",
+            "Quantidade",
+            "Quantidade anterior",
+            "Quantidade ano passado",
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.product_name.as_str().into(),
+            self.revenue.current.into(),
+            self.revenue.previous.into(),
+            self.revenue.vs_previous.percentage.into(),
+            self.revenue.last_year.into(),
+            self.revenue.vs_last_year.percentage.into(),
+            (self.quantity.current as i64).into(),
+            (self.quantity.previous as i64).into(),
+            (self.quantity.last_year as i64).into(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComparisonTotals {
+    pub revenue: Comparison,
+    pub quantity: Comparison,
+    pub sales_count: Comparison,
+    /// Despesas pelo vencimento
+    pub expenses: Comparison,
+    pub profit: Comparison,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComparisonReport {
+    pub current: DateWindow,
+    pub previous: DateWindow,
+    pub last_year: DateWindow,
+    pub totals: ComparisonTotals,
+    pub products: Vec<ProductComparison>,
+}
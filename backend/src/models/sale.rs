@@ -3,7 +3,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use sqlx::FromRow;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::NaiveDateTime;
+use crate::export::{Cell, Exportable};
 
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -11,18 +12,34 @@ pub struct Sale {
     pub id: Uuid,
     pub product_id: Uuid,
     pub product_name: String,
+    pub client_id: Option<Uuid>,
+    pub client_name: Option<String>,
+    pub account_id: Uuid,
+    pub account_name: String,
     pub quantity: i32,
     pub total_price: f64,
+    /// Custo unitário do produto na venda; vazio se o produto não tinha custo
+    pub unit_cost: Option<f64>,
     pub created_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub version: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateSaleSchema {
-    pub product_id: Uuid,
-    pub quantity: i32,
-}
+impl Exportable for Sale {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Produto", "Cliente", "Conta", "Quantidade", "Valor total", "Data", "Excluída em"]
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateSaleSchema {
-    pub quantity: Option<i32>,
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.product_name.as_str().into(),
+            self.client_name.as_deref().into(),
+            self.account_name.as_str().into(),
+            self.quantity.into(),
+            self.total_price.into(),
+            self.created_at.into(),
+            self.deleted_at.into(),
+        ]
+    }
 }
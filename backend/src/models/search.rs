@@ -0,0 +1,31 @@
+// Busca textual
+// src/models/search.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const SEARCH_TYPES: &[&str] = &["client", "product", "supplier", "expense"];
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    /// Tipos separados por vírgula (client,product,supplier,expense); vazio busca em todos
+    pub types: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SearchResult {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub rank: f64,
+    /// Trecho com os termos encontrados entre <mark> e </mark>, já escapado
+    pub highlight: String,
+}
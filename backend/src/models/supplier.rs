@@ -2,6 +2,7 @@
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use chrono::NaiveDateTime;
+use crate::export::{Cell, Exportable};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Supplier {
@@ -9,5 +10,25 @@ pub struct Supplier {
     pub name: String,
     pub email: Option<String>,
     pub phone: Option<String>,
+    /// CPF ou CNPJ, só dígitos
+    pub document: Option<String>,
     pub created_at: Option<NaiveDateTime>,
+    pub version: i32,
+}
+
+impl Exportable for Supplier {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Nome", "E-mail", "Telefone", "CPF/CNPJ", "Cadastrado em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.name.as_str().into(),
+            self.email.as_deref().into(),
+            self.phone.as_deref().into(),
+            self.document.as_deref().into(),
+            self.created_at.into(),
+        ]
+    }
 }
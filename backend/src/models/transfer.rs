@@ -0,0 +1,56 @@
+// Transferências entre contas
+// src/models/transfer.rs
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::export::{Cell, Exportable};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Transfer {
+    pub id: Uuid,
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: f64,
+    pub transferred_on: NaiveDate,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTransfer {
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: f64,
+    /// Hoje quando ausente
+    pub transferred_on: Option<NaiveDate>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferQuery {
+    /// Só as transferências que entram ou saem desta conta
+    pub account_id: Option<Uuid>,
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+impl Exportable for Transfer {
+    fn headers() -> &'static [&'static str] {
+        &["ID", "Conta de origem", "Conta de destino", "Valor", "Data", "Descrição", "Criado em", "Excluído em"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.from_account_id.into(),
+            self.to_account_id.into(),
+            self.amount.into(),
+            self.transferred_on.into(),
+            self.description.as_deref().into(),
+            self.created_at.into(),
+            self.deleted_at.into(),
+        ]
+    }
+}
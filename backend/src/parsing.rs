@@ -0,0 +1,103 @@
+// Leitura de valores em arquivos importados (CSV, extratos)
+// src/parsing.rs
+use chrono::NaiveDate;
+
+/// Número no formato brasileiro (`1.234,56`, `R$ -10,00`) ou com ponto decimal.
+/// Sem vírgula, pontos em grupos de três dígitos são de milhar (`1.234` é mil
+/// duzentos e trinta e quatro); os demais são o ponto decimal (`-50.00`).
+pub fn parse_decimal(raw: &str) -> Option<f64> {
+    let cleaned: String = raw
+        .replace("R$", "")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let normalized = if cleaned.contains(',') || thousands_grouped(&cleaned) {
+        cleaned.replace('.', "").replace(',', ".")
+    } else {
+        cleaned
+    };
+    normalized.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// `1.234`, `-12.345.678`: primeiro grupo de 1 a 3 dígitos sem zero à
+/// esquerda e os seguintes com exatamente 3.
+fn thousands_grouped(number: &str) -> bool {
+    let digits = number.strip_prefix(['-', '+']).unwrap_or(number);
+    let mut groups = digits.split('.');
+    let first = groups.next().unwrap_or("");
+    let valid = |group: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&group.len()) && group.bytes().all(|b| b.is_ascii_digit())
+    };
+
+    digits.contains('.') && valid(first, 1..=3) && !first.starts_with('0') && groups.all(|g| valid(g, 3..=3))
+}
+
+/// Data em dd/mm/aaaa ou aaaa-mm-dd.
+pub fn parse_date(raw: &str) -> Option<NaiveDate> {
+    let raw = raw.trim();
+    NaiveDate::parse_from_str(raw, "%d/%m/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(raw, "%Y-%m-%d"))
+        .ok()
+}
+
+/// Separador do CSV pelo cabeçalho: `;` (Excel em português) ou `,`.
+pub fn detect_delimiter(text: &str) -> u8 {
+    let header = text.lines().next().unwrap_or("");
+    if header.matches(';').count() > header.matches(',').count() {
+        b';'
+    } else {
+        b','
+    }
+}
+
+/// Minúsculas e sem acentos, para comparar cabeçalhos e descrições.
+pub fn fold(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_in_brazilian_and_dot_formats() {
+        assert_eq!(parse_decimal("1.234,56"), Some(1234.56));
+        assert_eq!(parse_decimal("R$ -10,00"), Some(-10.0));
+        assert_eq!(parse_decimal("-50.00"), Some(-50.0));
+        assert_eq!(parse_decimal("1234.5"), Some(1234.5));
+        assert_eq!(parse_decimal("42"), Some(42.0));
+        assert_eq!(parse_decimal("abc"), None);
+        assert_eq!(parse_decimal("inf"), None);
+    }
+
+    #[test]
+    fn dots_in_groups_of_three_are_thousands() {
+        assert_eq!(parse_decimal("1.234"), Some(1234.0));
+        assert_eq!(parse_decimal("-12.345.678"), Some(-12345678.0));
+        assert_eq!(parse_decimal("0.500"), Some(0.5));
+        assert_eq!(parse_decimal("1.5"), Some(1.5));
+        assert_eq!(parse_decimal("1234.567"), Some(1234.567));
+    }
+
+    #[test]
+    fn dates_and_delimiters() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29);
+        assert_eq!(parse_date("29/02/2024"), date);
+        assert_eq!(parse_date(" 2024-02-29 "), date);
+        assert_eq!(parse_date("30/02/2024"), None);
+        assert_eq!(detect_delimiter("Data;Valor;Descrição\n01/02/2024;1,50;x"), b';');
+        assert_eq!(detect_delimiter("date,amount\n2024-02-01,1.50"), b',');
+    }
+}
@@ -0,0 +1,280 @@
+// Geração de PDFs (recibos de venda e relatórios)
+// src/pdf.rs
+//
+// Usa as fontes padrão do PDF (Helvetica), que dispensam arquivos de fonte e
+// cobrem a acentuação do português (WinAnsiEncoding).
+use chrono::{Local, NaiveDate};
+use printpdf::{
+    BuiltinFont, Error, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference,
+    PdfLayerReference, Point,
+};
+
+use crate::{
+    config::CompanyConfig,
+    format::{brl, date_br, datetime_br},
+    models::{client::Client, report::SalesReport, sale::Sale},
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const RIGHT: f32 = PAGE_WIDTH - MARGIN;
+const ROW_HEIGHT: f32 = 6.0;
+const REPORT_ROWS_PER_PAGE: usize = 30;
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+}
+
+/// Página em construção, com as fontes já registradas no documento.
+struct Page<'a> {
+    layer: PdfLayerReference,
+    fonts: &'a Fonts,
+}
+
+impl Page<'_> {
+    fn text(&self, text: &str, size: f32, x: f32, y: f32) {
+        self.layer.use_text(text, size, Mm(x), Mm(y), &self.fonts.regular);
+    }
+
+    fn bold(&self, text: &str, size: f32, x: f32, y: f32) {
+        self.layer.use_text(text, size, Mm(x), Mm(y), &self.fonts.bold);
+    }
+
+    /// Texto alinhado à direita em `right` (largura estimada pelas métricas da Helvetica).
+    fn text_right(&self, text: &str, size: f32, right: f32, y: f32, bold: bool) {
+        let x = right - text_width(text, size);
+        if bold {
+            self.bold(text, size, x, y);
+        } else {
+            self.text(text, size, x, y);
+        }
+    }
+
+    fn rule(&self, y: f32) {
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(y)), false),
+                (Point::new(Mm(RIGHT), Mm(y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    /// Cabeçalho com os dados da empresa; devolve a altura livre logo abaixo.
+    fn company_header(&self, company: &CompanyConfig) -> f32 {
+        let mut y = PAGE_HEIGHT - MARGIN;
+        self.bold(&company.name, 16.0, MARGIN, y);
+
+        let contact = [company.phone.as_deref(), company.email.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("  |  ");
+        let lines = [
+            company.document.as_ref().map(|d| format!("CNPJ/CPF: {}", d)),
+            company.address.clone(),
+            Some(contact).filter(|c| !c.is_empty()),
+        ];
+        for line in lines.into_iter().flatten() {
+            y -= 5.0;
+            self.text(&line, 9.0, MARGIN, y);
+        }
+
+        y -= 4.0;
+        self.rule(y);
+        y - 10.0
+    }
+
+    fn footer(&self, text: &str) {
+        self.text(text, 8.0, MARGIN, MARGIN - 8.0);
+    }
+}
+
+fn new_document(title: &str) -> Result<(PdfDocumentReference, PdfLayerReference, Fonts), Error> {
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Conteúdo");
+    let fonts = Fonts {
+        regular: doc.add_builtin_font(BuiltinFont::Helvetica)?,
+        bold: doc.add_builtin_font(BuiltinFont::HelveticaBold)?,
+    };
+    let layer = doc.get_page(page).get_layer(layer);
+    Ok((doc, layer, fonts))
+}
+
+/// Recibo de uma venda: empresa, cliente, item e total.
+pub fn sale_receipt(
+    company: &CompanyConfig,
+    sale: &Sale,
+    client: Option<&Client>,
+) -> Result<Vec<u8>, Error> {
+    let number = receipt_number(sale);
+    let (doc, layer, fonts) = new_document(&format!("Recibo {}", number))?;
+    let page = Page { layer, fonts: &fonts };
+
+    let mut y = page.company_header(company);
+
+    page.bold("RECIBO DE VENDA", 14.0, MARGIN, y);
+    page.text_right(&format!("Nº {}", number), 11.0, RIGHT, y, true);
+    y -= 7.0;
+    if let Some(created_at) = sale.created_at {
+        page.text(&format!("Data: {}", datetime_br(created_at)), 10.0, MARGIN, y);
+        y -= 6.0;
+    }
+    if let Some(deleted_at) = sale.deleted_at {
+        page.bold(&format!("VENDA CANCELADA em {}", datetime_br(deleted_at)), 10.0, MARGIN, y);
+        y -= 6.0;
+    }
+
+    y -= 4.0;
+    page.bold("Cliente", 11.0, MARGIN, y);
+    y -= 6.0;
+    match client {
+        Some(client) => {
+            page.text(&client.name, 10.0, MARGIN, y);
+            y -= 5.0;
+            page.text(&format!("{}  |  {}", client.email, client.phone), 9.0, MARGIN, y);
+        }
+        None => page.text("Consumidor não identificado", 10.0, MARGIN, y),
+    }
+
+    // Itens
+    y -= 12.0;
+    let (qty_right, unit_right) = (125.0, 158.0);
+    page.bold("Produto", 10.0, MARGIN, y);
+    page.text_right("Qtd.", 10.0, qty_right, y, true);
+    page.text_right("Valor unit.", 10.0, unit_right, y, true);
+    page.text_right("Total", 10.0, RIGHT, y, true);
+    y -= 2.5;
+    page.rule(y);
+    y -= 5.5;
+
+    let unit_price = if sale.quantity != 0 {
+        sale.total_price / sale.quantity as f64
+    } else {
+        0.0
+    };
+    page.text(&truncate(&sale.product_name, 50), 10.0, MARGIN, y);
+    page.text_right(&sale.quantity.to_string(), 10.0, qty_right, y, false);
+    page.text_right(&brl(unit_price), 10.0, unit_right, y, false);
+    page.text_right(&brl(sale.total_price), 10.0, RIGHT, y, false);
+    y -= 2.5;
+    page.rule(y);
+
+    y -= 8.0;
+    page.text_right(&format!("Total: {}", brl(sale.total_price)), 12.0, RIGHT, y, true);
+
+    page.footer(&format!(
+        "Documento sem valor fiscal. Emitido em {}.",
+        datetime_br(Local::now().naive_local())
+    ));
+
+    doc.save_to_bytes()
+}
+
+/// Relatório de vendas por produto no período, paginado.
+pub fn sales_report(
+    company: &CompanyConfig,
+    start: NaiveDate,
+    end: NaiveDate,
+    rows: &[SalesReport],
+) -> Result<Vec<u8>, Error> {
+    let (doc, first_layer, fonts) = new_document("Relatório de vendas")?;
+    let emitted = datetime_br(Local::now().naive_local());
+
+    let total_quantity: i64 = rows.iter().map(|r| r.total_quantity).sum();
+    let total_revenue: f64 = rows.iter().map(|r| r.total_revenue).sum();
+
+    let chunks: Vec<&[SalesReport]> = if rows.is_empty() {
+        vec![&[]]
+    } else {
+        rows.chunks(REPORT_ROWS_PER_PAGE).collect()
+    };
+    let pages = chunks.len();
+    let (qty_right, revenue_right) = (125.0, 160.0);
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let layer = if index == 0 {
+            first_layer.clone()
+        } else {
+            let (page, layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Conteúdo");
+            doc.get_page(page).get_layer(layer)
+        };
+        let page = Page { layer, fonts: &fonts };
+
+        let mut y = page.company_header(company);
+        page.bold("Relatório de vendas por período", 14.0, MARGIN, y);
+        y -= 7.0;
+        page.text(&format!("Período: {} a {}", date_br(start), date_br(end)), 10.0, MARGIN, y);
+
+        y -= 12.0;
+        page.bold("Produto", 10.0, MARGIN, y);
+        page.text_right("Quantidade", 10.0, qty_right, y, true);
+        page.text_right("Receita", 10.0, revenue_right, y, true);
+        page.text_right("% do total", 10.0, RIGHT, y, true);
+        y -= 2.5;
+        page.rule(y);
+        y -= 5.5;
+
+        if rows.is_empty() {
+            page.text("Nenhuma venda no período.", 10.0, MARGIN, y);
+            y -= ROW_HEIGHT;
+        }
+
+        for row in chunk.iter() {
+            let share = if total_revenue > 0.0 {
+                row.total_revenue / total_revenue * 100.0
+            } else {
+                0.0
+            };
+            page.text(&truncate(&row.product_name, 50), 10.0, MARGIN, y);
+            page.text_right(&row.total_quantity.to_string(), 10.0, qty_right, y, false);
+            page.text_right(&brl(row.total_revenue), 10.0, revenue_right, y, false);
+            page.text_right(&format!("{:.1}%", share).replace('.', ","), 10.0, RIGHT, y, false);
+            y -= ROW_HEIGHT;
+        }
+
+        if index + 1 == pages {
+            y += 3.5;
+            page.rule(y);
+            y -= 5.5;
+            page.bold("Total", 10.0, MARGIN, y);
+            page.text_right(&total_quantity.to_string(), 10.0, qty_right, y, true);
+            page.text_right(&brl(total_revenue), 10.0, revenue_right, y, true);
+        }
+
+        page.footer(&format!("Emitido em {}  -  Página {} de {}", emitted, index + 1, pages));
+    }
+
+    doc.save_to_bytes()
+}
+
+/// Número curto do recibo (início do id da venda).
+pub fn receipt_number(sale: &Sale) -> String {
+    sale.id.simple().to_string()[..8].to_uppercase()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(max_chars - 3).collect::<String>())
+    }
+}
+
+/// Largura aproximada em mm, pelas larguras da Helvetica (em milésimos de em).
+fn text_width(text: &str, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            '0'..='9' | '$' => 556,
+            '.' | ',' | ' ' | 'i' | 'l' | 'I' => 278,
+            '%' => 889,
+            '-' | '(' | ')' => 333,
+            'm' | 'M' | 'W' => 833,
+            c if c.is_uppercase() => 667,
+            _ => 556,
+        })
+        .sum();
+    units as f32 / 1000.0 * size * 25.4 / 72.0
+}
@@ -0,0 +1,73 @@
+// Bloqueio de períodos contábeis fechados
+// src/periods.rs
+//
+// Os meses fechados formam sempre um prefixo do calendário: só se fecha um
+// mês depois dos anteriores com movimento, e só se reabre o último fechado.
+// Assim, qualquer data até o fim do último mês fechado está bloqueada e os
+// saldos gravados no fechamento continuam válidos.
+use actix_web::{http::StatusCode, HttpResponse};
+use chrono::{Datelike, NaiveDate};
+use serde_json::json;
+use sqlx::PgConnection;
+
+use crate::errors::{error_with, internal_error};
+
+// Chave do advisory lock que serializa fechamentos e alterações
+const LOCK_KEY: i64 = 0x7065_7269_6f64;
+
+/// Último dia do mês que começa em `month`.
+pub fn month_end(month: NaiveDate) -> NaiveDate {
+    let (year, next) = if month.month() == 12 { (month.year() + 1, 1) } else { (month.year(), month.month() + 1) };
+    NaiveDate::from_ymd_opt(year, next, 1)
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(month)
+}
+
+/// Último mês fechado, se houver.
+pub async fn last_closed(conn: &mut PgConnection) -> Result<Option<NaiveDate>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<NaiveDate>>("SELECT MAX(month) FROM fiscal_periods WHERE status = 'closed'")
+        .fetch_one(conn)
+        .await
+}
+
+/// Último dia bloqueado, se houver mês fechado. O lock compartilhado impede
+/// que outro mês seja fechado até o fim da transação.
+pub async fn closed_until(conn: &mut PgConnection) -> Result<Option<NaiveDate>, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock_shared($1)")
+        .bind(LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    Ok(last_closed(conn).await?.map(month_end))
+}
+
+/// Falha com 423 se alguma das datas de negócio cair em período fechado.
+/// Chamar dentro da transação da alteração, antes de gravar.
+pub async fn ensure_open(conn: &mut PgConnection, dates: &[NaiveDate]) -> Result<(), HttpResponse> {
+    let until = match closed_until(conn).await {
+        Ok(until) => until,
+        Err(err) => return Err(internal_error("Erro ao verificar período contábil", err)),
+    };
+
+    match (until, dates.iter().min()) {
+        (Some(until), Some(&date)) if date <= until => Err(error_with(
+            StatusCode::LOCKED,
+            format!("O período {} está fechado", date.format("%m/%Y")),
+            json!({ "closed_until": until }),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Lock exclusivo para fechar/reabrir: espera as alterações em andamento.
+pub async fn lock_exclusive(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(LOCK_KEY)
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
+
+/// Mês no formato AAAA-MM.
+pub fn parse_month(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", value.trim()), "%Y-%m-%d").ok()
+}
@@ -0,0 +1,188 @@
+// Conciliação automática de extratos
+// src/reconciliation.rs
+//
+// Compara linhas do extrato com pagamentos (débitos) ou vendas (créditos) por
+// valor, data e descrição. Só vincula pares que são, ao mesmo tempo, a melhor
+// opção da linha e do lançamento; empates ficam para a conciliação manual.
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::{models::bank::ReconcileParams, parsing::fold};
+
+/// Linha do extrato ou lançamento do sistema, com o valor em módulo.
+#[derive(sqlx::FromRow)]
+pub struct Entry {
+    pub id: Uuid,
+    pub date: NaiveDate,
+    pub amount: f64,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Match {
+    pub line_id: Uuid,
+    pub candidate_id: Uuid,
+    pub score: f64,
+}
+
+// Peso de cada critério na nota do par (soma 1)
+const DATE_WEIGHT: f64 = 0.4;
+const AMOUNT_WEIGHT: f64 = 0.3;
+const TEXT_WEIGHT: f64 = 0.3;
+
+pub fn auto_match(lines: &[Entry], candidates: &[Entry], params: &ReconcileParams) -> Vec<Match> {
+    let line_tokens: Vec<HashSet<String>> = lines.iter().map(|l| tokens(&l.text)).collect();
+    let candidate_tokens: Vec<HashSet<String>> = candidates.iter().map(|c| tokens(&c.text)).collect();
+
+    let mut pairs = Vec::new();
+    for (li, line) in lines.iter().enumerate() {
+        for (ci, candidate) in candidates.iter().enumerate() {
+            if let Some(score) = score(line, candidate, &line_tokens[li], &candidate_tokens[ci], params) {
+                pairs.push((li, ci, score));
+            }
+        }
+    }
+
+    let mut matched_lines = HashSet::new();
+    let mut matched_candidates = HashSet::new();
+    let mut matches = Vec::new();
+
+    // A cada rodada, vincula os pares mutuamente preferidos e sem empate;
+    // os vínculos liberam a disputa pelos lançamentos restantes.
+    loop {
+        pairs.retain(|(li, ci, _)| !matched_lines.contains(li) && !matched_candidates.contains(ci));
+        let decisive: Vec<(usize, usize, f64)> = pairs
+            .iter()
+            .copied()
+            .filter(|&(li, ci, score)| {
+                pairs.iter().all(|&(other_li, other_ci, other)| {
+                    (other_li == li && other_ci == ci) || (other_li != li && other_ci != ci) || other < score
+                })
+            })
+            .collect();
+
+        if decisive.is_empty() {
+            break;
+        }
+
+        for (li, ci, score) in decisive {
+            matched_lines.insert(li);
+            matched_candidates.insert(ci);
+            matches.push(Match {
+                line_id: lines[li].id,
+                candidate_id: candidates[ci].id,
+                score,
+            });
+        }
+    }
+
+    matches
+}
+
+/// Nota entre 0 e 1, ou `None` fora das tolerâncias de valor e data.
+fn score(
+    line: &Entry,
+    candidate: &Entry,
+    line_tokens: &HashSet<String>,
+    candidate_tokens: &HashSet<String>,
+    params: &ReconcileParams,
+) -> Option<f64> {
+    let amount_diff = (line.amount - candidate.amount).abs();
+    let day_diff = (line.date - candidate.date).num_days().abs();
+    // Meio centavo de folga para erros de arredondamento do ponto flutuante
+    if amount_diff > params.amount_tolerance + 0.005 || day_diff > params.date_tolerance_days {
+        return None;
+    }
+
+    let amount_score = 1.0 - amount_diff / (params.amount_tolerance + 0.005);
+    let date_score = 1.0 - day_diff as f64 / (params.date_tolerance_days + 1) as f64;
+    let text_score = jaccard(line_tokens, candidate_tokens);
+
+    Some(DATE_WEIGHT * date_score + AMOUNT_WEIGHT * amount_score + TEXT_WEIGHT * text_score)
+}
+
+/// Palavras com três letras ou mais, sem acento, para comparar descrições.
+fn tokens(text: &str) -> HashSet<String> {
+    fold(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_string)
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u128, day: u32, amount: f64, text: &str) -> Entry {
+        Entry {
+            id: Uuid::from_u128(id),
+            date: NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+            amount,
+            text: text.to_string(),
+        }
+    }
+
+    fn params() -> ReconcileParams {
+        ReconcileParams { amount_tolerance: 0.01, date_tolerance_days: 3 }
+    }
+
+    fn pairs(matches: &[Match]) -> Vec<(u128, u128)> {
+        let mut pairs: Vec<_> = matches.iter().map(|m| (m.line_id.as_u128(), m.candidate_id.as_u128())).collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn exact_pair_scores_one() {
+        let matches = auto_match(&[entry(1, 5, 50.0, "Luz S.A.")], &[entry(10, 5, 50.0, "luz s.a.")], &params());
+        assert_eq!(pairs(&matches), vec![(1, 10)]);
+        assert!((matches[0].score - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn outside_tolerances_is_not_a_candidate() {
+        let lines = [entry(1, 5, 50.0, "Luz")];
+        assert!(auto_match(&lines, &[entry(10, 9, 50.0, "Luz")], &params()).is_empty());
+        assert!(auto_match(&lines, &[entry(10, 5, 50.02, "Luz")], &params()).is_empty());
+        assert_eq!(pairs(&auto_match(&lines, &[entry(10, 8, 50.01, "Luz")], &params())), vec![(1, 10)]);
+    }
+
+    #[test]
+    fn ties_are_left_for_manual_reconciliation() {
+        // Dois lançamentos idênticos para a mesma linha
+        let lines = [entry(1, 5, 50.0, "Aluguel")];
+        let candidates = [entry(10, 5, 50.0, "Aluguel"), entry(11, 5, 50.0, "Aluguel")];
+        assert!(auto_match(&lines, &candidates, &params()).is_empty());
+
+        // Duas linhas idênticas disputando um lançamento
+        let lines = [entry(1, 5, 50.0, "Aluguel"), entry(2, 5, 50.0, "Aluguel")];
+        assert!(auto_match(&lines, &candidates[..1], &params()).is_empty());
+    }
+
+    #[test]
+    fn mutual_best_pairs_are_resolved_across_rounds() {
+        // Linha 1 prefere 10 (mesmo dia); linha 2 também, mas perde para 1 e,
+        // na rodada seguinte, fica com 11.
+        let lines = [entry(1, 5, 50.0, "Energia"), entry(2, 6, 50.0, "Energia")];
+        let candidates = [entry(10, 5, 50.0, "Energia"), entry(11, 7, 50.0, "Energia")];
+        let matches = auto_match(&lines, &candidates, &params());
+        assert_eq!(pairs(&matches), vec![(1, 10), (2, 11)]);
+    }
+
+    #[test]
+    fn description_breaks_otherwise_equal_pairs() {
+        let lines = [entry(1, 5, 80.0, "Pagamento internet fibra")];
+        let candidates = [entry(10, 5, 80.0, "Conta de água"), entry(11, 5, 80.0, "Internet fibra")];
+        assert_eq!(pairs(&auto_match(&lines, &candidates, &params())), vec![(1, 11)]);
+    }
+}
@@ -0,0 +1,70 @@
+// Identificador de requisição (X-Request-Id)
+// src/request_id.rs
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error,
+};
+use uuid::Uuid;
+
+pub const HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id da requisição em andamento, disponível em qualquer código executado
+/// pelo handler (inclusive o log das queries do sqlx).
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Reaproveita o `X-Request-Id` recebido (quando válido) ou gera um novo,
+/// e o devolve no cabeçalho da resposta. O id também substitui o cabeçalho da
+/// requisição, que é o que o log de acesso registra. Se o serviço interno
+/// falhar, o erro é convertido em resposta aqui para que ela também leve o
+/// cabeçalho.
+pub async fn propagate(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = req
+        .headers()
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid(value))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let header = HeaderValue::from_str(&id).ok();
+    if let Some(value) = &header {
+        req.headers_mut().insert(HeaderName::from_static(HEADER), value.clone());
+    }
+
+    match REQUEST_ID.scope(id, next.call(req)).await {
+        Ok(mut res) => {
+            if let Some(value) = header {
+                res.headers_mut().insert(HeaderName::from_static(HEADER), value);
+            }
+            Ok(res)
+        }
+        Err(err) => {
+            let mut response = err.error_response();
+            if let Some(value) = header {
+                response.headers_mut().insert(HeaderName::from_static(HEADER), value);
+            }
+            Err(InternalError::from_response(err, response).into())
+        }
+    }
+}
+
+fn is_valid(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 128
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
@@ -1,12 +1,13 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::NaiveDate;
 
 #[derive(Serialize, Deserialize)]
 pub struct CreateClient {
     pub name: String,
     pub email: String,
     pub phone: String,
+    /// CPF ou CNPJ, com ou sem pontuação
+    pub document: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -14,34 +15,25 @@ pub struct UpdateClient {
     pub name: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct CreateProduct {
-    pub name: String,
-    pub description: Option<String>,
-    pub price: f64,
-    pub stock: i32,
-}
-
-#[derive(Deserialize)]
-pub struct UpdateProduct {
-    pub name: Option<String>,
-    pub description: Option<String>,
-    pub price: Option<f64>,
-    pub stock: Option<i32>,
+    /// Vazio remove o documento
+    pub document: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CreateSale {
     pub product_id: Uuid,
     pub quantity: i32,
+    pub client_id: Option<Uuid>,
+    /// Conta em que o valor foi recebido
+    pub account_id: Uuid,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateSale {
     pub product_id: Option<Uuid>,
     pub quantity: Option<i32>,
+    pub client_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
 }
 
 #[derive(Deserialize)]
@@ -49,6 +41,8 @@ pub struct CreateSupplier {
     pub name: String,
     pub email: Option<String>,
     pub phone: Option<String>,
+    /// CPF ou CNPJ, com ou sem pontuação
+    pub document: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -56,20 +50,28 @@ pub struct UpdateSupplier {
     pub name: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
+    /// Vazio remove o documento
+    pub document: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ListParams {
+    /// Inclui registros excluídos logicamente
+    #[serde(default)]
+    pub include_deleted: bool,
 }
 
 #[derive(Deserialize)]
-pub struct CreatePayment {
-    pub expense_id: Option<Uuid>,
-    pub payment_date: Option<NaiveDate>,
-    pub amount: f64,
-    pub method: Option<String>,
+pub struct ImportParams {
+    /// Só valida o arquivo, sem gravar nada
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Separador do CSV; quando ausente é detectado pelo cabeçalho
+    pub delimiter: Option<String>,
 }
 
 #[derive(Deserialize)]
-pub struct UpdatePayment {
-    pub expense_id: Option<Uuid>,
-    pub payment_date: Option<NaiveDate>,
-    pub amount: Option<f64>,
-    pub method: Option<String>,
+pub struct MergeRequest {
+    /// Cadastros absorvidos pelo registro do caminho e depois removidos
+    pub duplicate_ids: Vec<Uuid>,
 }
@@ -0,0 +1,274 @@
+// Leitura de extratos bancários (OFX e CSV)
+// src/statement.rs
+//
+// O OFX 1.x é SGML (tags de valor sem fechamento) e o 2.x é XML; os dois são
+// lidos pelo mesmo parser tolerante, que só procura os blocos <STMTTRN>.
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::parsing::{detect_delimiter, fold, parse_date, parse_decimal};
+
+/// Transação lida do extrato, ainda sem conta associada.
+#[derive(Debug)]
+pub struct ParsedLine {
+    pub fitid: String,
+    pub posted_on: NaiveDate,
+    pub amount: f64,
+    pub description: String,
+}
+
+/// Interpreta o arquivo como OFX ou CSV, conforme o conteúdo.
+pub fn parse(bytes: &[u8]) -> Result<Vec<ParsedLine>, String> {
+    let text = decode(bytes);
+    let upper = text.to_ascii_uppercase();
+    let lines = if upper.contains("OFXHEADER") || upper.contains("<OFX>") {
+        parse_ofx(&text)?
+    } else {
+        parse_csv(&text)?
+    };
+
+    if lines.is_empty() {
+        return Err("Nenhuma transação encontrada no extrato".to_string());
+    }
+    Ok(lines)
+}
+
+/// Bancos brasileiros ainda exportam OFX em latin-1 (CHARSET:1252); quando o
+/// arquivo não é UTF-8 válido, cada byte vira o caractere de mesmo código.
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn parse_ofx(text: &str) -> Result<Vec<ParsedLine>, String> {
+    let upper = text.to_ascii_uppercase();
+    let mut lines = Vec::new();
+    let mut synthesized = Occurrences::default();
+    let mut cursor = 0;
+
+    while let Some(start) = upper[cursor..].find("<STMTTRN>") {
+        let start = cursor + start + "<STMTTRN>".len();
+        let end = upper[start..]
+            .find("</STMTTRN>")
+            .map(|i| start + i)
+            .ok_or("OFX inválido: bloco <STMTTRN> sem fechamento")?;
+        let block = &text[start..end];
+        cursor = end;
+        let number = lines.len() + 1;
+
+        let posted_on = ofx_value(block, "DTPOSTED")
+            .and_then(|raw| raw.get(..8).map(str::to_string))
+            .and_then(|raw| NaiveDate::parse_from_str(&raw, "%Y%m%d").ok())
+            .ok_or_else(|| format!("Transação {}: DTPOSTED ausente ou inválido", number))?;
+        let amount = ofx_value(block, "TRNAMT")
+            .and_then(|raw| parse_decimal(&raw))
+            .ok_or_else(|| format!("Transação {}: TRNAMT ausente ou inválido", number))?;
+
+        let description = [ofx_value(block, "NAME"), ofx_value(block, "MEMO")]
+            .into_iter()
+            .flatten()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" - ");
+
+        let fitid = match ofx_value(block, "FITID").filter(|f| !f.is_empty()) {
+            Some(fitid) => fitid,
+            None => synthesized.fitid(posted_on, amount, &description),
+        };
+
+        lines.push(ParsedLine { fitid, posted_on, amount, description });
+    }
+
+    Ok(lines)
+}
+
+/// Valor de uma tag simples: o texto até a próxima tag (com ou sem fechamento).
+fn ofx_value(block: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = block.to_ascii_uppercase().find(&open)? + open.len();
+    let rest = &block[start..];
+    let value = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+// Cabeçalhos aceitos no CSV (já sem acento e em minúsculas)
+const DATE_COLUMNS: &[&str] = &["data", "date", "data lancamento", "data do lancamento"];
+const AMOUNT_COLUMNS: &[&str] = &["valor", "amount", "valor (r$)"];
+const DESCRIPTION_COLUMNS: &[&str] = &["descricao", "historico", "description", "lancamento"];
+const FITID_COLUMNS: &[&str] = &["fitid", "id", "documento", "numero do documento"];
+
+fn parse_csv(text: &str) -> Result<Vec<ParsedLine>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(detect_delimiter(text))
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers: Vec<String> = match reader.headers() {
+        Ok(headers) => headers.iter().map(fold).collect(),
+        Err(err) => return Err(format!("CSV inválido: {}", err)),
+    };
+    let column = |aliases: &[&str]| headers.iter().position(|h| aliases.contains(&h.as_str()));
+
+    let date_col = column(DATE_COLUMNS).ok_or("CSV sem a coluna de data (Data)")?;
+    let amount_col = column(AMOUNT_COLUMNS).ok_or("CSV sem a coluna de valor (Valor)")?;
+    let description_col = column(DESCRIPTION_COLUMNS);
+    let fitid_col = column(FITID_COLUMNS);
+
+    let mut lines = Vec::new();
+    let mut synthesized = Occurrences::default();
+
+    for (index, record) in reader.records().enumerate() {
+        // Linha 1 é o cabeçalho
+        let line = index + 2;
+        let record = record.map_err(|err| format!("Linha {}: {}", line, err))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let cell = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").to_string();
+
+        let raw_date = cell(Some(date_col));
+        let posted_on = parse_date(&raw_date)
+            .ok_or_else(|| format!("Linha {}: data inválida '{}'", line, raw_date))?;
+        let raw_amount = cell(Some(amount_col));
+        let amount = parse_decimal(&raw_amount)
+            .ok_or_else(|| format!("Linha {}: valor inválido '{}'", line, raw_amount))?;
+        let description = cell(description_col);
+
+        let fitid = match cell(fitid_col) {
+            fitid if !fitid.is_empty() => fitid,
+            _ => synthesized.fitid(posted_on, amount, &description),
+        };
+
+        lines.push(ParsedLine { fitid, posted_on, amount, description });
+    }
+
+    Ok(lines)
+}
+
+/// Gera FITIDs estáveis para extratos que não trazem o identificador: o hash
+/// de data, valor e descrição, mais a ordem da repetição no arquivo, para
+/// que duas transações idênticas no mesmo dia não virem uma só.
+#[derive(Default)]
+struct Occurrences(HashMap<String, usize>);
+
+impl Occurrences {
+    fn fitid(&mut self, posted_on: NaiveDate, amount: f64, description: &str) -> String {
+        let key = format!("{}|{:.2}|{}", posted_on, amount, description.trim().to_lowercase());
+        let count = self.0.entry(key.clone()).or_insert(0);
+        *count += 1;
+
+        let digest = Sha256::digest(format!("{}|{}", key, count).as_bytes());
+        format!("gen-{}", &hex::encode(digest)[..32])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    const SGML: &str = "OFXHEADER:100\nDATA:OFXSGML\nCHARSET:1252\n\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><BANKTRANLIST>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240305120000[-3:BRT]<TRNAMT>-50.00<FITID>A1<NAME>PADARIA<MEMO>Cartão</STMTTRN>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240306<TRNAMT>1.234,56<FITID>A2<MEMO>PIX RECEBIDO</STMTTRN>
+</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+    #[test]
+    fn sgml_ofx_with_unclosed_tags() {
+        let lines = parse(SGML.as_bytes()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].fitid, "A1");
+        assert_eq!(lines[0].posted_on, date(5));
+        assert_eq!(lines[0].amount, -50.0);
+        assert_eq!(lines[0].description, "PADARIA - Cartão");
+        assert_eq!(lines[1].amount, 1234.56);
+        assert_eq!(lines[1].description, "PIX RECEBIDO");
+    }
+
+    #[test]
+    fn xml_ofx_with_closed_tags() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><?OFX OFXHEADER="200"?>
+<OFX><BANKTRANLIST>
+  <STMTTRN>
+    <DTPOSTED>20240307</DTPOSTED>
+    <TRNAMT>-19.90</TRNAMT>
+    <FITID>X9</FITID>
+    <NAME>Loja &amp; Cia</NAME>
+  </STMTTRN>
+</BANKTRANLIST></OFX>"#;
+        let lines = parse(xml.as_bytes()).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].fitid, "X9");
+        assert_eq!(lines[0].posted_on, date(7));
+        assert_eq!(lines[0].amount, -19.9);
+        assert_eq!(lines[0].description, "Loja & Cia");
+    }
+
+    #[test]
+    fn unterminated_block_and_empty_statement_are_errors() {
+        assert!(parse(b"<OFX><STMTTRN><DTPOSTED>20240305<TRNAMT>1.00").is_err());
+        assert!(parse(b"<OFX></OFX>").is_err());
+        assert!(parse(b"<OFX><STMTTRN><TRNAMT>1.00</STMTTRN></OFX>").is_err());
+    }
+
+    #[test]
+    fn latin1_is_decoded() {
+        let mut bytes = b"<OFX><STMTTRN><DTPOSTED>20240305<TRNAMT>-5.00<FITID>L1<MEMO>".to_vec();
+        bytes.extend_from_slice(b"Pa\xE7oca Jos\xE9");
+        bytes.extend_from_slice(b"</STMTTRN></OFX>");
+        let lines = parse(&bytes).unwrap();
+        assert_eq!(lines[0].description, "Paçoca José");
+    }
+
+    #[test]
+    fn missing_fitid_is_stable_and_distinct_for_repeated_lines() {
+        let ofx = "<OFX>\
+            <STMTTRN><DTPOSTED>20240305<TRNAMT>-3.50<MEMO>CAFE</STMTTRN>\
+            <STMTTRN><DTPOSTED>20240305<TRNAMT>-3.50<MEMO>CAFE</STMTTRN>\
+            <STMTTRN><DTPOSTED>20240305<TRNAMT>-3.50<MEMO>PAO</STMTTRN></OFX>";
+        let first = parse(ofx.as_bytes()).unwrap();
+        let second = parse(ofx.as_bytes()).unwrap();
+
+        let ids: Vec<&str> = first.iter().map(|l| l.fitid.as_str()).collect();
+        assert!(ids.iter().all(|id| id.starts_with("gen-")));
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[1], ids[2]);
+        assert_eq!(ids, second.iter().map(|l| l.fitid.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn csv_with_semicolon_and_comma() {
+        let semicolon = "Data;Histórico;Valor\n05/03/2024;Padaria;-1.234,50\n\n06/03/2024;PIX;10,00\n";
+        let lines = parse(semicolon.as_bytes()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].posted_on, date(5));
+        assert_eq!(lines[0].amount, -1234.5);
+        assert_eq!(lines[0].description, "Padaria");
+        assert!(lines[0].fitid.starts_with("gen-"));
+
+        let comma = "date,description,amount,id\n2024-03-06,Coffee,-3.50,T1\n";
+        let lines = parse(comma.as_bytes()).unwrap();
+        assert_eq!(lines[0].fitid, "T1");
+        assert_eq!(lines[0].posted_on, date(6));
+        assert_eq!(lines[0].amount, -3.5);
+    }
+
+    #[test]
+    fn csv_errors_name_the_line() {
+        assert!(parse(b"Descricao;Valor\nx;1,00").unwrap_err().contains("data"));
+        let err = parse("Data;Valor\n05/03/2024;1,00\n31/02/2024;2,00".as_bytes()).unwrap_err();
+        assert!(err.starts_with("Linha 3"), "{}", err);
+    }
+}
@@ -0,0 +1,44 @@
+// Leitura de arquivos enviados via multipart (importação de CSV e extratos)
+// src/upload.rs
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::errors::bad_request;
+
+/// Tamanho máximo de cada campo do multipart.
+pub const MAX_FILE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Lê todos os campos do multipart, na ordem em que chegam, como pares
+/// `(nome, conteúdo)`. Campos acima de `MAX_FILE_BYTES` respondem 400.
+pub async fn read_fields(mut payload: Multipart) -> Result<Vec<(String, Vec<u8>)>, HttpResponse> {
+    let mut fields = Vec::new();
+
+    loop {
+        let mut field = match payload.try_next().await {
+            Ok(Some(field)) => field,
+            Ok(None) => return Ok(fields),
+            Err(err) => return Err(bad_request(format!("Multipart inválido: {}", err))),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+
+        let mut data = Vec::new();
+        loop {
+            match field.try_next().await {
+                Ok(Some(chunk)) => {
+                    if data.len() + chunk.len() > MAX_FILE_BYTES {
+                        return Err(bad_request(format!(
+                            "Arquivo maior que o limite de {} MB",
+                            MAX_FILE_BYTES / (1024 * 1024)
+                        )));
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                Ok(None) => break,
+                Err(err) => return Err(bad_request(format!("Erro ao ler o arquivo: {}", err))),
+            }
+        }
+
+        fields.push((name, data));
+    }
+}
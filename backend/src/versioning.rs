@@ -0,0 +1,74 @@
+// Controle de concorrência otimista (ETag / If-Match)
+// src/versioning.rs
+use actix_web::{
+    http::{
+        header::{self, EntityTag, ETag, Header, IfMatch, TryIntoHeaderPair},
+        StatusCode,
+    },
+    HttpRequest, HttpResponse,
+};
+use serde_json::json;
+
+use crate::errors::{bad_request, error_response, error_with};
+
+/// ETag forte derivado da coluna `version` do registro.
+pub fn etag(version: i32) -> ETag {
+    ETag(EntityTag::new_strong(version.to_string()))
+}
+
+/// Condição enviada no `If-Match`.
+#[derive(Debug)]
+pub enum Expected {
+    /// `If-Match: *`: qualquer versão existente serve.
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl Expected {
+    pub fn matches(&self, version: i32) -> bool {
+        match self {
+            Expected::Any => true,
+            Expected::Versions(versions) => versions.contains(&version),
+        }
+    }
+}
+
+/// Versões aceitas pelo cliente no `If-Match`. Alterações exigem o cabeçalho:
+/// sem ele respondemos 428, para que ninguém sobrescreva dados às cegas.
+/// `If-Match: *` é aceito e não restringe a versão.
+pub fn expected_versions(req: &HttpRequest) -> Result<Expected, HttpResponse> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Err(error_response(
+            StatusCode::PRECONDITION_REQUIRED,
+            "Envie o cabeçalho If-Match com o ETag obtido na leitura do registro",
+        ));
+    }
+
+    let versions = match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => return Ok(Expected::Any),
+        Ok(IfMatch::Items(tags)) => tags
+            .iter()
+            .filter_map(|tag| tag.tag().parse::<i32>().ok())
+            .collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+
+    if versions.is_empty() {
+        return Err(bad_request("If-Match inválido: use o ETag retornado pela API"));
+    }
+
+    Ok(Expected::Versions(versions))
+}
+
+/// 412: o registro mudou desde que o cliente o leu.
+pub fn precondition_failed(current: i32) -> HttpResponse {
+    let mut response = error_with(
+        StatusCode::PRECONDITION_FAILED,
+        "O registro foi alterado por outra pessoa; recarregue e tente novamente",
+        json!({ "current_version": current }),
+    );
+    if let Ok((name, value)) = etag(current).try_into_pair() {
+        response.headers_mut().insert(name, value);
+    }
+    response
+}
@@ -9,6 +9,7 @@ export default function NewClientPage() {
   const [name, setName] = useState('');
   const [email, setEmail] = useState('');
   const [phone, setPhone] = useState('');
+  const [document, setDocument] = useState('');
   const [loading, setLoading] = useState(false);
   const router = useRouter();
 
@@ -19,12 +20,17 @@ export default function NewClientPage() {
     const res = await fetch('http://localhost:8080/api/clients', {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ name, email, phone }),
+      body: JSON.stringify({ name, email, phone, document }),
     });
 
     setLoading(false);
 
     if (res.ok) {
+      // ids de cadastros parecidos, separados por vírgula
+      const similar = (res.headers.get('possible-duplicates') ?? '').split(',').filter(Boolean);
+      if (similar.length > 0) {
+        alert(`Cliente cadastrado, mas parece repetir ${similar.length} cadastro(s) existente(s)`);
+      }
       router.push('/clients');
     } else {
       alert('Erro ao cadastrar cliente');
@@ -83,6 +89,21 @@ export default function NewClientPage() {
           />
         </div>
 
+        <div>
+          <label htmlFor="document" className="block text-gray-700 font-semibold mb-2">
+            CPF/CNPJ
+          </label>
+          <input
+            id="document"
+            type="text"
+            value={document}
+            onChange={e => setDocument(e.target.value)}
+            className="w-full px-4 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+            disabled={loading}
+            placeholder="Somente números ou com pontuação"
+          />
+        </div>
+
         <button
           type="submit"
           disabled={loading}
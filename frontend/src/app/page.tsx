@@ -32,27 +32,15 @@ export default function Home() {
       setLoading(true);
 
       try {
-        // IMPORTANTE: usamos /api/clients (lista) em vez de /api/clients/count
-        const [
-          clientsRes,
-          salesRes,
-          revenueRes,
-          expensesRes,
-          monthlyRes,
-        ] = await Promise.all([
-          fetch(`${API}/api/clients`),
-          fetch(`${API}/api/sales/count`),
-          fetch(`${API}/api/sales/revenue`),
-          fetch(`${API}/api/expenses/total`),
+        // resumo consolidado (todo o histórico, como o stream ao vivo)
+        const [summaryRes, monthlyRes] = await Promise.all([
+          fetch(`${API}/api/dashboard/summary?period=all`),
           fetch(`${API}/api/sales/monthly`),
         ]);
 
         // Verifica cada resposta e loga detalhes se não ok
         const responses = [
-          { name: "clients", res: clientsRes },
-          { name: "sales", res: salesRes },
-          { name: "revenue", res: revenueRes },
-          { name: "expenses", res: expensesRes },
+          { name: "summary", res: summaryRes },
           { name: "monthly", res: monthlyRes },
         ];
 
@@ -68,35 +56,13 @@ export default function Home() {
           }
         }
 
-        // Todas ok -> parse JSON
-        const clientsJson = await clientsRes.json();
-        const salesJson = await salesRes.json();
-        const revenueJson = await revenueRes.json();
-        const expensesJson = await expensesRes.json();
+        const summary = await summaryRes.json();
         const monthlyJson = await monthlyRes.json();
 
-        // --- totalClients: suportar vários formatos ---
-        // Possíveis formatos:
-        // 1) array direto -> clientsJson = [ {id,...}, ... ]
-        // 2) objeto { status: "success", clients: [...] }
-        // 3) objeto { count: N } (menos provável no seu caso)
-        let clientsCount: number | null = null;
-        if (Array.isArray(clientsJson)) {
-          clientsCount = clientsJson.length;
-        } else if (clientsJson && typeof clientsJson === "object") {
-          if (Array.isArray((clientsJson as any).clients)) {
-            clientsCount = (clientsJson as any).clients.length;
-          } else if (typeof (clientsJson as any).count === "number") {
-            clientsCount = (clientsJson as any).count;
-          } else {
-            clientsCount = null;
-          }
-        }
-
-        setTotalClients(clientsCount ?? null);
-        setTotalSales(salesJson.count ?? null);
-        setTotalRevenue(revenueJson.revenue ?? null);
-        setTotalExpenses(expensesJson.total ?? null);
+        setTotalClients(summary.clients ?? null);
+        setTotalSales(summary.sales_count ?? null);
+        setTotalRevenue(summary.revenue ?? null);
+        setTotalExpenses(summary.expenses ?? null);
 
         // normaliza monthlyJson para o formato esperado
         setMonthlySales(
@@ -118,6 +84,28 @@ export default function Home() {
     fetchData();
   }, []);
 
+  // atualizações ao vivo: snapshot ao conectar, depois só o que mudou
+  useEffect(() => {
+    const source = new EventSource(`${API}/api/dashboard/stream`);
+
+    const apply = (values: Record<string, any>) => {
+      if (values.sales_count !== undefined) setTotalSales(values.sales_count);
+      if (values.revenue !== undefined) setTotalRevenue(values.revenue);
+      if (values.expenses_total !== undefined) setTotalExpenses(values.expenses_total);
+      if (Array.isArray(values.monthly_sales)) setMonthlySales(values.monthly_sales);
+    };
+
+    source.addEventListener("snapshot", (e) => apply(JSON.parse((e as MessageEvent).data)));
+    source.addEventListener("delta", (e) => {
+      const { changes } = JSON.parse((e as MessageEvent).data);
+      const values: Record<string, any> = {};
+      for (const [key, change] of Object.entries<any>(changes)) values[key] = change.value;
+      apply(values);
+    });
+
+    return () => source.close();
+  }, []);
+
   if (loading) return <div className="p-6">Carregando painel...</div>;
   if (error)
     return (
@@ -8,6 +8,7 @@ interface Sale {
   id: string;
   product_id: string;
   product_name: string;
+  client_name?: string | null;
   quantity: number;
   total_price: number;
   created_at: string;
@@ -61,6 +62,7 @@ export default function SalesPage() {
               <th className="p-3">ID</th>
               <th className="p-3">Produto ID</th>
               <th className="p-3">Produto</th>
+              <th className="p-3">Cliente</th>
               <th className="p-3">Quantidade</th>
               <th className="p-3">Total</th>
               <th className="p-3">Data</th>
@@ -73,6 +75,7 @@ export default function SalesPage() {
                 <td className="p-3">{sale.id}</td>
                 <td className="p-3">{sale.product_id}</td>
                 <td className="p-3">{sale.product_name}</td>
+                <td className="p-3">{sale.client_name ?? '-'}</td>
                 <td className="p-3">{sale.quantity}</td>
                 <td className="p-3">R$ {(sale.total_price ?? 0).toFixed(2)}</td>
                 <td className="p-3">{new Date(sale.created_at).toLocaleString()}</td>
@@ -83,6 +86,14 @@ export default function SalesPage() {
                   >
                     Editar
                   </Link>
+                  <a
+                    href={`http://localhost:8080/api/sales/${sale.id}/receipt`}
+                    target="_blank"
+                    rel="noopener noreferrer"
+                    className="bg-gray-500 hover:bg-gray-600 text-white px-3 py-1 rounded"
+                  >
+                    Recibo
+                  </a>
                   <button
                     onClick={() => deleteSale(sale.id)}
                     className="bg-red-500 hover:bg-red-600 text-white px-3 py-1 rounded"
@@ -94,7 +105,7 @@ export default function SalesPage() {
             ))}
             {sales.length === 0 && (
               <tr>
-                <td colSpan={7} className="p-3 text-center text-gray-500">
+                <td colSpan={8} className="p-3 text-center text-gray-500">
                   Nenhuma venda encontrada.
                 </td>
               </tr>
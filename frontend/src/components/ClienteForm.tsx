@@ -9,6 +9,7 @@ interface Client {
   name: string;
   email: string;
   phone: string;
+  version?: number;
 }
 
 interface ClienteFormProps {
@@ -34,11 +35,13 @@ export default function ClienteForm({ client }: ClienteFormProps) {
 
     const method = isEdit ? 'PATCH' : 'POST';
 
+    const headers: Record<string, string> = { 'Content-Type': 'application/json' };
+    // A API recusa edições sem a versão lida (controle de concorrência)
+    if (client?.version !== undefined) headers['If-Match'] = `"${client.version}"`;
+
     const res = await fetch(url, {
       method,
-      headers: {
-        'Content-Type': 'application/json',
-      },
+      headers,
       body: JSON.stringify({ name, email, phone }),
     });
 
@@ -46,6 +49,8 @@ export default function ClienteForm({ client }: ClienteFormProps) {
 
     if (res.ok) {
       router.push('/clients');
+    } else if (res.status === 412) {
+      alert('Este cliente foi alterado por outra pessoa. Recarregue a página e tente novamente.');
     } else {
       alert('Erro ao salvar cliente');
     }
@@ -1,14 +1,16 @@
 // components/ExpenseForm.tsx
 'use client';
-import { useState, FormEvent } from "react";
+import { useEffect, useState, FormEvent } from "react";
 import { useRouter } from "next/navigation";
 
 export interface Expense {
   id?: string;
   description: string;
+  category?: string;
   due_date: string;
   amount: number;
   paid: boolean;
+  version?: number;
 }
 
 interface ExpenseFormProps {
@@ -20,11 +22,20 @@ export default function ExpenseForm({ expenseId, initialData }: ExpenseFormProps
   const router = useRouter();
 
   const [description, setDescription] = useState(initialData?.description ?? "");
+  const [category, setCategory] = useState(initialData?.category ?? "");
+  const [categories, setCategories] = useState<string[]>([]);
   const [due_date, setDueDate] = useState(initialData?.due_date ?? "");
   const [amount, setAmount] = useState(initialData?.amount ?? 0);
   const [paid, setPaid] = useState(initialData?.paid ?? false);
   const [loading, setLoading] = useState(false);
 
+  useEffect(() => {
+    fetch("http://localhost:8080/api/expenses/categories")
+      .then(res => res.json())
+      .then(data => setCategories(data))
+      .catch(err => console.error("Erro ao carregar categorias:", err));
+  }, []);
+
   async function handleSubmit(e: FormEvent) {
     e.preventDefault();
     setLoading(true);
@@ -35,12 +46,21 @@ export default function ExpenseForm({ expenseId, initialData }: ExpenseFormProps
         ? `http://localhost:8080/api/expenses/${expenseId}`
         : "http://localhost:8080/api/expenses";
 
+      const headers: Record<string, string> = { "Content-Type": "application/json" };
+      // A API recusa edições sem a versão lida (controle de concorrência)
+      if (initialData?.version !== undefined) headers["If-Match"] = `"${initialData.version}"`;
+
       const res = await fetch(url, {
         method,
-        headers: { "Content-Type": "application/json" },
-        body: JSON.stringify({ description, due_date, amount, paid }),
+        headers,
+        body: JSON.stringify({ description, category, due_date, amount, paid }),
       });
 
+      if (res.status === 412) {
+        alert("Esta despesa foi alterada por outra pessoa. Recarregue a página e tente novamente.");
+        setLoading(false);
+        return;
+      }
       if (!res.ok) throw new Error("Erro ao salvar despesa");
 
       router.push("/expenses");
@@ -66,6 +86,23 @@ export default function ExpenseForm({ expenseId, initialData }: ExpenseFormProps
         />
       </label>
 
+      <label className="block mb-2">
+        Categoria:
+        <input
+          type="text"
+          list="expense-categories"
+          placeholder="Sem categoria"
+          value={category}
+          onChange={e => setCategory(e.target.value)}
+          className="w-full border px-3 py-2 rounded"
+        />
+        <datalist id="expense-categories">
+          {categories.map(c => (
+            <option key={c} value={c} />
+          ))}
+        </datalist>
+      </label>
+
       <label className="block mb-2">
         Vencimento:
         <input
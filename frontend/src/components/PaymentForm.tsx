@@ -1,6 +1,6 @@
 // components/PaymentForm.tsx
 'use client';
-import { useState, FormEvent } from "react";
+import { useEffect, useState, FormEvent } from "react";
 import { useRouter } from "next/navigation";
 
 export interface Payment {
@@ -9,6 +9,13 @@ export interface Payment {
   payment_date: string;
   amount: number;
   method?: string;
+  account_id?: string;
+  version?: number;
+}
+
+interface Account {
+  id: string;
+  name: string;
 }
 
 interface PaymentFormProps {
@@ -23,7 +30,18 @@ export default function PaymentForm({ paymentId, initialData }: PaymentFormProps
   const [payment_date, setPaymentDate] = useState(initialData?.payment_date ?? "");
   const [amount, setAmount] = useState(initialData?.amount ?? 0);
   const [method, setMethod] = useState(initialData?.method ?? "");
+  const [account_id, setAccountId] = useState(initialData?.account_id ?? "");
+  const [accounts, setAccounts] = useState<Account[]>([]);
   const [loading, setLoading] = useState(false);
+  // Mesma chave em reenvios do formulário: a API devolve o pagamento já criado
+  const [idempotencyKey, setIdempotencyKey] = useState(() => crypto.randomUUID());
+
+  useEffect(() => {
+    fetch("http://localhost:8080/api/accounts")
+      .then(res => res.json())
+      .then(data => setAccounts(data))
+      .catch(err => console.error("Erro ao carregar contas:", err));
+  }, []);
 
   async function handleSubmit(e: FormEvent) {
     e.preventDefault();
@@ -35,13 +53,27 @@ export default function PaymentForm({ paymentId, initialData }: PaymentFormProps
         ? `http://localhost:8080/api/payments/${paymentId}`
         : "http://localhost:8080/api/payments";
 
+      const headers: Record<string, string> = { "Content-Type": "application/json" };
+      // A API recusa edições sem a versão lida (controle de concorrência)
+      if (initialData?.version !== undefined) headers["If-Match"] = `"${initialData.version}"`;
+      if (!paymentId) headers["Idempotency-Key"] = idempotencyKey;
+
       const res = await fetch(url, {
         method: methodHttp,
-        headers: { "Content-Type": "application/json" },
-        body: JSON.stringify({ expense_id: expense_id || null, payment_date, amount, method }),
+        headers,
+        body: JSON.stringify({ expense_id: expense_id || null, payment_date, amount, method, account_id }),
       });
 
-      if (!res.ok) throw new Error("Erro ao salvar pagamento");
+      if (res.status === 412) {
+        alert("Este pagamento foi alterado por outra pessoa. Recarregue a página e tente novamente.");
+        setLoading(false);
+        return;
+      }
+      if (!res.ok) {
+        // Requisição recusada: o próximo envio (corrigido) precisa de outra chave
+        setIdempotencyKey(crypto.randomUUID());
+        throw new Error("Erro ao salvar pagamento");
+      }
 
       router.push("/payments");
     } catch (err) {
@@ -90,6 +122,23 @@ export default function PaymentForm({ paymentId, initialData }: PaymentFormProps
         />
       </label>
 
+      <label className="block mb-2">
+        Conta:
+        <select
+          required
+          value={account_id}
+          onChange={e => setAccountId(e.target.value)}
+          className="w-full border px-3 py-2 rounded"
+        >
+          <option value="">Selecione a conta de saída</option>
+          {accounts.map(account => (
+            <option key={account.id} value={account.id}>
+              {account.name}
+            </option>
+          ))}
+        </select>
+      </label>
+
       <label className="block mb-4">
         Método:
         <input
@@ -10,6 +10,7 @@ export interface Product {
   description?: string;
   price: number;
   stock: number;
+  version?: number;
 }
 
 interface ProductFormProps {
@@ -45,15 +46,21 @@ export default function ProductForm({ product }: ProductFormProps) {
       ? `http://localhost:8080/api/products/${product.id}`
       : "http://localhost:8080/api/products";
 
+    const headers: Record<string, string> = { "Content-Type": "application/json" };
+    // A API recusa edições sem a versão lida (controle de concorrência)
+    if (product?.version !== undefined) headers["If-Match"] = `"${product.version}"`;
+
     const res = await fetch(url, {
       method,
-      headers: { "Content-Type": "application/json" },
+      headers,
       body: JSON.stringify(formData),
     });
 
     if (res.ok) {
       router.push("/products");
       router.refresh();
+    } else if (res.status === 412) {
+      alert("Este produto foi alterado por outra pessoa. Recarregue a página e tente novamente.");
     } else {
       alert("Erro ao salvar produto");
     }
@@ -10,11 +10,24 @@ interface Product {
   price: number;
 }
 
+interface Client {
+  id: string;
+  name: string;
+}
+
+interface Account {
+  id: string;
+  name: string;
+}
+
 interface SaleFormProps {
   sale?: {
     id: string;
     product_id: string;
+    client_id?: string | null;
+    account_id?: string;
     quantity: number;
+    version?: number;
   };
 }
 
@@ -23,15 +36,31 @@ export default function SaleForm({ sale }: SaleFormProps) {
   const isEdit = !!sale;
 
   const [products, setProducts] = useState<Product[]>([]);
+  const [clients, setClients] = useState<Client[]>([]);
   const [productId, setProductId] = useState(sale?.product_id || '');
+  const [accounts, setAccounts] = useState<Account[]>([]);
+  const [clientId, setClientId] = useState(sale?.client_id || '');
+  const [accountId, setAccountId] = useState(sale?.account_id || '');
   const [quantity, setQuantity] = useState(sale?.quantity || 1);
   const [loading, setLoading] = useState(false);
+  // Mesma chave em reenvios do formulário: a API devolve a venda já criada
+  const [idempotencyKey, setIdempotencyKey] = useState(() => crypto.randomUUID());
 
   useEffect(() => {
     fetch('http://localhost:8080/api/products')
       .then(res => res.json())
       .then(data => setProducts(data))
       .catch(err => console.error('Erro ao carregar produtos:', err));
+
+    fetch('http://localhost:8080/api/clients')
+      .then(res => res.json())
+      .then(data => setClients(data.clients ?? []))
+      .catch(err => console.error('Erro ao carregar clientes:', err));
+
+    fetch('http://localhost:8080/api/accounts')
+      .then(res => res.json())
+      .then(data => setAccounts(data))
+      .catch(err => console.error('Erro ao carregar contas:', err));
   }, []);
 
   async function handleSubmit(e: React.FormEvent) {
@@ -43,11 +72,16 @@ export default function SaleForm({ sale }: SaleFormProps) {
       : 'http://localhost:8080/api/sales';
 
     const method = isEdit ? 'PATCH' : 'POST';
-    const body = { product_id: productId, quantity };
+    const body = { product_id: productId, quantity, client_id: clientId || null, account_id: accountId };
+
+    const headers: Record<string, string> = { 'Content-Type': 'application/json' };
+    // A API recusa edições sem a versão lida (controle de concorrência)
+    if (sale?.version !== undefined) headers['If-Match'] = `"${sale.version}"`;
+    if (!isEdit) headers['Idempotency-Key'] = idempotencyKey;
 
     const res = await fetch(url, {
       method,
-      headers: { 'Content-Type': 'application/json' },
+      headers,
       body: JSON.stringify(body),
     });
 
@@ -55,7 +89,11 @@ export default function SaleForm({ sale }: SaleFormProps) {
 
     if (res.ok) {
       router.push('/sales');
+    } else if (res.status === 412) {
+      alert('Esta venda foi alterada por outra pessoa. Recarregue a página e tente novamente.');
     } else {
+      // Requisição recusada: o próximo envio (corrigido) precisa de outra chave
+      setIdempotencyKey(crypto.randomUUID());
       alert('Erro ao salvar venda');
     }
   }
@@ -82,6 +120,39 @@ export default function SaleForm({ sale }: SaleFormProps) {
         </select>
       </div>
 
+      <div>
+        <label className="block text-gray-700 dark:text-gray-300 mb-1">Cliente (opcional)</label>
+        <select
+          value={clientId}
+          onChange={e => setClientId(e.target.value)}
+          className="w-full border rounded px-3 py-2 dark:bg-gray-900 dark:text-white"
+        >
+          <option value="">Consumidor não identificado</option>
+          {clients.map(client => (
+            <option key={client.id} value={client.id}>
+              {client.name}
+            </option>
+          ))}
+        </select>
+      </div>
+
+      <div>
+        <label className="block text-gray-700 dark:text-gray-300 mb-1">Conta de recebimento</label>
+        <select
+          value={accountId}
+          onChange={e => setAccountId(e.target.value)}
+          required
+          className="w-full border rounded px-3 py-2 dark:bg-gray-900 dark:text-white"
+        >
+          <option value="">Selecione uma conta</option>
+          {accounts.map(account => (
+            <option key={account.id} value={account.id}>
+              {account.name}
+            </option>
+          ))}
+        </select>
+      </div>
+
       <div>
         <label className="block text-gray-700 dark:text-gray-300 mb-1">Quantidade</label>
         <input